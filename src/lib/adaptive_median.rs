@@ -0,0 +1,397 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::rank_filter::{
+    rank_filter_dispatch, rank_filter_work, window_histogram_pixel, RankHistogram, RankValue,
+};
+use crate::threading_policy::type_work_per_pixel;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{RankEdgeMode, ThreadingPolicy};
+
+/// Adds or removes square ring at distance `ring` from `x`, `y`, ring 0 is the center pixel
+#[inline(always)]
+fn adaptive_median_ring<T: RankValue, const CHANNELS_CONFIGURATION: usize, const ADD: bool>(
+    src: &[T],
+    src_stride: u32,
+    histogram: &mut RankHistogram<T, CHANNELS_CONFIGURATION>,
+    x: i64,
+    y: i64,
+    ring: i64,
+    width: u32,
+    height: u32,
+    edge_mode: RankEdgeMode,
+) {
+    for dx in -ring..=ring {
+        window_histogram_pixel::<
+            T,
+            RankHistogram<T, CHANNELS_CONFIGURATION>,
+            CHANNELS_CONFIGURATION,
+            ADD,
+        >(
+            src,
+            src_stride,
+            histogram,
+            x + dx,
+            y - ring,
+            width,
+            height,
+            edge_mode,
+        );
+        if ring > 0 {
+            window_histogram_pixel::<
+                T,
+                RankHistogram<T, CHANNELS_CONFIGURATION>,
+                CHANNELS_CONFIGURATION,
+                ADD,
+            >(
+                src,
+                src_stride,
+                histogram,
+                x + dx,
+                y + ring,
+                width,
+                height,
+                edge_mode,
+            );
+        }
+    }
+    for dy in -ring + 1..ring {
+        window_histogram_pixel::<
+            T,
+            RankHistogram<T, CHANNELS_CONFIGURATION>,
+            CHANNELS_CONFIGURATION,
+            ADD,
+        >(
+            src,
+            src_stride,
+            histogram,
+            x - ring,
+            y + dy,
+            width,
+            height,
+            edge_mode,
+        );
+        window_histogram_pixel::<
+            T,
+            RankHistogram<T, CHANNELS_CONFIGURATION>,
+            CHANNELS_CONFIGURATION,
+            ADD,
+        >(
+            src,
+            src_stride,
+            histogram,
+            x + ring,
+            y + dy,
+            width,
+            height,
+            edge_mode,
+        );
+    }
+}
+
+/// Adaptive median filter
+///
+/// H. Hwang, R. A. Haddad, Adaptive median filters: new algorithms and results, 1995.
+/// Window grows while the median is an impulse (equals to minimum or maximum of the window),
+/// then pixel is replaced with the median only if pixel itself is an impulse.
+/// Each channel is processed independently.
+fn adaptive_median_impl<T: RankValue, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    max_radius: u32,
+    edge_mode: RankEdgeMode,
+    start_y: u32,
+    end_y: u32,
+) {
+    let max_radius = max_radius.max(1) as i64;
+    let mut histogram = RankHistogram::<T, CHANNELS_CONFIGURATION>::new();
+    for y in start_y..end_y {
+        let y_wide = y as i64;
+        let y_src_offset = y as usize * src_stride as usize;
+        let y_dst_offset = y as usize * dst_stride as usize;
+        for x in 0..width {
+            let x_wide = x as i64;
+            let px = x as usize * CHANNELS_CONFIGURATION;
+            let mut resolved = [false; 4];
+            let mut output = [T::from_bin(0); 4];
+            let mut unresolved = CHANNELS_CONFIGURATION;
+
+            adaptive_median_ring::<T, CHANNELS_CONFIGURATION, true>(
+                src,
+                src_stride,
+                &mut histogram,
+                x_wide,
+                y_wide,
+                0,
+                width,
+                height,
+                edge_mode,
+            );
+            let mut radius = 0i64;
+            while unresolved > 0 && radius < max_radius {
+                radius += 1;
+                adaptive_median_ring::<T, CHANNELS_CONFIGURATION, true>(
+                    src,
+                    src_stride,
+                    &mut histogram,
+                    x_wide,
+                    y_wide,
+                    radius,
+                    width,
+                    height,
+                    edge_mode,
+                );
+                let window = ((2 * radius + 1) * (2 * radius + 1)) as u32;
+                for c in 0..CHANNELS_CONFIGURATION {
+                    if resolved[c] {
+                        continue;
+                    }
+                    let min = histogram.rank(c, window, 0f64, edge_mode).bin();
+                    let median = histogram.rank(c, window, 0.5f64, edge_mode);
+                    let max = histogram.rank(c, window, 1f64, edge_mode).bin();
+                    let median_bin = median.bin();
+                    if (min < median_bin && median_bin < max) || radius == max_radius {
+                        let center = unsafe { *src.get_unchecked(y_src_offset + px + c) };
+                        let center_bin = center.bin();
+                        output[c] = if min < center_bin && center_bin < max {
+                            center
+                        } else {
+                            median
+                        };
+                        resolved[c] = true;
+                        unresolved -= 1;
+                    }
+                }
+            }
+
+            for ring in 0..=radius {
+                adaptive_median_ring::<T, CHANNELS_CONFIGURATION, false>(
+                    src,
+                    src_stride,
+                    &mut histogram,
+                    x_wide,
+                    y_wide,
+                    ring,
+                    width,
+                    height,
+                    edge_mode,
+                );
+            }
+
+            for (c, &v) in output.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+                unsafe {
+                    unsafe_dst.write(y_dst_offset + px + c, v);
+                }
+            }
+        }
+    }
+}
+
+/// Performs adaptive median filter on the image.
+///
+/// Removes salt-and-pepper noise, window grows up to `max_radius` until the median is not an impulse,
+/// and only pixels detected as impulses are replaced, so clean regions keep their details.
+/// O(R^2) complexity in the worst case, usually close to O(1) on a sparse noise.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `max_radius` - Maximum radius of the window, at least 1
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, see *RankEdgeMode*, *KernelClip* ignores them and *Constant* treats them as zero
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn adaptive_median_blur(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    max_radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: RankEdgeMode,
+) {
+    let unsafe_dst = UnsafeSlice::new(dst);
+    rank_filter_dispatch(
+        width,
+        height,
+        threading_policy,
+        rank_filter_work(max_radius) * type_work_per_pixel::<u8>(),
+        |start_y, end_y| match channels {
+            FastBlurChannels::Channels3 => {
+                adaptive_median_impl::<u8, 3>(
+                    src,
+                    src_stride,
+                    &unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    max_radius,
+                    edge_mode,
+                    start_y,
+                    end_y,
+                );
+            }
+            FastBlurChannels::Channels4 => {
+                adaptive_median_impl::<u8, 4>(
+                    src,
+                    src_stride,
+                    &unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    max_radius,
+                    edge_mode,
+                    start_y,
+                    end_y,
+                );
+            }
+        },
+    );
+}
+
+/// Performs adaptive median filter on the single plane image, gray, scanned document etc.
+///
+/// Removes salt-and-pepper noise, window grows up to `max_radius` until the median is not an impulse,
+/// and only pixels detected as impulses are replaced, so clean regions keep their details.
+/// O(R^2) complexity in the worst case, usually close to O(1) on a sparse noise.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width if not aligned
+/// * `dst_stride` - Lane length, default is width if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `max_radius` - Maximum radius of the window, at least 1
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, see *RankEdgeMode*, *KernelClip* ignores them and *Constant* treats them as zero
+///
+/// # Panics
+/// Panic is stride/width/height do not match provided
+pub fn adaptive_median_blur_plane(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    max_radius: u32,
+    threading_policy: ThreadingPolicy,
+    edge_mode: RankEdgeMode,
+) {
+    let unsafe_dst = UnsafeSlice::new(dst);
+    rank_filter_dispatch(
+        width,
+        height,
+        threading_policy,
+        rank_filter_work(max_radius) * type_work_per_pixel::<u8>(),
+        |start_y, end_y| {
+            adaptive_median_impl::<u8, 1>(
+                src,
+                src_stride,
+                &unsafe_dst,
+                dst_stride,
+                width,
+                height,
+                max_radius,
+                edge_mode,
+                start_y,
+                end_y,
+            );
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn salt_and_pepper_is_removed_and_clean_pixels_kept() {
+        let width = 40usize;
+        let height = 30usize;
+        let clean: Vec<u8> = (0..width * height)
+            .map(|i| 60 + ((i % width) * 2 + (i / width)) as u8)
+            .collect();
+        let mut noisy = clean.clone();
+        for i in (0..noisy.len()).step_by(13) {
+            noisy[i] = if i % 2 == 0 { 255 } else { 0 };
+        }
+        let mut dst = vec![0u8; noisy.len()];
+        adaptive_median_blur_plane(
+            &noisy,
+            width as u32,
+            &mut dst,
+            width as u32,
+            width as u32,
+            height as u32,
+            3,
+            ThreadingPolicy::Single,
+            RankEdgeMode::Reflect,
+        );
+        let mut changed = 0usize;
+        for (i, (&d, &c)) in dst.iter().zip(clean.iter()).enumerate() {
+            assert!((d as i32 - c as i32).abs() <= 3, "pixel {} is {}", i, d);
+            if noisy[i] == c && d != c {
+                changed += 1;
+            }
+        }
+        // Clean pixel equal to the window extremum is treated as an impulse too
+        assert!(changed * 20 < dst.len(), "{} clean pixels changed", changed);
+    }
+
+    #[test]
+    fn constant_image_stays_constant() {
+        let src = vec![77u8; 15 * 8 * 4];
+        let mut dst = vec![0u8; src.len()];
+        adaptive_median_blur(
+            &src,
+            15 * 4,
+            &mut dst,
+            15 * 4,
+            15,
+            8,
+            2,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Single,
+            RankEdgeMode::Clamp,
+        );
+        assert_eq!(dst, src);
+    }
+}
@@ -0,0 +1,703 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::threading_policy::type_work_per_pixel;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::ThreadingPolicy;
+use alloc::vec;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Declares conductance function of the anisotropic diffusion
+pub enum DiffusionConductance {
+    /// `exp(-(gradient / kappa)^2)`, privileges high contrast edges over low contrast ones
+    #[default]
+    Exponential = 0,
+    /// `1 / (1 + (gradient / kappa)^2)`, privileges wide regions over smaller ones
+    Quadratic = 1,
+}
+
+pub(crate) trait DiffusionValue: Copy + Send + Sync + 'static {
+    fn to_f32(self) -> f32;
+    fn from_f32(value: f32) -> Self;
+}
+
+impl DiffusionValue for u16 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value.round().clamp(0f32, u16::MAX as f32) as u16
+    }
+}
+
+impl DiffusionValue for f32 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+#[inline(always)]
+fn conductance_flux<const CONDUCTANCE: usize>(gradient: f32, inverse_kappa: f32) -> f32 {
+    let scaled = gradient * inverse_kappa;
+    let squared = scaled * scaled;
+    let conductance = if CONDUCTANCE == DiffusionConductance::Exponential as usize {
+        (-squared).exp()
+    } else {
+        1f32 / (1f32 + squared)
+    };
+    conductance * gradient
+}
+
+/// Explicit Perona–Malik step on 4-neighborhood for rows `start_y..end_y`,
+/// flux through the image border is zero
+fn anisotropic_diffusion_step<const CHANNELS_CONFIGURATION: usize, const CONDUCTANCE: usize>(
+    src: &[f32],
+    unsafe_dst: &UnsafeSlice<f32>,
+    width: u32,
+    height: u32,
+    inverse_kappa: f32,
+    step: f32,
+    start_y: u32,
+    end_y: u32,
+) {
+    let stride = width as usize * CHANNELS_CONFIGURATION;
+    for y in start_y as usize..end_y as usize {
+        let row = &src[y * stride..(y + 1) * stride];
+        let top = if y > 0 {
+            &src[(y - 1) * stride..y * stride]
+        } else {
+            row
+        };
+        let bottom = if y + 1 < height as usize {
+            &src[(y + 1) * stride..(y + 2) * stride]
+        } else {
+            row
+        };
+        for x in 0..width as usize {
+            let px = x * CHANNELS_CONFIGURATION;
+            let left = if x > 0 {
+                px - CHANNELS_CONFIGURATION
+            } else {
+                px
+            };
+            let right = if x + 1 < width as usize {
+                px + CHANNELS_CONFIGURATION
+            } else {
+                px
+            };
+            for c in 0..CHANNELS_CONFIGURATION {
+                let center = row[px + c];
+                let flux = conductance_flux::<CONDUCTANCE>(top[px + c] - center, inverse_kappa)
+                    + conductance_flux::<CONDUCTANCE>(bottom[px + c] - center, inverse_kappa)
+                    + conductance_flux::<CONDUCTANCE>(row[left + c] - center, inverse_kappa)
+                    + conductance_flux::<CONDUCTANCE>(row[right + c] - center, inverse_kappa);
+                unsafe {
+                    unsafe_dst.write(y * stride + px + c, center + step * flux);
+                }
+            }
+        }
+    }
+}
+
+fn anisotropic_diffusion_impl<
+    T: DiffusionValue,
+    const CHANNELS_CONFIGURATION: usize,
+    const CONDUCTANCE: usize,
+>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    iterations: u32,
+    kappa: f32,
+    step: f32,
+    threading_policy: ThreadingPolicy,
+) {
+    let row_length = width as usize * CHANNELS_CONFIGURATION;
+    assert!(
+        src_stride as usize >= row_length && dst_stride as usize >= row_length,
+        "Stride must be at least width * channels"
+    );
+    assert!(
+        src.len() >= src_stride as usize * height as usize,
+        "Source buffer is smaller than stride * height"
+    );
+    assert!(
+        dst.len() >= dst_stride as usize * height as usize,
+        "Destination buffer is smaller than stride * height"
+    );
+    if width == 0 || height == 0 {
+        return;
+    }
+    let mut current = vec![0f32; row_length * height as usize];
+    for (dst, src) in current
+        .chunks_exact_mut(row_length)
+        .zip(src.chunks(src_stride as usize))
+    {
+        for (dst, &src) in dst.iter_mut().zip(src[..row_length].iter()) {
+            *dst = src.to_f32();
+        }
+    }
+    let mut next = vec![0f32; current.len()];
+
+    let inverse_kappa = 1f32 / kappa.max(f32::MIN_POSITIVE);
+    let step = step.clamp(0f32, 0.25f32);
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        iterations as f32 * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    for _ in 0..iterations {
+        let source = &current;
+        let unsafe_dst = UnsafeSlice::new(&mut next);
+        pool.scope(|scope| {
+            let segment_size = height / thread_count;
+            for i in 0..thread_count {
+                let start_y = i * segment_size;
+                let mut end_y = (i + 1) * segment_size;
+                if i == thread_count - 1 {
+                    end_y = height;
+                }
+                let unsafe_dst = &unsafe_dst;
+                scope.spawn(move |_| {
+                    anisotropic_diffusion_step::<CHANNELS_CONFIGURATION, CONDUCTANCE>(
+                        source,
+                        unsafe_dst,
+                        width,
+                        height,
+                        inverse_kappa,
+                        step,
+                        start_y,
+                        end_y,
+                    );
+                });
+            }
+        });
+        core::mem::swap(&mut current, &mut next);
+    }
+
+    for (dst, src) in dst
+        .chunks_mut(dst_stride as usize)
+        .zip(current.chunks_exact(row_length))
+    {
+        for (dst, &src) in dst[..row_length].iter_mut().zip(src.iter()) {
+            *dst = T::from_f32(src);
+        }
+    }
+}
+
+fn anisotropic_diffusion_dispatch<T: DiffusionValue, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    iterations: u32,
+    kappa: f32,
+    step: f32,
+    conductance: DiffusionConductance,
+    threading_policy: ThreadingPolicy,
+) {
+    let executor = match conductance {
+        DiffusionConductance::Exponential => {
+            anisotropic_diffusion_impl::<
+                T,
+                CHANNELS_CONFIGURATION,
+                { DiffusionConductance::Exponential as usize },
+            >
+        }
+        DiffusionConductance::Quadratic => {
+            anisotropic_diffusion_impl::<
+                T,
+                CHANNELS_CONFIGURATION,
+                { DiffusionConductance::Quadratic as usize },
+            >
+        }
+    };
+    executor(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        iterations,
+        kappa,
+        step,
+        threading_policy,
+    );
+}
+
+/// Performs Perona–Malik anisotropic diffusion on the u16 image.
+///
+/// Iteratively smooths the image while conductance function stops diffusion across edges,
+/// gradients much larger than kappa are preserved. Each channel diffuses independently.
+/// O(N) complexity for each iteration.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `iterations` - Count of diffusion iterations
+/// * `kappa` - Gradient threshold in levels 0..65535, gradients below are smoothed, above are preserved
+/// * `step` - Integration step, diffusion is stable for 0..0.25, larger values are clamped
+/// * `conductance` - Conductance function, see [DiffusionConductance] for more info
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn anisotropic_diffusion_u16(
+    src: &[u16],
+    src_stride: u32,
+    dst: &mut [u16],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    iterations: u32,
+    kappa: f32,
+    step: f32,
+    conductance: DiffusionConductance,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    let dispatcher = match channels {
+        FastBlurChannels::Channels3 => anisotropic_diffusion_dispatch::<u16, 3>,
+        FastBlurChannels::Channels4 => anisotropic_diffusion_dispatch::<u16, 4>,
+    };
+    dispatcher(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        iterations,
+        kappa,
+        step,
+        conductance,
+        threading_policy,
+    );
+}
+
+/// Performs Perona–Malik anisotropic diffusion on the u16 single plane image, gray etc.
+///
+/// Iteratively smooths the image while conductance function stops diffusion across edges,
+/// gradients much larger than kappa are preserved.
+/// O(N) complexity for each iteration.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width if not aligned
+/// * `dst_stride` - Lane length, default is width if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `iterations` - Count of diffusion iterations
+/// * `kappa` - Gradient threshold in levels 0..65535, gradients below are smoothed, above are preserved
+/// * `step` - Integration step, diffusion is stable for 0..0.25, larger values are clamped
+/// * `conductance` - Conductance function, see [DiffusionConductance] for more info
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is stride/width/height do not match provided
+pub fn anisotropic_diffusion_plane_u16(
+    src: &[u16],
+    src_stride: u32,
+    dst: &mut [u16],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    iterations: u32,
+    kappa: f32,
+    step: f32,
+    conductance: DiffusionConductance,
+    threading_policy: ThreadingPolicy,
+) {
+    anisotropic_diffusion_dispatch::<u16, 1>(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        iterations,
+        kappa,
+        step,
+        conductance,
+        threading_policy,
+    );
+}
+
+/// Performs Perona–Malik anisotropic diffusion on the f32 image.
+///
+/// Iteratively smooths the image while conductance function stops diffusion across edges,
+/// gradients much larger than kappa are preserved. Each channel diffuses independently.
+/// O(N) complexity for each iteration.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `iterations` - Count of diffusion iterations
+/// * `kappa` - Gradient threshold in the units of the image, gradients below are smoothed, above are preserved
+/// * `step` - Integration step, diffusion is stable for 0..0.25, larger values are clamped
+/// * `conductance` - Conductance function, see [DiffusionConductance] for more info
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is width/height/channel configuration do not match provided
+pub fn anisotropic_diffusion_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    iterations: u32,
+    kappa: f32,
+    step: f32,
+    conductance: DiffusionConductance,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    let stride = width * channels.get_channels() as u32;
+    let dispatcher = match channels {
+        FastBlurChannels::Channels3 => anisotropic_diffusion_dispatch::<f32, 3>,
+        FastBlurChannels::Channels4 => anisotropic_diffusion_dispatch::<f32, 4>,
+    };
+    dispatcher(
+        src,
+        stride,
+        dst,
+        stride,
+        width,
+        height,
+        iterations,
+        kappa,
+        step,
+        conductance,
+        threading_policy,
+    );
+}
+
+/// Performs Perona–Malik anisotropic diffusion on the f32 single plane image, gray etc.
+///
+/// Iteratively smooths the image while conductance function stops diffusion across edges,
+/// gradients much larger than kappa are preserved.
+/// O(N) complexity for each iteration.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `iterations` - Count of diffusion iterations
+/// * `kappa` - Gradient threshold in the units of the image, gradients below are smoothed, above are preserved
+/// * `step` - Integration step, diffusion is stable for 0..0.25, larger values are clamped
+/// * `conductance` - Conductance function, see [DiffusionConductance] for more info
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is width/height do not match provided
+pub fn anisotropic_diffusion_plane_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    iterations: u32,
+    kappa: f32,
+    step: f32,
+    conductance: DiffusionConductance,
+    threading_policy: ThreadingPolicy,
+) {
+    anisotropic_diffusion_dispatch::<f32, 1>(
+        src,
+        width,
+        dst,
+        width,
+        width,
+        height,
+        iterations,
+        kappa,
+        step,
+        conductance,
+        threading_policy,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn reference(
+        src: &[f32],
+        width: usize,
+        height: usize,
+        iterations: u32,
+        kappa: f32,
+        step: f32,
+        conductance: DiffusionConductance,
+    ) -> Vec<f32> {
+        let channels = src.len() / (width * height);
+        let g = |d: f32| -> f32 {
+            let s = (d / kappa) * (d / kappa);
+            match conductance {
+                DiffusionConductance::Exponential => (-s).exp() * d,
+                DiffusionConductance::Quadratic => d / (1f32 + s),
+            }
+        };
+        let mut current = src.to_vec();
+        for _ in 0..iterations {
+            let mut next = current.clone();
+            for y in 0..height {
+                for x in 0..width {
+                    for c in 0..channels {
+                        let at = |x: usize, y: usize| current[(y * width + x) * channels + c];
+                        let center = at(x, y);
+                        let neighbors = [
+                            at(x, y.saturating_sub(1)),
+                            at(x, (y + 1).min(height - 1)),
+                            at(x.saturating_sub(1), y),
+                            at((x + 1).min(width - 1), y),
+                        ];
+                        let flux = neighbors.iter().map(|&n| g(n - center)).sum::<f32>();
+                        next[(y * width + x) * channels + c] = center + step * flux;
+                    }
+                }
+            }
+            current = next;
+        }
+        current
+    }
+
+    fn test_image(width: usize, height: usize, channels: usize) -> Vec<f32> {
+        (0..width * height * channels)
+            .map(|i| {
+                let x = (i / channels) % width;
+                let base = if x < width / 2 { 0.2f32 } else { 0.8f32 };
+                base + ((i * 7919 + 3) % 23) as f32 * 0.002f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn f32_matches_reference() {
+        let (width, height) = (17usize, 13usize);
+        for conductance in [
+            DiffusionConductance::Exponential,
+            DiffusionConductance::Quadratic,
+        ] {
+            for (channels, cn) in [
+                (None, 1usize),
+                (Some(FastBlurChannels::Channels3), 3),
+                (Some(FastBlurChannels::Channels4), 4),
+            ] {
+                let src = test_image(width, height, cn);
+                let expected = reference(&src, width, height, 7, 0.05f32, 0.2f32, conductance);
+                for threading_policy in [ThreadingPolicy::Single, ThreadingPolicy::Fixed(4)] {
+                    let mut dst = vec![0f32; src.len()];
+                    match channels {
+                        None => anisotropic_diffusion_plane_f32(
+                            &src,
+                            &mut dst,
+                            width as u32,
+                            height as u32,
+                            7,
+                            0.05f32,
+                            0.2f32,
+                            conductance,
+                            threading_policy,
+                        ),
+                        Some(channels) => anisotropic_diffusion_f32(
+                            &src,
+                            &mut dst,
+                            width as u32,
+                            height as u32,
+                            7,
+                            0.05f32,
+                            0.2f32,
+                            conductance,
+                            channels,
+                            threading_policy,
+                        ),
+                    }
+                    for (i, (&d, &e)) in dst.iter().zip(expected.iter()).enumerate() {
+                        assert!(
+                            (d - e).abs() < 1e-5,
+                            "{:?} at {}: {} vs {}",
+                            conductance,
+                            i,
+                            d,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn diffusion_conserves_mass_and_keeps_edge() {
+        let (width, height) = (20usize, 10usize);
+        let src = test_image(width, height, 1);
+        let mut dst = vec![0f32; src.len()];
+        anisotropic_diffusion_plane_f32(
+            &src,
+            &mut dst,
+            width as u32,
+            height as u32,
+            50,
+            0.05f32,
+            0.25f32,
+            DiffusionConductance::Exponential,
+            ThreadingPolicy::Adaptive,
+        );
+        let mass_src = src.iter().map(|&v| v as f64).sum::<f64>();
+        let mass_dst = dst.iter().map(|&v| v as f64).sum::<f64>();
+        assert!((mass_src - mass_dst).abs() < 1e-3);
+        for (i, &v) in dst.iter().enumerate() {
+            if i % width < width / 2 {
+                assert!(v < 0.3f32, "at {}: {}", i, v);
+            } else {
+                assert!(v > 0.7f32, "at {}: {}", i, v);
+            }
+        }
+    }
+
+    #[test]
+    fn u16_matches_reference() {
+        let (width, height) = (15usize, 9usize);
+        let src = test_image(width, height, 3)
+            .iter()
+            .map(|&v| (v * 65535f32).round() as u16)
+            .collect::<Vec<_>>();
+        let mut dst = vec![0u16; src.len()];
+        anisotropic_diffusion_u16(
+            &src,
+            (width * 3) as u32,
+            &mut dst,
+            (width * 3) as u32,
+            width as u32,
+            height as u32,
+            5,
+            3000f32,
+            0.25f32,
+            DiffusionConductance::Quadratic,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Adaptive,
+        );
+        let expected = reference(
+            &src.iter().map(|&v| v as f32).collect::<Vec<_>>(),
+            width,
+            height,
+            5,
+            3000f32,
+            0.25f32,
+            DiffusionConductance::Quadratic,
+        );
+        for (&d, &e) in dst.iter().zip(expected.iter()) {
+            assert!((d as f32 - e).abs() <= 1f32, "{} vs {}", d, e);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_source_panics() {
+        let src = vec![0f32; 10 * 10 * 3 - 1];
+        let mut dst = vec![0f32; 10 * 10 * 3];
+        anisotropic_diffusion_f32(
+            &src,
+            &mut dst,
+            10,
+            10,
+            2,
+            0.1f32,
+            0.25f32,
+            DiffusionConductance::Exponential,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_destination_panics() {
+        let src = vec![0u16; 10 * 10];
+        let mut dst = vec![0u16; 10 * 10 - 1];
+        anisotropic_diffusion_plane_u16(
+            &src,
+            10,
+            &mut dst,
+            10,
+            10,
+            10,
+            2,
+            3000f32,
+            0.25f32,
+            DiffusionConductance::Quadratic,
+            ThreadingPolicy::Single,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn small_stride_panics() {
+        let src = vec![0u16; 10 * 10 * 3];
+        let mut dst = vec![0u16; 10 * 10 * 3];
+        anisotropic_diffusion_u16(
+            &src,
+            29,
+            &mut dst,
+            30,
+            10,
+            10,
+            2,
+            3000f32,
+            0.25f32,
+            DiffusionConductance::Quadratic,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+        );
+    }
+}
@@ -0,0 +1,415 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::rank_filter::rank_filter_work;
+use crate::{
+    box_blur, fast_gaussian, fast_gaussian_next, fast_gaussian_superior, gaussian_blur,
+    gaussian_box_blur, median_blur, stack_blur, tent_blur, EdgeMode, RankWindow, ThreadingPolicy,
+};
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Declares blur algorithm to be selected at runtime
+pub enum BlurAlgorithm {
+    /// *stack_blur*, only *EdgeMode::Clamp* is supported
+    Stack = 0,
+    /// *fast_gaussian*, *EdgeMode::KernelClip* is not supported
+    #[default]
+    FastGaussian = 1,
+    /// *fast_gaussian_next*, *EdgeMode::KernelClip* is not supported
+    FastGaussianNext = 2,
+    /// *fast_gaussian_superior*, only *EdgeMode::Clamp* is supported
+    FastGaussianSuperior = 3,
+    /// *gaussian_blur* with kernel size `2 * radius + 1` and default sigma
+    Gaussian = 4,
+    /// *box_blur*, only *EdgeMode::Clamp* is supported
+    Box = 5,
+    /// *tent_blur*, only *EdgeMode::Clamp* is supported
+    Tent = 6,
+    /// *gaussian_box_blur*, only *EdgeMode::Clamp* is supported
+    GaussianBox = 7,
+    /// *median_blur* with square window
+    Median = 8,
+}
+
+impl BlurAlgorithm {
+    /// Returns true if the algorithm handles the edge mode, others make blur functions panic
+    pub fn supports_edge_mode(&self, edge_mode: EdgeMode) -> bool {
+        match self {
+            BlurAlgorithm::Stack
+            | BlurAlgorithm::FastGaussianSuperior
+            | BlurAlgorithm::Box
+            | BlurAlgorithm::Tent
+            | BlurAlgorithm::GaussianBox => edge_mode == EdgeMode::Clamp,
+            BlurAlgorithm::FastGaussian | BlurAlgorithm::FastGaussianNext => {
+                edge_mode != EdgeMode::KernelClip
+            }
+            BlurAlgorithm::Gaussian | BlurAlgorithm::Median => true,
+        }
+    }
+
+    /// Panics if the edge mode is not supported by the algorithm
+    pub(crate) fn check_edge_mode(&self, edge_mode: EdgeMode) {
+        if !self.supports_edge_mode(edge_mode) {
+            panic!("{:?} edge mode is not supported by {:?}", edge_mode, self);
+        }
+    }
+
+    /// Cost of the pixel relative to stack blur for *ThreadingPolicy::get_threads_count_for_work*
+    pub(crate) fn work_per_pixel(&self, radius: u32) -> f32 {
+        match self {
+            BlurAlgorithm::Gaussian => (2 * radius + 1) as f32 / 6f32,
+            BlurAlgorithm::Tent => 2f32,
+            BlurAlgorithm::GaussianBox => 3f32,
+            BlurAlgorithm::Median => rank_filter_work(radius),
+            _ => 1f32,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+/// Parameters of the image and the blur for *blur* and *BlurFilter*
+pub struct BlurParams {
+    /// Algorithm selected by *blur*, custom *BlurFilter* may ignore it
+    pub algorithm: BlurAlgorithm,
+    /// Width of the image
+    pub width: u32,
+    /// Height of the image
+    pub height: u32,
+    /// Source lane length, default is width * channels_count
+    pub src_stride: u32,
+    /// Destination lane length, default is width * channels_count
+    pub dst_stride: u32,
+    /// Radius of the blur, meaning is the same as in the function of the algorithm
+    pub radius: u32,
+    /// Count of channels in the image
+    pub channels: FastBlurChannels,
+    /// Rule to handle edges, see *BlurAlgorithm::supports_edge_mode*
+    pub edge_mode: EdgeMode,
+    /// Threading policy according to *ThreadingPolicy*
+    pub threading_policy: ThreadingPolicy,
+}
+
+impl BlurParams {
+    /// Creates parameters for not aligned image with *EdgeMode::Clamp* and *ThreadingPolicy::Adaptive*
+    pub fn new(
+        algorithm: BlurAlgorithm,
+        width: u32,
+        height: u32,
+        radius: u32,
+        channels: FastBlurChannels,
+    ) -> BlurParams {
+        let stride = width * channels.get_channels() as u32;
+        BlurParams {
+            algorithm,
+            width,
+            height,
+            src_stride: stride,
+            dst_stride: stride,
+            radius,
+            channels,
+            edge_mode: EdgeMode::Clamp,
+            threading_policy: ThreadingPolicy::Adaptive,
+        }
+    }
+}
+
+/// Blur filter with uniform source to destination semantics.
+///
+/// Implemented by *BlurAlgorithm*, implement it to plug own filter where blur is selected at runtime.
+pub trait BlurFilter {
+    /// Blurs `src` into `dst` according to the parameters
+    fn blur(&self, src: &[u8], dst: &mut [u8], params: &BlurParams);
+
+    /// Distance in pixels the blur reads around the pixel, *blur_tiled* extends each tile by it.
+    /// Default is the radius of the parameters
+    fn halo(&self, params: &BlurParams) -> u32 {
+        params.radius
+    }
+}
+
+/// In place algorithms are performed on destination with source copied into it
+pub(crate) fn copy_to_destination(src: &[u8], dst: &mut [u8], params: &BlurParams) {
+    let row_length = params.width as usize * params.channels.get_channels();
+    for (dst, src) in dst
+        .chunks_mut(params.dst_stride as usize)
+        .zip(src.chunks(params.src_stride as usize))
+        .take(params.height as usize)
+    {
+        dst[..row_length].copy_from_slice(&src[..row_length]);
+    }
+}
+
+impl BlurFilter for BlurAlgorithm {
+    fn blur(&self, src: &[u8], dst: &mut [u8], params: &BlurParams) {
+        let p = params;
+        self.check_edge_mode(p.edge_mode);
+        match self {
+            BlurAlgorithm::Stack => {
+                copy_to_destination(src, dst, p);
+                stack_blur(
+                    dst,
+                    p.dst_stride,
+                    p.width,
+                    p.height,
+                    p.radius,
+                    p.channels,
+                    p.threading_policy,
+                );
+            }
+            BlurAlgorithm::FastGaussian => {
+                copy_to_destination(src, dst, p);
+                fast_gaussian(
+                    dst,
+                    p.dst_stride,
+                    p.width,
+                    p.height,
+                    p.radius,
+                    p.channels,
+                    p.threading_policy,
+                    p.edge_mode,
+                );
+            }
+            BlurAlgorithm::FastGaussianNext => {
+                copy_to_destination(src, dst, p);
+                fast_gaussian_next(
+                    dst,
+                    p.dst_stride,
+                    p.width,
+                    p.height,
+                    p.radius,
+                    p.channels,
+                    p.threading_policy,
+                    p.edge_mode,
+                );
+            }
+            BlurAlgorithm::FastGaussianSuperior => {
+                copy_to_destination(src, dst, p);
+                fast_gaussian_superior(
+                    dst,
+                    p.dst_stride,
+                    p.width,
+                    p.height,
+                    p.radius,
+                    p.channels,
+                    p.threading_policy,
+                );
+            }
+            BlurAlgorithm::Gaussian => {
+                let kernel_size = 2 * p.radius + 1;
+                gaussian_blur(
+                    src,
+                    p.src_stride,
+                    dst,
+                    p.dst_stride,
+                    p.width,
+                    p.height,
+                    kernel_size,
+                    kernel_size as f32 / 6f32,
+                    p.channels,
+                    p.edge_mode,
+                    p.threading_policy,
+                );
+            }
+            BlurAlgorithm::Box => {
+                box_blur(
+                    src,
+                    p.src_stride,
+                    dst,
+                    p.dst_stride,
+                    p.width,
+                    p.height,
+                    p.radius,
+                    p.channels,
+                    p.threading_policy,
+                );
+            }
+            BlurAlgorithm::Tent => {
+                tent_blur(
+                    src,
+                    p.src_stride,
+                    dst,
+                    p.dst_stride,
+                    p.width,
+                    p.height,
+                    p.radius,
+                    p.channels,
+                    p.threading_policy,
+                );
+            }
+            BlurAlgorithm::GaussianBox => {
+                gaussian_box_blur(
+                    src,
+                    p.src_stride,
+                    dst,
+                    p.dst_stride,
+                    p.width,
+                    p.height,
+                    p.radius,
+                    p.channels,
+                    p.threading_policy,
+                );
+            }
+            BlurAlgorithm::Median => {
+                median_blur(
+                    src,
+                    p.src_stride,
+                    dst,
+                    p.dst_stride,
+                    p.width,
+                    p.height,
+                    p.radius,
+                    RankWindow::Square,
+                    p.channels,
+                    p.threading_policy,
+                    p.edge_mode.into(),
+                );
+            }
+        }
+    }
+
+    fn halo(&self, params: &BlurParams) -> u32 {
+        let radius = params.radius;
+        match self {
+            BlurAlgorithm::Stack => radius.clamp(2, 254),
+            BlurAlgorithm::FastGaussianNext => (3 * radius).div_ceil(2),
+            BlurAlgorithm::FastGaussianSuperior | BlurAlgorithm::Tent => 2 * radius,
+            BlurAlgorithm::GaussianBox => 3 * radius,
+            _ => radius,
+        }
+    }
+}
+
+/// Performs blur selected at runtime on the image.
+///
+/// Dispatches to the function of *BlurParams::algorithm*, all algorithms read `src` and write `dst`,
+/// in place algorithms are performed on `dst` after source is copied into it.
+///
+/// # Arguments
+///
+/// * `params` - Image and blur parameters, see [BlurParams] for more info
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+/// or edge mode is not supported by the algorithm
+pub fn blur(src: &[u8], dst: &mut [u8], params: &BlurParams) {
+    params.algorithm.blur(src, dst, params);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn blur_matches_algorithm_functions() {
+        let (width, height) = (23u32, 17u32);
+        let src = (0..(width * height * 4) as usize)
+            .map(|i| ((i * 7919 + 1) % 251) as u8)
+            .collect::<alloc::vec::Vec<_>>();
+        let params = BlurParams::new(
+            BlurAlgorithm::FastGaussian,
+            width,
+            height,
+            4,
+            FastBlurChannels::Channels4,
+        );
+        let mut dst = vec![0u8; src.len()];
+        blur(&src, &mut dst, &params);
+        let mut expected = src.clone();
+        fast_gaussian(
+            &mut expected,
+            width * 4,
+            width,
+            height,
+            4,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Adaptive,
+            EdgeMode::Clamp,
+        );
+        assert_eq!(dst, expected);
+
+        let params = BlurParams {
+            algorithm: BlurAlgorithm::Box,
+            ..params
+        };
+        blur(&src, &mut dst, &params);
+        box_blur(
+            &src,
+            width * 4,
+            &mut expected,
+            width * 4,
+            width,
+            height,
+            4,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Adaptive,
+        );
+        assert_eq!(dst, expected);
+    }
+
+    #[test]
+    fn supported_edge_modes() {
+        let edge_modes = [
+            EdgeMode::Clamp,
+            EdgeMode::KernelClip,
+            EdgeMode::Wrap,
+            EdgeMode::Reflect,
+            EdgeMode::Reflect101,
+        ];
+        for edge_mode in edge_modes {
+            for algorithm in [
+                BlurAlgorithm::Stack,
+                BlurAlgorithm::FastGaussianSuperior,
+                BlurAlgorithm::Box,
+                BlurAlgorithm::Tent,
+                BlurAlgorithm::GaussianBox,
+            ] {
+                assert_eq!(
+                    algorithm.supports_edge_mode(edge_mode),
+                    edge_mode == EdgeMode::Clamp
+                );
+            }
+            assert_eq!(
+                BlurAlgorithm::FastGaussianNext.supports_edge_mode(edge_mode),
+                edge_mode != EdgeMode::KernelClip
+            );
+            assert!(BlurAlgorithm::Gaussian.supports_edge_mode(edge_mode));
+            assert!(BlurAlgorithm::Median.supports_edge_mode(edge_mode));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsupported_edge_mode_panics() {
+        let mut params = BlurParams::new(BlurAlgorithm::Tent, 8, 8, 2, FastBlurChannels::Channels3);
+        params.edge_mode = EdgeMode::Reflect101;
+        let src = vec![0u8; 8 * 8 * 3];
+        let mut dst = vec![0u8; src.len()];
+        blur(&src, &mut dst, &params);
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+#[cfg(feature = "colorutils")]
+use crate::blur_scratch::{blur_in_linear_in_pool, blur_in_linear_scratch_size};
+use crate::blur_scratch::{blur_in_pool, blur_scratch_size};
+use crate::gaussian::{create_gaussian_kernel, GaussianKernel};
+use crate::median_blur::median_histograms_size;
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use crate::{BlurAlgorithm, BlurParams};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "colorutils")]
+use colorutils_rs::TransferFunction;
+
+/// Kernel, thread pool and count of threads shared by the plans
+struct PreparedBlur {
+    pool: BlurPool,
+    thread_count: u32,
+    gaussian_kernel: Option<GaussianKernel>,
+}
+
+impl PreparedBlur {
+    /// Prepares blur of the image which pixels are blurred in `T`
+    fn new<T>(params: &BlurParams) -> PreparedBlur {
+        params.algorithm.check_edge_mode(params.edge_mode);
+        let work_per_pixel =
+            params.algorithm.work_per_pixel(params.radius) * type_work_per_pixel::<T>();
+        let thread_count = params.threading_policy.get_threads_count_for_work(
+            params.width,
+            params.height,
+            work_per_pixel,
+        ) as u32;
+        let pool = params.threading_policy.create_pool(thread_count as usize);
+        let gaussian_kernel = match params.algorithm {
+            BlurAlgorithm::Gaussian => {
+                let kernel_size = 2 * params.radius + 1;
+                Some(create_gaussian_kernel(
+                    params.width,
+                    params.height,
+                    kernel_size,
+                    kernel_size as f32 / 6f32,
+                    params.edge_mode,
+                ))
+            }
+            _ => None,
+        };
+        PreparedBlur {
+            pool,
+            thread_count,
+            gaussian_kernel,
+        }
+    }
+}
+
+/// Blur prepared once for the image size, channels and parameters.
+///
+/// Holds gaussian kernel, median histograms, scratch buffers and thread pool, so repeated *execute*
+/// on the frames of the same size do not rebuild the kernel, allocate intermediate images or create threads.
+/// Execute allocates nothing only when the work runs on the calling thread, as with *ThreadingPolicy::Single*,
+/// other policies allocate to schedule jobs on the threads.
+/// Plans are available for u8 images, see *LinearBlurPlan* for blurring in linear colorspace
+/// and `*_with_scratch` functions as *gaussian_blur_f32_with_scratch* for u16 and f32 images.
+pub struct BlurPlan {
+    params: BlurParams,
+    prepared: PreparedBlur,
+    median_histograms: Vec<u16>,
+    scratch: Vec<u8>,
+}
+
+impl BlurPlan {
+    /// Creates the plan, builds kernel, scratch buffers and thread pool for the parameters
+    ///
+    /// # Panics
+    /// Panic if kernel size of gaussian is not supported or edge mode is not supported by the algorithm
+    pub fn new(params: BlurParams) -> BlurPlan {
+        let prepared = PreparedBlur::new::<u8>(&params);
+        let median_histograms = match params.algorithm {
+            BlurAlgorithm::Median => vec![
+                0u16;
+                median_histograms_size(params.width, params.channels)
+                    * prepared.thread_count as usize
+            ],
+            _ => Vec::new(),
+        };
+        let scratch = vec![0u8; blur_scratch_size(&params)];
+        BlurPlan {
+            params,
+            prepared,
+            median_histograms,
+            scratch,
+        }
+    }
+
+    /// Parameters the plan was created for
+    pub fn params(&self) -> &BlurParams {
+        &self.params
+    }
+
+    /// Blurs `src` into `dst` with prepared kernel, buffers and thread pool
+    ///
+    /// # Panics
+    /// Panic is stride/width/height/channel configuration do not match the plan
+    pub fn execute(&mut self, src: &[u8], dst: &mut [u8]) {
+        let median_histograms = match self.params.algorithm {
+            BlurAlgorithm::Median => Some(self.median_histograms.as_mut_slice()),
+            _ => None,
+        };
+        blur_in_pool(
+            src,
+            dst,
+            &self.params,
+            self.prepared.gaussian_kernel.as_ref(),
+            median_histograms,
+            &mut self.scratch,
+            &self.prepared.pool,
+            self.prepared.thread_count,
+        );
+    }
+}
+
+/// Blur in linear colorspace prepared once for the image size, channels, parameters and transfer function.
+///
+/// Holds gaussian kernel, linear images, stack blur stacks, scratch buffers and thread pool,
+/// so repeated *execute* on the frames of the same size work as *blur_in_linear_with_scratch*
+/// without rebuilding the kernel, allocating linear images or creating threads.
+#[cfg(feature = "colorutils")]
+pub struct LinearBlurPlan {
+    params: BlurParams,
+    transfer_function: TransferFunction,
+    prepared: PreparedBlur,
+    scratch: Vec<f32>,
+}
+
+#[cfg(feature = "colorutils")]
+impl LinearBlurPlan {
+    /// Creates the plan, builds kernel, linear images and thread pool for the parameters
+    ///
+    /// # Panics
+    /// Panic if kernel size of gaussian is not supported, edge mode is not supported by the algorithm
+    /// or algorithm is *FastGaussianSuperior* or *Median* those are not available in linear colorspace
+    pub fn new(params: BlurParams, transfer_function: TransferFunction) -> LinearBlurPlan {
+        if params.algorithm == BlurAlgorithm::FastGaussianSuperior
+            || params.algorithm == BlurAlgorithm::Median
+        {
+            panic!(
+                "{:?} is not available in linear colorspace",
+                params.algorithm
+            );
+        }
+        let prepared = PreparedBlur::new::<f32>(&params);
+        let scratch = vec![0f32; blur_in_linear_scratch_size(&params)];
+        LinearBlurPlan {
+            params,
+            transfer_function,
+            prepared,
+            scratch,
+        }
+    }
+
+    /// Parameters the plan was created for
+    pub fn params(&self) -> &BlurParams {
+        &self.params
+    }
+
+    /// Blurs `src` into `dst` in linear colorspace with prepared kernel, buffers and thread pool
+    ///
+    /// # Panics
+    /// Panic is stride/width/height/channel configuration do not match the plan
+    pub fn execute(&mut self, src: &[u8], dst: &mut [u8]) {
+        blur_in_linear_in_pool(
+            src,
+            dst,
+            &self.params,
+            self.transfer_function,
+            self.prepared.gaussian_kernel.as_ref(),
+            &mut self.scratch,
+            &self.prepared.pool,
+            self.prepared.thread_count,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blur, EdgeMode, FastBlurChannels, ThreadingPolicy};
+
+    const ALGORITHMS: [BlurAlgorithm; 9] = [
+        BlurAlgorithm::Stack,
+        BlurAlgorithm::FastGaussian,
+        BlurAlgorithm::FastGaussianNext,
+        BlurAlgorithm::FastGaussianSuperior,
+        BlurAlgorithm::Gaussian,
+        BlurAlgorithm::Box,
+        BlurAlgorithm::Tent,
+        BlurAlgorithm::GaussianBox,
+        BlurAlgorithm::Median,
+    ];
+
+    fn test_image(height: u32, stride: u32) -> Vec<u8> {
+        (0..(stride * height) as usize)
+            .map(|i| ((i * 7919 + 17) % 251) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn plan_matches_blur() {
+        let (width, height) = (37u32, 29u32);
+        for threading_policy in [ThreadingPolicy::Single, ThreadingPolicy::Fixed(3)] {
+            for algorithm in ALGORITHMS {
+                for edge_mode in [EdgeMode::Clamp, EdgeMode::KernelClip, EdgeMode::Reflect101] {
+                    if !algorithm.supports_edge_mode(edge_mode) {
+                        continue;
+                    }
+                    let mut params =
+                        BlurParams::new(algorithm, width, height, 4, FastBlurChannels::Channels3);
+                    params.src_stride = width * 3 + 2;
+                    params.edge_mode = edge_mode;
+                    params.threading_policy = threading_policy;
+                    let src = test_image(height, params.src_stride);
+                    let mut expected = vec![0u8; (params.dst_stride * height) as usize];
+                    blur(&src, &mut expected, &params);
+                    let mut plan = BlurPlan::new(params);
+                    for _ in 0..2 {
+                        let mut dst = vec![0u8; expected.len()];
+                        plan.execute(&src, &mut dst);
+                        assert_eq!(dst, expected, "{:?} {:?}", algorithm, edge_mode);
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "colorutils")]
+    #[test]
+    fn linear_plan_matches_blur_in_linear() {
+        use crate::blur_in_linear_with_scratch;
+        let (width, height) = (31u32, 23u32);
+        for algorithm in ALGORITHMS {
+            if algorithm == BlurAlgorithm::FastGaussianSuperior
+                || algorithm == BlurAlgorithm::Median
+            {
+                continue;
+            }
+            for edge_mode in [EdgeMode::Clamp, EdgeMode::KernelClip, EdgeMode::Reflect101] {
+                if !algorithm.supports_edge_mode(edge_mode) {
+                    continue;
+                }
+                let mut params =
+                    BlurParams::new(algorithm, width, height, 3, FastBlurChannels::Channels4);
+                params.edge_mode = edge_mode;
+                let src = test_image(height, params.src_stride);
+                let mut expected = vec![0u8; src.len()];
+                let mut scratch = vec![0f32; blur_in_linear_scratch_size(&params)];
+                blur_in_linear_with_scratch(
+                    &src,
+                    &mut expected,
+                    &params,
+                    TransferFunction::Srgb,
+                    &mut scratch,
+                );
+                let mut plan = LinearBlurPlan::new(params, TransferFunction::Srgb);
+                for _ in 0..2 {
+                    let mut dst = vec![0u8; src.len()];
+                    plan.execute(&src, &mut dst);
+                    assert_eq!(dst, expected, "{:?} {:?}", algorithm, edge_mode);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "colorutils")]
+    #[test]
+    #[should_panic]
+    fn linear_plan_rejects_median() {
+        let params = BlurParams::new(BlurAlgorithm::Median, 8, 8, 1, FastBlurChannels::Channels3);
+        LinearBlurPlan::new(params, TransferFunction::Srgb);
+    }
+}
@@ -0,0 +1,1063 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::blur_filter::copy_to_destination;
+use crate::channels_configuration::FastBlurChannels;
+#[cfg(feature = "colorutils")]
+use crate::fast_gaussian::fast_gaussian_f32_in_pool;
+use crate::fast_gaussian::fast_gaussian_in_pool;
+#[cfg(feature = "colorutils")]
+use crate::fast_gaussian_next::fast_gaussian_next_f32_in_pool;
+use crate::fast_gaussian_next::fast_gaussian_next_in_pool;
+use crate::fast_gaussian_superior::fast_gaussian_superior_in_pool;
+use crate::gaussian::{
+    create_gaussian_kernel, fill_gaussian_kernel_1d, gaussian_blur_in_pool,
+    gaussian_blur_kernel_in_pool, GaussianKernel,
+};
+use crate::median_blur::median_blur_in_pool;
+use crate::r#box::{box_blur_in_pool, gaussian_box_blur_in_pool, tent_blur_in_pool};
+use crate::rank_filter::rank_filter_in_pool;
+use crate::stack_blur::{stack_blur_in_pool, stack_blur_stacks_size};
+#[cfg(feature = "colorutils")]
+use crate::stack_blur_f32::{stack_blur_f32_in_pool, stack_blur_f32_stacks_size};
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use crate::{BlurAlgorithm, BlurParams, EdgeMode, RankWindow};
+#[cfg(feature = "colorutils")]
+use colorutils_rs::{
+    linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
+};
+use core::mem::{align_of, size_of};
+
+/// Returns size in bytes of the scratch required by *blur_with_scratch* for the parameters
+pub fn blur_scratch_size(params: &BlurParams) -> usize {
+    let image_size = params.dst_stride as usize * params.height as usize;
+    match params.algorithm {
+        BlurAlgorithm::Gaussian => image_size + (2 * params.radius as usize + 2) * size_of::<f32>(),
+        BlurAlgorithm::Stack => {
+            let thread_count = params.threading_policy.get_threads_count_for_work(
+                params.width,
+                params.height,
+                params.algorithm.work_per_pixel(params.radius) * type_work_per_pixel::<u8>(),
+            );
+            thread_count * stack_blur_stacks_size(params.radius)
+        }
+        BlurAlgorithm::Box => image_size,
+        BlurAlgorithm::Tent => 2 * image_size,
+        BlurAlgorithm::GaussianBox => 3 * image_size,
+        BlurAlgorithm::FastGaussian | BlurAlgorithm::FastGaussianNext
+            if params.edge_mode != EdgeMode::Clamp =>
+        {
+            image_size
+        }
+        _ => 0,
+    }
+}
+
+/// Reinterprets `scratch` as `len` values of `U` starting at its first position aligned for `U`
+///
+/// # Safety
+/// Any bit pattern of the scratch must be a valid `U`
+///
+/// # Panics
+/// Panic if scratch cannot be aligned for `U` or `len` values do not fit after the alignment
+pub(crate) unsafe fn aligned_scratch<T, U>(scratch: &mut [T], len: usize) -> &mut [U] {
+    let offset = scratch.as_ptr().align_offset(align_of::<U>());
+    assert!(
+        offset <= scratch.len()
+            && (scratch.len() - offset) * size_of::<T>() >= len * size_of::<U>(),
+        "Scratch has {} elements but {} aligned values of {} bytes are required",
+        scratch.len(),
+        len,
+        size_of::<U>()
+    );
+    core::slice::from_raw_parts_mut(scratch.as_mut_ptr().add(offset) as *mut U, len)
+}
+
+/// Performs blur selected at runtime on the image with intermediate buffers taken from `scratch`.
+///
+/// Same as *blur* but gaussian kernel, stack blur stacks and intermediate images are placed into `scratch`,
+/// so nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single* or when adaptive policy selects one thread.
+/// Multithreaded policies still allocate to schedule jobs on the threads.
+/// Median and gaussian with *EdgeMode::KernelClip* still allocate their histograms and clipped kernels.
+///
+/// # Arguments
+///
+/// * `params` - Image and blur parameters, see [BlurParams] for more info
+/// * `scratch` - Buffer of at least *blur_scratch_size* bytes, its content is overwritten
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided,
+/// scratch is smaller than required or edge mode is not supported by the algorithm
+pub fn blur_with_scratch(src: &[u8], dst: &mut [u8], params: &BlurParams, scratch: &mut [u8]) {
+    params.algorithm.check_edge_mode(params.edge_mode);
+    let required = blur_scratch_size(params);
+    if scratch.len() < required {
+        panic!(
+            "Scratch has {} bytes but {} is required",
+            scratch.len(),
+            required
+        );
+    }
+    let thread_count = params.threading_policy.get_threads_count_for_work(
+        params.width,
+        params.height,
+        params.algorithm.work_per_pixel(params.radius) * type_work_per_pixel::<u8>(),
+    ) as u32;
+    let pool = params.threading_policy.create_pool(thread_count as usize);
+    blur_in_pool(src, dst, params, None, None, scratch, &pool, thread_count);
+}
+
+/// Performs blur selected at runtime in the thread pool, scratch must have *blur_scratch_size* bytes,
+/// gaussian uses prepared kernel when provided and builds it in the scratch otherwise,
+/// median uses prepared column histograms when provided and allocates them otherwise
+pub(crate) fn blur_in_pool(
+    src: &[u8],
+    dst: &mut [u8],
+    params: &BlurParams,
+    gaussian_kernel: Option<&GaussianKernel>,
+    median_histograms: Option<&mut [u16]>,
+    scratch: &mut [u8],
+    pool: &BlurPool,
+    thread_count: u32,
+) {
+    let p = params;
+    let image_size = p.dst_stride as usize * p.height as usize;
+    match p.algorithm {
+        BlurAlgorithm::Stack => {
+            copy_to_destination(src, dst, p);
+            let stacks_size = stack_blur_stacks_size(p.radius);
+            stack_blur_in_pool(
+                dst,
+                p.dst_stride,
+                p.width,
+                p.height,
+                p.radius.clamp(2, 254),
+                p.channels,
+                scratch,
+                pool,
+                thread_count.min((scratch.len() / stacks_size) as u32),
+            );
+        }
+        BlurAlgorithm::FastGaussian => {
+            copy_to_destination(src, dst, p);
+            let image_len = dst.len().min(image_size);
+            fast_gaussian_in_pool(
+                &mut dst[..image_len],
+                p.dst_stride,
+                p.width,
+                p.height,
+                p.radius,
+                p.channels,
+                p.edge_mode,
+                pool,
+                thread_count,
+                Some(scratch),
+            );
+        }
+        BlurAlgorithm::FastGaussianNext => {
+            copy_to_destination(src, dst, p);
+            let image_len = dst.len().min(image_size);
+            fast_gaussian_next_in_pool(
+                &mut dst[..image_len],
+                p.dst_stride,
+                p.width,
+                p.height,
+                p.radius,
+                p.channels,
+                p.edge_mode,
+                pool,
+                thread_count,
+                Some(scratch),
+            );
+        }
+        BlurAlgorithm::FastGaussianSuperior => {
+            copy_to_destination(src, dst, p);
+            fast_gaussian_superior_in_pool(
+                dst,
+                p.dst_stride,
+                p.width,
+                p.height,
+                p.radius,
+                p.channels,
+                pool,
+                thread_count,
+            );
+        }
+        BlurAlgorithm::Gaussian => {
+            let kernel_size = 2 * p.radius + 1;
+            let sigma = kernel_size as f32 / 6f32;
+            let (transient, kernel_scratch) = scratch.split_at_mut(image_size);
+            match (gaussian_kernel, p.edge_mode) {
+                (Some(kernel), _) => {
+                    let executor = match p.channels {
+                        FastBlurChannels::Channels3 => gaussian_blur_in_pool::<u8, 3>,
+                        FastBlurChannels::Channels4 => gaussian_blur_in_pool::<u8, 4>,
+                    };
+                    executor(
+                        src,
+                        p.src_stride,
+                        dst,
+                        p.dst_stride,
+                        p.width,
+                        p.height,
+                        kernel,
+                        transient,
+                        p.edge_mode,
+                        pool,
+                        thread_count,
+                    );
+                }
+                (None, EdgeMode::KernelClip) => {
+                    let kernel =
+                        create_gaussian_kernel(p.width, p.height, kernel_size, sigma, p.edge_mode);
+                    blur_in_pool(
+                        src,
+                        dst,
+                        p,
+                        Some(&kernel),
+                        median_histograms,
+                        scratch,
+                        pool,
+                        thread_count,
+                    );
+                }
+                (None, _) => {
+                    // Weights are placed after the transient image at the first f32 aligned byte
+                    let kernel: &mut [f32] =
+                        unsafe { aligned_scratch(kernel_scratch, kernel_size as usize) };
+                    fill_gaussian_kernel_1d(kernel, sigma);
+                    let executor = match p.channels {
+                        FastBlurChannels::Channels3 => gaussian_blur_kernel_in_pool::<u8, 3>,
+                        FastBlurChannels::Channels4 => gaussian_blur_kernel_in_pool::<u8, 4>,
+                    };
+                    executor(
+                        src,
+                        p.src_stride,
+                        dst,
+                        p.dst_stride,
+                        p.width,
+                        p.height,
+                        kernel,
+                        transient,
+                        p.edge_mode,
+                        pool,
+                        thread_count,
+                    );
+                }
+            }
+        }
+        BlurAlgorithm::Box => {
+            let executor = match p.channels {
+                FastBlurChannels::Channels3 => box_blur_in_pool::<u8, 3>,
+                FastBlurChannels::Channels4 => box_blur_in_pool::<u8, 4>,
+            };
+            executor(
+                src,
+                p.src_stride,
+                dst,
+                p.dst_stride,
+                p.width,
+                p.height,
+                p.radius,
+                &mut scratch[..image_size],
+                pool,
+                thread_count,
+            );
+        }
+        BlurAlgorithm::Tent => {
+            let executor = match p.channels {
+                FastBlurChannels::Channels3 => tent_blur_in_pool::<u8, 3>,
+                FastBlurChannels::Channels4 => tent_blur_in_pool::<u8, 4>,
+            };
+            executor(
+                src,
+                p.src_stride,
+                dst,
+                p.dst_stride,
+                p.width,
+                p.height,
+                p.radius,
+                &mut scratch[..2 * image_size],
+                pool,
+                thread_count,
+            );
+        }
+        BlurAlgorithm::GaussianBox => {
+            let executor = match p.channels {
+                FastBlurChannels::Channels3 => gaussian_box_blur_in_pool::<u8, 3>,
+                FastBlurChannels::Channels4 => gaussian_box_blur_in_pool::<u8, 4>,
+            };
+            executor(
+                src,
+                p.src_stride,
+                dst,
+                p.dst_stride,
+                p.width,
+                p.height,
+                [p.radius; 3],
+                &mut scratch[..3 * image_size],
+                pool,
+                thread_count,
+            );
+        }
+        BlurAlgorithm::Median => match median_histograms {
+            Some(histograms) => median_blur_in_pool(
+                src,
+                p.src_stride,
+                dst,
+                p.dst_stride,
+                p.width,
+                p.height,
+                p.radius,
+                p.channels,
+                p.edge_mode.into(),
+                histograms,
+                pool,
+                thread_count,
+            ),
+            None => rank_filter_in_pool(
+                src,
+                p.src_stride,
+                dst,
+                p.dst_stride,
+                p.width,
+                p.height,
+                p.radius,
+                50f32,
+                RankWindow::Square,
+                p.channels,
+                p.edge_mode.into(),
+                pool,
+                thread_count,
+            ),
+        },
+    }
+}
+
+/// Returns count of f32 in the scratch required by *blur_in_linear_with_scratch* for the parameters
+#[cfg(feature = "colorutils")]
+pub fn blur_in_linear_scratch_size(params: &BlurParams) -> usize {
+    let image_size =
+        params.width as usize * params.height as usize * params.channels.get_channels();
+    match params.algorithm {
+        BlurAlgorithm::Stack => {
+            let thread_count = params.threading_policy.get_threads_count_for_work(
+                params.width,
+                params.height,
+                params.algorithm.work_per_pixel(params.radius) * type_work_per_pixel::<f32>(),
+            );
+            image_size + thread_count * stack_blur_f32_stacks_size(params.radius)
+        }
+        BlurAlgorithm::FastGaussian | BlurAlgorithm::FastGaussianNext => {
+            if params.edge_mode == EdgeMode::Clamp {
+                image_size
+            } else {
+                2 * image_size
+            }
+        }
+        BlurAlgorithm::Gaussian => 3 * image_size + 2 * params.radius as usize + 1,
+        BlurAlgorithm::Box => 3 * image_size,
+        BlurAlgorithm::Tent => 4 * image_size,
+        BlurAlgorithm::GaussianBox => 5 * image_size,
+        BlurAlgorithm::FastGaussianSuperior | BlurAlgorithm::Median => 0,
+    }
+}
+
+/// Performs blur selected at runtime in linear colorspace with linear images taken from `scratch`.
+///
+/// Same as the `*_in_linear` function of the algorithm but linear images, intermediate buffers,
+/// stack blur stacks and gaussian kernel are placed into `scratch`, so nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single* or when adaptive policy selects one thread.
+/// Multithreaded policies still allocate to schedule jobs on the threads
+/// and gaussian with *EdgeMode::KernelClip* its clipped kernels.
+///
+/// # Arguments
+///
+/// * `params` - Image and blur parameters, see [BlurParams] for more info
+/// * `transfer_function` - Transfer function in linear colorspace
+/// * `scratch` - Buffer of at least *blur_in_linear_scratch_size* elements, its content is overwritten
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided,
+/// scratch is smaller than required, edge mode is not supported by the algorithm
+/// or algorithm is *FastGaussianSuperior* or *Median* those are not available in linear colorspace
+#[cfg(feature = "colorutils")]
+pub fn blur_in_linear_with_scratch(
+    src: &[u8],
+    dst: &mut [u8],
+    params: &BlurParams,
+    transfer_function: TransferFunction,
+    scratch: &mut [f32],
+) {
+    let p = params;
+    if p.algorithm == BlurAlgorithm::FastGaussianSuperior || p.algorithm == BlurAlgorithm::Median {
+        panic!("{:?} is not available in linear colorspace", p.algorithm);
+    }
+    p.algorithm.check_edge_mode(p.edge_mode);
+    let required = blur_in_linear_scratch_size(p);
+    if scratch.len() < required {
+        panic!(
+            "Scratch has {} elements but {} is required",
+            scratch.len(),
+            required
+        );
+    }
+    let thread_count = p.threading_policy.get_threads_count_for_work(
+        p.width,
+        p.height,
+        p.algorithm.work_per_pixel(p.radius) * type_work_per_pixel::<f32>(),
+    ) as u32;
+    let pool = p.threading_policy.create_pool(thread_count as usize);
+    blur_in_linear_in_pool(
+        src,
+        dst,
+        p,
+        transfer_function,
+        None,
+        scratch,
+        &pool,
+        thread_count,
+    );
+}
+
+/// Performs blur selected at runtime in linear colorspace in the thread pool,
+/// scratch must have *blur_in_linear_scratch_size* elements,
+/// gaussian uses prepared kernel when provided and builds it in the scratch otherwise
+#[cfg(feature = "colorutils")]
+pub(crate) fn blur_in_linear_in_pool(
+    src: &[u8],
+    dst: &mut [u8],
+    params: &BlurParams,
+    transfer_function: TransferFunction,
+    gaussian_kernel: Option<&GaussianKernel>,
+    scratch: &mut [f32],
+    pool: &BlurPool,
+    thread_count: u32,
+) {
+    let p = params;
+    let image_size = p.width as usize * p.height as usize * p.channels.get_channels();
+    let linear_stride = p.width * p.channels.get_channels() as u32;
+    let linear_bytes_stride = linear_stride * size_of::<f32>() as u32;
+
+    let forward_transformer = match p.channels {
+        FastBlurChannels::Channels3 => rgb_to_linear,
+        FastBlurChannels::Channels4 => rgba_to_linear,
+    };
+
+    let inverse_transformer = match p.channels {
+        FastBlurChannels::Channels3 => linear_to_rgb,
+        FastBlurChannels::Channels4 => linear_to_rgba,
+    };
+
+    let (linear_data, scratch) = scratch.split_at_mut(image_size);
+    forward_transformer(
+        src,
+        p.src_stride,
+        linear_data,
+        linear_bytes_stride,
+        p.width,
+        p.height,
+        transfer_function,
+    );
+
+    let in_place = matches!(
+        p.algorithm,
+        BlurAlgorithm::Stack | BlurAlgorithm::FastGaussian | BlurAlgorithm::FastGaussianNext
+    );
+    if in_place {
+        match p.algorithm {
+            BlurAlgorithm::Stack => {
+                let stacks_size = stack_blur_f32_stacks_size(p.radius);
+                stack_blur_f32_in_pool(
+                    linear_data,
+                    p.width,
+                    p.height,
+                    p.radius,
+                    p.channels,
+                    scratch,
+                    pool,
+                    thread_count.min((scratch.len() / stacks_size) as u32),
+                )
+            }
+            BlurAlgorithm::FastGaussian => fast_gaussian_f32_in_pool(
+                linear_data,
+                p.width,
+                p.height,
+                p.radius,
+                p.channels,
+                p.edge_mode,
+                pool,
+                thread_count,
+                Some(scratch),
+            ),
+            _ => fast_gaussian_next_f32_in_pool(
+                linear_data,
+                p.width,
+                p.height,
+                p.radius,
+                p.channels,
+                p.edge_mode,
+                pool,
+                thread_count,
+                Some(scratch),
+            ),
+        }
+        inverse_transformer(
+            linear_data,
+            linear_bytes_stride,
+            dst,
+            p.dst_stride,
+            p.width,
+            p.height,
+            transfer_function,
+        );
+        return;
+    }
+
+    let (linear_blurred, scratch) = scratch.split_at_mut(image_size);
+    match p.algorithm {
+        BlurAlgorithm::Gaussian => {
+            let kernel_size = 2 * p.radius + 1;
+            let sigma = kernel_size as f32 / 6f32;
+            let (transient, kernel_scratch) = scratch.split_at_mut(image_size);
+            let prepared;
+            let kernel = match (gaussian_kernel, p.edge_mode) {
+                (Some(kernel), _) => Some(kernel),
+                (None, EdgeMode::KernelClip) => {
+                    prepared =
+                        create_gaussian_kernel(p.width, p.height, kernel_size, sigma, p.edge_mode);
+                    Some(&prepared)
+                }
+                (None, _) => None,
+            };
+            if let Some(kernel) = kernel {
+                let executor = match p.channels {
+                    FastBlurChannels::Channels3 => gaussian_blur_in_pool::<f32, 3>,
+                    FastBlurChannels::Channels4 => gaussian_blur_in_pool::<f32, 4>,
+                };
+                executor(
+                    linear_data,
+                    linear_stride,
+                    linear_blurred,
+                    linear_stride,
+                    p.width,
+                    p.height,
+                    kernel,
+                    transient,
+                    p.edge_mode,
+                    pool,
+                    thread_count,
+                );
+            } else {
+                let kernel = &mut kernel_scratch[..kernel_size as usize];
+                fill_gaussian_kernel_1d(kernel, sigma);
+                let executor = match p.channels {
+                    FastBlurChannels::Channels3 => gaussian_blur_kernel_in_pool::<f32, 3>,
+                    FastBlurChannels::Channels4 => gaussian_blur_kernel_in_pool::<f32, 4>,
+                };
+                executor(
+                    linear_data,
+                    linear_stride,
+                    linear_blurred,
+                    linear_stride,
+                    p.width,
+                    p.height,
+                    kernel,
+                    transient,
+                    p.edge_mode,
+                    pool,
+                    thread_count,
+                );
+            }
+        }
+        BlurAlgorithm::Box => {
+            let executor = match p.channels {
+                FastBlurChannels::Channels3 => box_blur_in_pool::<f32, 3>,
+                FastBlurChannels::Channels4 => box_blur_in_pool::<f32, 4>,
+            };
+            executor(
+                linear_data,
+                linear_stride,
+                linear_blurred,
+                linear_stride,
+                p.width,
+                p.height,
+                p.radius,
+                &mut scratch[..image_size],
+                pool,
+                thread_count,
+            );
+        }
+        BlurAlgorithm::Tent => {
+            let executor = match p.channels {
+                FastBlurChannels::Channels3 => tent_blur_in_pool::<f32, 3>,
+                FastBlurChannels::Channels4 => tent_blur_in_pool::<f32, 4>,
+            };
+            executor(
+                linear_data,
+                linear_stride,
+                linear_blurred,
+                linear_stride,
+                p.width,
+                p.height,
+                p.radius,
+                &mut scratch[..2 * image_size],
+                pool,
+                thread_count,
+            );
+        }
+        _ => {
+            let executor = match p.channels {
+                FastBlurChannels::Channels3 => gaussian_box_blur_in_pool::<f32, 3>,
+                FastBlurChannels::Channels4 => gaussian_box_blur_in_pool::<f32, 4>,
+            };
+            executor(
+                linear_data,
+                linear_stride,
+                linear_blurred,
+                linear_stride,
+                p.width,
+                p.height,
+                [p.radius; 3],
+                &mut scratch[..3 * image_size],
+                pool,
+                thread_count,
+            );
+        }
+    }
+    inverse_transformer(
+        linear_blurred,
+        linear_bytes_stride,
+        dst,
+        p.dst_stride,
+        p.width,
+        p.height,
+        transfer_function,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blur, gaussian_blur_f32, gaussian_blur_f32_scratch_size, gaussian_blur_f32_with_scratch,
+        gaussian_blur_u16, gaussian_blur_u16_scratch_size, gaussian_blur_u16_with_scratch,
+        gaussian_box_blur_f32, gaussian_box_blur_f32_with_scratch, gaussian_box_blur_scratch_size,
+        gaussian_box_blur_u16, gaussian_box_blur_u16_with_scratch, stack_blur, stack_blur_f32,
+        stack_blur_f32_scratch_size, stack_blur_f32_with_scratch, stack_blur_scratch_size,
+        stack_blur_with_scratch, tent_blur_f32, tent_blur_f32_with_scratch, tent_blur_scratch_size,
+        tent_blur_u16, tent_blur_u16_with_scratch, ThreadingPolicy,
+    };
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    const POLICIES: [ThreadingPolicy; 2] = [ThreadingPolicy::Single, ThreadingPolicy::Fixed(3)];
+
+    fn test_image(len: usize) -> Vec<u8> {
+        (0..len).map(|i| ((i * 7919 + 17) % 251) as u8).collect()
+    }
+
+    fn test_image_u16(len: usize) -> Vec<u16> {
+        (0..len).map(|i| ((i * 7919 + 17) % 65521) as u16).collect()
+    }
+
+    fn test_image_f32(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| ((i * 7919 + 17) % 251) as f32 / 251f32)
+            .collect()
+    }
+
+    #[test]
+    fn aligned_scratch_starts_at_aligned_position() {
+        let mut scratch = [0u8; 64];
+        for start in 0..8 {
+            let len = (64 - start - 7) / size_of::<f64>();
+            let values: &mut [f64] = unsafe { aligned_scratch(&mut scratch[start..], len) };
+            assert_eq!(values.len(), len);
+            assert_eq!(values.as_ptr() as usize % align_of::<f64>(), 0);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn aligned_scratch_panics_when_values_do_not_fit() {
+        let mut scratch = [0u32; 4];
+        let _: &mut [f64] = unsafe { aligned_scratch(&mut scratch[1..], 2) };
+    }
+
+    #[test]
+    fn blur_with_scratch_matches_blur() {
+        let (width, height) = (37u32, 29u32);
+        for threading_policy in POLICIES {
+            for algorithm in [
+                BlurAlgorithm::Stack,
+                BlurAlgorithm::FastGaussian,
+                BlurAlgorithm::FastGaussianNext,
+                BlurAlgorithm::FastGaussianSuperior,
+                BlurAlgorithm::Gaussian,
+                BlurAlgorithm::Box,
+                BlurAlgorithm::Tent,
+                BlurAlgorithm::GaussianBox,
+                BlurAlgorithm::Median,
+            ] {
+                for edge_mode in [EdgeMode::Clamp, EdgeMode::KernelClip, EdgeMode::Reflect101] {
+                    if !algorithm.supports_edge_mode(edge_mode) {
+                        continue;
+                    }
+                    let mut params =
+                        BlurParams::new(algorithm, width, height, 4, FastBlurChannels::Channels3);
+                    params.src_stride = width * 3 + 2;
+                    params.dst_stride = width * 3 + 5;
+                    params.edge_mode = edge_mode;
+                    params.threading_policy = threading_policy;
+                    let src = test_image((params.src_stride * height) as usize);
+                    let mut expected = vec![0u8; (params.dst_stride * height) as usize];
+                    blur(&src, &mut expected, &params);
+                    let mut scratch = vec![0xA5u8; blur_scratch_size(&params)];
+                    let mut dst = vec![0u8; expected.len()];
+                    blur_with_scratch(&src, &mut dst, &params, &mut scratch);
+                    assert_eq!(dst, expected, "{:?} {:?}", algorithm, edge_mode);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn blur_with_scratch_rejects_small_scratch() {
+        let params = BlurParams::new(BlurAlgorithm::Tent, 8, 8, 2, FastBlurChannels::Channels4);
+        let src = test_image(8 * 8 * 4);
+        let mut dst = vec![0u8; src.len()];
+        let mut scratch = vec![0u8; blur_scratch_size(&params) - 1];
+        blur_with_scratch(&src, &mut dst, &params, &mut scratch);
+    }
+
+    #[cfg(feature = "colorutils")]
+    #[test]
+    fn blur_in_linear_with_scratch_matches_stack_blur_in_linear() {
+        use crate::stack_blur_in_linear;
+        let (width, height) = (33u32, 21u32);
+        for threading_policy in POLICIES {
+            let mut params = BlurParams::new(
+                BlurAlgorithm::Stack,
+                width,
+                height,
+                6,
+                FastBlurChannels::Channels4,
+            );
+            params.threading_policy = threading_policy;
+            let src = test_image((width * height * 4) as usize);
+            let mut expected = src.clone();
+            stack_blur_in_linear(
+                &mut expected,
+                width * 4,
+                width,
+                height,
+                6,
+                FastBlurChannels::Channels4,
+                threading_policy,
+                TransferFunction::Srgb,
+            );
+            let mut scratch = vec![0f32; blur_in_linear_scratch_size(&params)];
+            let mut dst = vec![0u8; src.len()];
+            blur_in_linear_with_scratch(
+                &src,
+                &mut dst,
+                &params,
+                TransferFunction::Srgb,
+                &mut scratch,
+            );
+            assert_eq!(dst, expected);
+        }
+    }
+
+    #[test]
+    fn gaussian_with_scratch_matches_gaussian() {
+        let (width, height, kernel_size) = (27u32, 19u32, 9u32);
+        for threading_policy in POLICIES {
+            for edge_mode in [EdgeMode::Clamp, EdgeMode::Reflect101, EdgeMode::KernelClip] {
+                for channels in [FastBlurChannels::Channels3, FastBlurChannels::Channels4] {
+                    let len = (width * height) as usize * channels.get_channels();
+
+                    let src = test_image_u16(len);
+                    let mut expected = vec![0u16; len];
+                    gaussian_blur_u16(
+                        &src,
+                        &mut expected,
+                        width,
+                        height,
+                        kernel_size,
+                        2f32,
+                        channels,
+                        edge_mode,
+                        threading_policy,
+                    );
+                    let mut scratch =
+                        vec![
+                            0xA5A5u16;
+                            gaussian_blur_u16_scratch_size(width, height, kernel_size, channels)
+                        ];
+                    let mut dst = vec![0u16; len];
+                    gaussian_blur_u16_with_scratch(
+                        &src,
+                        &mut dst,
+                        width,
+                        height,
+                        kernel_size,
+                        2f32,
+                        channels,
+                        edge_mode,
+                        threading_policy,
+                        &mut scratch,
+                    );
+                    assert_eq!(dst, expected, "u16 {:?}", edge_mode);
+
+                    let src = test_image_f32(len);
+                    let mut expected = vec![0f32; len];
+                    gaussian_blur_f32(
+                        &src,
+                        &mut expected,
+                        width,
+                        height,
+                        kernel_size,
+                        2f32,
+                        channels,
+                        edge_mode,
+                        threading_policy,
+                    );
+                    let mut scratch =
+                        vec![
+                            f32::NAN;
+                            gaussian_blur_f32_scratch_size(width, height, kernel_size, channels)
+                        ];
+                    let mut dst = vec![0f32; len];
+                    gaussian_blur_f32_with_scratch(
+                        &src,
+                        &mut dst,
+                        width,
+                        height,
+                        kernel_size,
+                        2f32,
+                        channels,
+                        edge_mode,
+                        threading_policy,
+                        &mut scratch,
+                    );
+                    assert_eq!(dst, expected, "f32 {:?}", edge_mode);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn box_passes_with_scratch_match_allocating() {
+        let (width, height, radius) = (29u32, 17u32, 3u32);
+        let channels = FastBlurChannels::Channels4;
+        let len = (width * height * 4) as usize;
+        for threading_policy in POLICIES {
+            let src = test_image_u16(len);
+            let mut expected = vec![0u16; len];
+            let mut dst = vec![0u16; len];
+            let mut scratch = vec![0xA5A5u16; tent_blur_scratch_size(width, height, channels)];
+            tent_blur_u16(
+                &src,
+                &mut expected,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+            );
+            tent_blur_u16_with_scratch(
+                &src,
+                &mut dst,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+                &mut scratch,
+            );
+            assert_eq!(dst, expected);
+            let mut scratch =
+                vec![0xA5A5u16; gaussian_box_blur_scratch_size(width, height, channels)];
+            gaussian_box_blur_u16(
+                &src,
+                &mut expected,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+            );
+            gaussian_box_blur_u16_with_scratch(
+                &src,
+                &mut dst,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+                &mut scratch,
+            );
+            assert_eq!(dst, expected);
+
+            let src = test_image_f32(len);
+            let mut expected = vec![0f32; len];
+            let mut dst = vec![0f32; len];
+            let mut scratch = vec![f32::NAN; tent_blur_scratch_size(width, height, channels)];
+            tent_blur_f32(
+                &src,
+                &mut expected,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+            );
+            tent_blur_f32_with_scratch(
+                &src,
+                &mut dst,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+                &mut scratch,
+            );
+            assert_eq!(dst, expected);
+            let mut scratch =
+                vec![f32::NAN; gaussian_box_blur_scratch_size(width, height, channels)];
+            gaussian_box_blur_f32(
+                &src,
+                &mut expected,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+            );
+            gaussian_box_blur_f32_with_scratch(
+                &src,
+                &mut dst,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+                &mut scratch,
+            );
+            assert_eq!(dst, expected);
+        }
+    }
+
+    #[test]
+    fn stack_blur_f32_with_scratch_matches_stack_blur_f32() {
+        let (width, height) = (41u32, 23u32);
+        let channels = FastBlurChannels::Channels3;
+        let src = test_image_f32((width * height * 3) as usize);
+        // Second radius runs on f64 accumulator with its wider stacks
+        for radius in [5u32, 400u32] {
+            for threading_policy in POLICIES {
+                let mut expected = src.clone();
+                stack_blur_f32(
+                    &mut expected,
+                    width,
+                    height,
+                    radius,
+                    channels,
+                    threading_policy,
+                );
+                let scratch_size =
+                    stack_blur_f32_scratch_size(width, height, radius, threading_policy);
+                // Full scratch and scratch fitting stacks of a single thread
+                for scratch_size in [
+                    scratch_size,
+                    stack_blur_f32_scratch_size(1, 1, radius, ThreadingPolicy::Single),
+                ] {
+                    let mut scratch = vec![f32::NAN; scratch_size];
+                    let mut dst = src.clone();
+                    stack_blur_f32_with_scratch(
+                        &mut dst,
+                        width,
+                        height,
+                        radius,
+                        channels,
+                        threading_policy,
+                        &mut scratch,
+                    );
+                    assert_eq!(dst, expected, "{} {:?}", radius, threading_policy);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stack_blur_with_scratch_matches_stack_blur() {
+        let (width, height) = (41u32, 23u32);
+        let channels = FastBlurChannels::Channels4;
+        let stride = width * 4 + 5;
+        let src = test_image((stride * height) as usize);
+        // Second radius runs on the wider accumulator
+        for radius in [5u32, 200u32] {
+            for threading_policy in POLICIES {
+                let mut expected = src.clone();
+                stack_blur(
+                    &mut expected,
+                    stride,
+                    width,
+                    height,
+                    radius,
+                    channels,
+                    threading_policy,
+                );
+                let scratch_size = stack_blur_scratch_size(width, height, radius, threading_policy);
+                // Full scratch and scratch fitting stacks of a single thread
+                for scratch_size in [
+                    scratch_size,
+                    stack_blur_scratch_size(1, 1, radius, ThreadingPolicy::Single),
+                ] {
+                    // Scratch starts one byte past the allocation to be unaligned for the stacks
+                    let mut scratch = vec![0xffu8; scratch_size + 1];
+                    let mut dst = src.clone();
+                    stack_blur_with_scratch(
+                        &mut dst,
+                        stride,
+                        width,
+                        height,
+                        radius,
+                        channels,
+                        threading_policy,
+                        &mut scratch[1..],
+                    );
+                    assert_eq!(dst, expected, "{} {:?}", radius, threading_policy);
+                }
+            }
+        }
+    }
+}
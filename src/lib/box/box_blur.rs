@@ -25,12 +25,17 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "colorutils")]
 use colorutils_rs::{
     linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
 };
 use num_traits::cast::FromPrimitive;
 use num_traits::AsPrimitive;
-use rayon::ThreadPool;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
 use crate::channels_configuration::FastBlurChannels;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
@@ -40,7 +45,9 @@ use crate::r#box::box_blur_neon::neon_support;
     target_feature = "sse4.1"
 ))]
 use crate::r#box::box_blur_sse::sse_support;
+use crate::sigma::{gaussian_box_blur_radii_for_sigma, tent_blur_radius_for_sigma};
 use crate::unsafe_slice::UnsafeSlice;
+use crate::BlurAlgorithm;
 use crate::ThreadingPolicy;
 
 fn box_blur_horizontal_pass_impl<
@@ -58,8 +65,8 @@ fn box_blur_horizontal_pass_impl<
     start_y: u32,
     end_y: u32,
 ) where
-    T: std::ops::AddAssign
-        + std::ops::SubAssign
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
         + Copy
         + FromPrimitive
         + Default
@@ -68,9 +75,9 @@ fn box_blur_horizontal_pass_impl<
         + AsPrimitive<J>,
     J: FromPrimitive
         + Copy
-        + std::ops::Mul<Output = J>
-        + std::ops::AddAssign
-        + std::ops::SubAssign
+        + core::ops::Mul<Output = J>
+        + core::ops::AddAssign
+        + core::ops::SubAssign
         + AsPrimitive<f32>,
 {
     let box_channels: FastBlurChannels = CHANNELS_CONFIGURATION.into();
@@ -99,7 +106,7 @@ fn box_blur_horizontal_pass_impl<
             }
         }
 
-        for x in 1..std::cmp::min(half_kernel, width) {
+        for x in 1..core::cmp::min(half_kernel, width) {
             let px = x as usize * channels_count;
             kernel[0] += unsafe { *src.get_unchecked(y_src_shift + px) }.as_();
             kernel[1] += unsafe { *src.get_unchecked(y_src_shift + px + 1) }.as_();
@@ -113,9 +120,9 @@ fn box_blur_horizontal_pass_impl<
         }
 
         for x in 0..width {
-            let next = std::cmp::min(x + half_kernel, width - 1) as usize * channels_count;
+            let next = core::cmp::min(x + half_kernel, width - 1) as usize * channels_count;
             let previous =
-                std::cmp::max(x as i64 - half_kernel as i64, 0) as usize * channels_count;
+                core::cmp::max(x as i64 - half_kernel as i64, 0) as usize * channels_count;
             let px = x as usize * channels_count;
             // Prune previous and add next and compute mean
 
@@ -200,11 +207,11 @@ fn box_blur_horizontal_pass<
     width: u32,
     height: u32,
     radius: u32,
-    pool: &ThreadPool,
+    pool: &BlurPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign
-        + std::ops::SubAssign
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
         + AsPrimitive<u64>
@@ -221,16 +228,16 @@ fn box_blur_horizontal_pass<
         start_y: u32,
         end_y: u32,
     ) = box_blur_horizontal_pass_impl::<T, u32, CHANNEL_CONFIGURATION, false>;
-    if std::any::type_name::<T>() == "u8" || std::any::type_name::<T>() == "u16" {
+    if core::any::type_name::<T>() == "u8" || core::any::type_name::<T>() == "u16" {
         _dispatcher_horizontal =
             box_blur_horizontal_pass_impl::<T, u32, CHANNEL_CONFIGURATION, true>;
-    } else if std::any::type_name::<T>() == "f32" {
+    } else if core::any::type_name::<T>() == "f32" {
         _dispatcher_horizontal =
             box_blur_horizontal_pass_impl::<T, f32, CHANNEL_CONFIGURATION, false>;
     }
     #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
     {
-        if std::any::type_name::<T>() == "u8" {
+        if core::any::type_name::<T>() == "u8" {
             _dispatcher_horizontal =
                 neon_support::box_blur_horizontal_pass_neon::<T, CHANNEL_CONFIGURATION>;
         }
@@ -240,7 +247,7 @@ fn box_blur_horizontal_pass<
         target_feature = "sse4.1"
     ))]
     {
-        if std::any::type_name::<T>() == "u8" {
+        if core::any::type_name::<T>() == "u8" {
             _dispatcher_horizontal =
                 sse_support::box_blur_horizontal_pass_sse::<T, { CHANNEL_CONFIGURATION }>;
         }
@@ -282,8 +289,8 @@ fn box_blur_vertical_pass_impl<T, J, const CHANNEL_CONFIGURATION: usize, const U
     start_x: u32,
     end_x: u32,
 ) where
-    T: std::ops::AddAssign
-        + std::ops::SubAssign
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
         + Copy
         + FromPrimitive
         + Default
@@ -292,9 +299,9 @@ fn box_blur_vertical_pass_impl<T, J, const CHANNEL_CONFIGURATION: usize, const U
         + AsPrimitive<J>,
     J: FromPrimitive
         + Copy
-        + std::ops::Mul<Output = J>
-        + std::ops::AddAssign
-        + std::ops::SubAssign
+        + core::ops::Mul<Output = J>
+        + core::ops::AddAssign
+        + core::ops::SubAssign
         + AsPrimitive<f32>,
 {
     let box_channels: FastBlurChannels = CHANNEL_CONFIGURATION.into();
@@ -323,7 +330,7 @@ fn box_blur_vertical_pass_impl<T, J, const CHANNEL_CONFIGURATION: usize, const U
             }
         }
 
-        for y in 1..std::cmp::min(half_kernel, height) {
+        for y in 1..core::cmp::min(half_kernel, height) {
             let y_src_shift = y as usize * src_stride as usize;
             kernel[0] += unsafe { *src.get_unchecked(y_src_shift + px) }.as_();
             kernel[1] += unsafe { *src.get_unchecked(y_src_shift + px + 1) }.as_();
@@ -337,9 +344,9 @@ fn box_blur_vertical_pass_impl<T, J, const CHANNEL_CONFIGURATION: usize, const U
         }
 
         for y in 0..height {
-            let next = std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
+            let next = core::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
             let previous =
-                std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+                core::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
             let y_dst_shift = dst_stride as usize * y as usize;
             // Prune previous and add next and compute mean
 
@@ -424,11 +431,11 @@ fn box_blur_vertical_pass<
     width: u32,
     height: u32,
     radius: u32,
-    pool: &ThreadPool,
+    pool: &BlurPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign
-        + std::ops::SubAssign
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
         + AsPrimitive<u64>
@@ -446,9 +453,9 @@ fn box_blur_vertical_pass<
         start_x: u32,
         end_x: u32,
     ) = box_blur_vertical_pass_impl::<T, u32, CHANNEL_CONFIGURATION, false>;
-    if std::any::type_name::<T>() == "u8" || std::any::type_name::<T>() == "u16" {
+    if core::any::type_name::<T>() == "u8" || core::any::type_name::<T>() == "u16" {
         _dispatcher_vertical = box_blur_vertical_pass_impl::<T, u32, CHANNEL_CONFIGURATION, true>;
-    } else if std::any::type_name::<T>() == "f32" {
+    } else if core::any::type_name::<T>() == "f32" {
         _dispatcher_vertical = box_blur_vertical_pass_impl::<T, f32, CHANNEL_CONFIGURATION, false>;
     }
     #[cfg(all(
@@ -456,14 +463,14 @@ fn box_blur_vertical_pass<
         target_feature = "sse4.1"
     ))]
     {
-        if std::any::type_name::<T>() == "u8" {
+        if core::any::type_name::<T>() == "u8" {
             _dispatcher_vertical =
                 sse_support::box_blur_vertical_pass_sse::<T, CHANNEL_CONFIGURATION>;
         }
     }
     #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
     {
-        if std::any::type_name::<T>() == "u8" {
+        if core::any::type_name::<T>() == "u8" {
             _dispatcher_vertical =
                 neon_support::box_blur_vertical_pass_neon::<T, CHANNEL_CONFIGURATION>;
         }
@@ -496,7 +503,8 @@ fn box_blur_vertical_pass<
     });
 }
 
-fn box_blur_impl<
+/// Performs box blur with the thread pool, transient must have `dst_stride * height` elements
+pub(crate) fn box_blur_in_pool<
     T: FromPrimitive + Default + Sync + Send + Copy,
     const CHANNEL_CONFIGURATION: usize,
 >(
@@ -507,23 +515,22 @@ fn box_blur_impl<
     width: u32,
     height: u32,
     radius: u32,
-    pool: &ThreadPool,
+    transient: &mut [T],
+    pool: &BlurPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign
-        + std::ops::SubAssign
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
         + AsPrimitive<u64>
         + AsPrimitive<f32>
         + AsPrimitive<f64>,
 {
-    let mut transient: Vec<T> =
-        vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
     box_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION>(
         src,
         src_stride,
-        &mut transient,
+        transient,
         dst_stride,
         width,
         height,
@@ -532,13 +539,51 @@ fn box_blur_impl<
         thread_count,
     );
     box_blur_vertical_pass::<T, CHANNEL_CONFIGURATION>(
-        &transient,
+        transient,
+        dst_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        pool,
+        thread_count,
+    );
+}
+
+fn box_blur_impl<
+    T: FromPrimitive + Default + Sync + Send + Copy,
+    const CHANNEL_CONFIGURATION: usize,
+>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    pool: &BlurPool,
+    thread_count: u32,
+) where
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>,
+{
+    let mut transient: Vec<T> =
+        vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
+    box_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        src,
         src_stride,
         dst,
         dst_stride,
         width,
         height,
         radius,
+        &mut transient,
         pool,
         thread_count,
     );
@@ -571,11 +616,12 @@ pub fn box_blur(
     channels: FastBlurChannels,
     threading_policy: ThreadingPolicy,
 ) {
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Box.work_per_pixel(radius) * type_work_per_pixel::<u8>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
     match channels {
         FastBlurChannels::Channels3 => {
             box_blur_impl::<u8, 3>(
@@ -632,11 +678,12 @@ pub fn box_blur_u16(
     threading_policy: ThreadingPolicy,
 ) {
     let stride = width * channels.get_channels() as u32;
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Box.work_per_pixel(radius) * type_work_per_pixel::<u16>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
     match channels {
         FastBlurChannels::Channels3 => {
             box_blur_impl::<u16, 3>(
@@ -692,11 +739,12 @@ pub fn box_blur_f32(
     threading_policy: ThreadingPolicy,
 ) {
     let stride = width * channels.get_channels() as u32;
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Box.work_per_pixel(radius) * type_work_per_pixel::<f32>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
     match channels {
         FastBlurChannels::Channels3 => {
             box_blur_impl::<f32, 3>(
@@ -745,6 +793,7 @@ pub fn box_blur_f32(
 ///
 /// # Panics
 /// Panic is stride/width/height/channel configuration do not match provided
+#[cfg(feature = "colorutils")]
 pub fn box_blur_in_linear(
     src: &[u8],
     src_stride: u32,
@@ -776,7 +825,7 @@ pub fn box_blur_in_linear(
         &src,
         src_stride,
         &mut linear_data,
-        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width * core::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         width,
         height,
         transfer_function,
@@ -794,7 +843,7 @@ pub fn box_blur_in_linear(
 
     inverse_transformer(
         &linear_data_2,
-        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width * core::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         dst,
         dst_stride,
         width,
@@ -803,7 +852,8 @@ pub fn box_blur_in_linear(
     );
 }
 
-fn tent_blur_impl<
+/// Performs tent blur with the thread pool, scratch must have `2 * dst_stride * height` elements
+pub(crate) fn tent_blur_in_pool<
     T: FromPrimitive + Default + Sync + Send + Copy,
     const CHANNEL_CONFIGURATION: usize,
 >(
@@ -814,42 +864,83 @@ fn tent_blur_impl<
     width: u32,
     height: u32,
     radius: u32,
-    threading_policy: ThreadingPolicy,
+    scratch: &mut [T],
+    pool: &BlurPool,
+    thread_count: u32,
 ) where
-    T: std::ops::AddAssign
-        + std::ops::SubAssign
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
         + AsPrimitive<u64>
         + AsPrimitive<f32>
         + AsPrimitive<f64>,
 {
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-    let mut transient: Vec<T> =
-        vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
+    let (transient, box_transient) = scratch.split_at_mut(dst_stride as usize * height as usize);
+    box_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
         src,
         src_stride,
-        &mut transient,
+        transient,
         dst_stride,
         width,
         height,
         radius,
-        &pool,
+        box_transient,
+        pool,
+        thread_count,
+    );
+    box_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        transient,
+        dst_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        box_transient,
+        pool,
         thread_count,
     );
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
-        &transient,
+}
+
+fn tent_blur_impl<
+    T: FromPrimitive + Default + Sync + Send + Copy,
+    const CHANNEL_CONFIGURATION: usize,
+>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+) where
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>,
+{
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Tent.work_per_pixel(radius) * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    let mut scratch: Vec<T> =
+        vec![T::from_u32(0).unwrap_or_default(); 2 * dst_stride as usize * height as usize];
+    tent_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        src,
         src_stride,
         dst,
         dst_stride,
         width,
         height,
         radius,
+        &mut scratch,
         &pool,
         thread_count,
     );
@@ -1024,6 +1115,133 @@ pub fn tent_blur_f32(
     }
 }
 
+/// Returns count of elements in the scratch required by *tent_blur_u16_with_scratch* and *tent_blur_f32_with_scratch*
+pub fn tent_blur_scratch_size(width: u32, height: u32, channels: FastBlurChannels) -> usize {
+    2 * width as usize * height as usize * channels.get_channels()
+}
+
+fn tent_blur_scratch_impl<T, const CHANNEL_CONFIGURATION: usize>(
+    src: &[T],
+    dst: &mut [T],
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [T],
+) where
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + core::ops::AddAssign
+        + core::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>,
+{
+    let stride = width * CHANNEL_CONFIGURATION as u32;
+    let required = 2 * stride as usize * height as usize;
+    if scratch.len() < required {
+        panic!(
+            "Scratch has {} elements but {} is required",
+            scratch.len(),
+            required
+        );
+    }
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Tent.work_per_pixel(radius) * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    tent_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        src,
+        stride,
+        dst,
+        stride,
+        width,
+        height,
+        radius,
+        &mut scratch[..required],
+        &pool,
+        thread_count,
+    );
+}
+
+/// Performs tent blur on the image with intermediate images taken from `scratch`.
+///
+/// Same as *tent_blur_u16* but nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single*. Multithreaded policies still allocate to schedule jobs on the threads.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - almost any radius is supported
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `scratch` - Buffer of at least *tent_blur_scratch_size* elements, its content is overwritten
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided or scratch is smaller than required
+#[allow(clippy::too_many_arguments)]
+pub fn tent_blur_u16_with_scratch(
+    src: &[u16],
+    dst: &mut [u16],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [u16],
+) {
+    let executor = match channels {
+        FastBlurChannels::Channels3 => tent_blur_scratch_impl::<u16, 3>,
+        FastBlurChannels::Channels4 => tent_blur_scratch_impl::<u16, 4>,
+    };
+    executor(src, dst, width, height, radius, threading_policy, scratch);
+}
+
+/// Performs tent blur on the image with intermediate images taken from `scratch`.
+///
+/// Same as *tent_blur_f32* but nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single*. Multithreaded policies still allocate to schedule jobs on the threads.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - almost any radius is supported
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `scratch` - Buffer of at least *tent_blur_scratch_size* elements, its content is overwritten
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided or scratch is smaller than required
+#[allow(clippy::too_many_arguments)]
+pub fn tent_blur_f32_with_scratch(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [f32],
+) {
+    let executor = match channels {
+        FastBlurChannels::Channels3 => tent_blur_scratch_impl::<f32, 3>,
+        FastBlurChannels::Channels4 => tent_blur_scratch_impl::<f32, 4>,
+    };
+    executor(src, dst, width, height, radius, threading_policy, scratch);
+}
+
 /// Performs tent blur on the image in linear colorspace
 ///
 /// Tent blur just makes a two passes box blur on the image since two times box it is almost equal to tent filter.
@@ -1045,6 +1263,7 @@ pub fn tent_blur_f32(
 ///
 /// # Panics
 /// Panic is stride/width/height/channel configuration do not match provided
+#[cfg(feature = "colorutils")]
 pub fn tent_blur_in_linear(
     src: &[u8],
     src_stride: u32,
@@ -1076,7 +1295,7 @@ pub fn tent_blur_in_linear(
         &src,
         src_stride,
         &mut linear_data,
-        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width * core::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         width,
         height,
         transfer_function,
@@ -1094,7 +1313,7 @@ pub fn tent_blur_in_linear(
 
     inverse_transformer(
         &linear_data_2,
-        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width * core::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         dst,
         dst_stride,
         width,
@@ -1103,7 +1322,8 @@ pub fn tent_blur_in_linear(
     );
 }
 
-fn gaussian_box_blur_impl<
+/// Performs gaussian box blur with the thread pool, scratch must have `3 * dst_stride * height` elements
+pub(crate) fn gaussian_box_blur_in_pool<
     T: FromPrimitive + Default + Sync + Send + Copy,
     const CHANNEL_CONFIGURATION: usize,
 >(
@@ -1113,56 +1333,98 @@ fn gaussian_box_blur_impl<
     dst_stride: u32,
     width: u32,
     height: u32,
-    radius: u32,
-    threading_policy: ThreadingPolicy,
+    radius: [u32; 3],
+    scratch: &mut [T],
+    pool: &BlurPool,
+    thread_count: u32,
 ) where
-    T: std::ops::AddAssign
-        + std::ops::SubAssign
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
         + Copy
         + AsPrimitive<u32>
         + AsPrimitive<u64>
         + AsPrimitive<f32>
         + AsPrimitive<f64>,
 {
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-    let mut transient: Vec<T> =
-        vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
-    let mut transient2: Vec<T> =
-        vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
-        &src,
+    let image_size = dst_stride as usize * height as usize;
+    let (transient, scratch) = scratch.split_at_mut(image_size);
+    let (transient2, box_transient) = scratch.split_at_mut(image_size);
+    box_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        src,
         src_stride,
-        &mut transient,
+        transient,
         dst_stride,
         width,
         height,
-        radius,
-        &pool,
+        radius[0],
+        box_transient,
+        pool,
         thread_count,
     );
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
-        &transient,
-        src_stride,
-        &mut transient2,
+    box_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        transient,
+        dst_stride,
+        transient2,
         dst_stride,
         width,
         height,
-        radius,
-        &pool,
+        radius[1],
+        box_transient,
+        pool,
         thread_count,
     );
-    box_blur_impl::<T, CHANNEL_CONFIGURATION>(
-        &transient2,
+    box_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        transient2,
+        dst_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        radius[2],
+        box_transient,
+        pool,
+        thread_count,
+    );
+}
+
+fn gaussian_box_blur_impl<
+    T: FromPrimitive + Default + Sync + Send + Copy,
+    const CHANNEL_CONFIGURATION: usize,
+>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: [u32; 3],
+    threading_policy: ThreadingPolicy,
+) where
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>,
+{
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::GaussianBox.work_per_pixel(radius[0]) * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    let mut scratch: Vec<T> =
+        vec![T::from_u32(0).unwrap_or_default(); 3 * dst_stride as usize * height as usize];
+    gaussian_box_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        src,
         src_stride,
         dst,
         dst_stride,
         width,
         height,
         radius,
+        &mut scratch,
         &pool,
         thread_count,
     );
@@ -1208,7 +1470,7 @@ pub fn gaussian_box_blur(
                 dst_stride,
                 width,
                 height,
-                radius,
+                [radius; 3],
                 threading_policy,
             );
         }
@@ -1220,7 +1482,7 @@ pub fn gaussian_box_blur(
                 dst_stride,
                 width,
                 height,
-                radius,
+                [radius; 3],
                 threading_policy,
             );
         }
@@ -1266,7 +1528,7 @@ pub fn gaussian_box_blur_u16(
                 stride,
                 width,
                 height,
-                radius,
+                [radius; 3],
                 threading_policy,
             );
         }
@@ -1278,7 +1540,7 @@ pub fn gaussian_box_blur_u16(
                 stride,
                 width,
                 height,
-                radius,
+                [radius; 3],
                 threading_policy,
             );
         }
@@ -1323,7 +1585,7 @@ pub fn gaussian_box_blur_f32(
                 stride,
                 width,
                 height,
-                radius,
+                [radius; 3],
                 threading_policy,
             );
         }
@@ -1335,13 +1597,143 @@ pub fn gaussian_box_blur_f32(
                 stride,
                 width,
                 height,
-                radius,
+                [radius; 3],
                 threading_policy,
             );
         }
     }
 }
 
+/// Returns count of elements in the scratch required by *gaussian_box_blur_u16_with_scratch* and *gaussian_box_blur_f32_with_scratch*
+pub fn gaussian_box_blur_scratch_size(
+    width: u32,
+    height: u32,
+    channels: FastBlurChannels,
+) -> usize {
+    3 * width as usize * height as usize * channels.get_channels()
+}
+
+fn gaussian_box_blur_scratch_impl<T, const CHANNEL_CONFIGURATION: usize>(
+    src: &[T],
+    dst: &mut [T],
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [T],
+) where
+    T: FromPrimitive
+        + Default
+        + Sync
+        + Send
+        + core::ops::AddAssign
+        + core::ops::SubAssign
+        + Copy
+        + AsPrimitive<u32>
+        + AsPrimitive<u64>
+        + AsPrimitive<f32>
+        + AsPrimitive<f64>,
+{
+    let stride = width * CHANNEL_CONFIGURATION as u32;
+    let required = 3 * stride as usize * height as usize;
+    if scratch.len() < required {
+        panic!(
+            "Scratch has {} elements but {} is required",
+            scratch.len(),
+            required
+        );
+    }
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::GaussianBox.work_per_pixel(radius) * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    gaussian_box_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        src,
+        stride,
+        dst,
+        stride,
+        width,
+        height,
+        [radius; 3],
+        &mut scratch[..required],
+        &pool,
+        thread_count,
+    );
+}
+
+/// Performs gaussian box blur approximation on the image with intermediate images taken from `scratch`.
+///
+/// Same as *gaussian_box_blur_u16* but nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single*. Multithreaded policies still allocate to schedule jobs on the threads.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - almost any radius is supported
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `scratch` - Buffer of at least *gaussian_box_blur_scratch_size* elements, its content is overwritten
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided or scratch is smaller than required
+#[allow(clippy::too_many_arguments)]
+pub fn gaussian_box_blur_u16_with_scratch(
+    src: &[u16],
+    dst: &mut [u16],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [u16],
+) {
+    let executor = match channels {
+        FastBlurChannels::Channels3 => gaussian_box_blur_scratch_impl::<u16, 3>,
+        FastBlurChannels::Channels4 => gaussian_box_blur_scratch_impl::<u16, 4>,
+    };
+    executor(src, dst, width, height, radius, threading_policy, scratch);
+}
+
+/// Performs gaussian box blur approximation on the image with intermediate images taken from `scratch`.
+///
+/// Same as *gaussian_box_blur_f32* but nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single*. Multithreaded policies still allocate to schedule jobs on the threads.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - almost any radius is supported
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `scratch` - Buffer of at least *gaussian_box_blur_scratch_size* elements, its content is overwritten
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided or scratch is smaller than required
+#[allow(clippy::too_many_arguments)]
+pub fn gaussian_box_blur_f32_with_scratch(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [f32],
+) {
+    let executor = match channels {
+        FastBlurChannels::Channels3 => gaussian_box_blur_scratch_impl::<f32, 3>,
+        FastBlurChannels::Channels4 => gaussian_box_blur_scratch_impl::<f32, 4>,
+    };
+    executor(src, dst, width, height, radius, threading_policy, scratch);
+}
 /// Performs gaussian box blur approximation on the image.
 ///
 /// This method launches three times box blur on the image since 2 passes box filter it is a tent filter and 3 passes of box blur it is almost gaussian filter.
@@ -1363,6 +1755,7 @@ pub fn gaussian_box_blur_f32(
 ///
 /// # Panics
 /// Panic is stride/width/height/channel configuration do not match provided
+#[cfg(feature = "colorutils")]
 pub fn gaussian_box_blur_in_linear(
     src: &[u8],
     src_stride: u32,
@@ -1394,7 +1787,7 @@ pub fn gaussian_box_blur_in_linear(
         &src,
         src_stride,
         &mut linear_data,
-        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width * core::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         width,
         height,
         transfer_function,
@@ -1412,7 +1805,7 @@ pub fn gaussian_box_blur_in_linear(
 
     inverse_transformer(
         &linear_data_2,
-        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width * core::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         dst,
         dst_stride,
         width,
@@ -1420,3 +1813,269 @@ pub fn gaussian_box_blur_in_linear(
         transfer_function,
     );
 }
+
+/// Performs tent blur on the image with radius derived from gaussian sigma.
+///
+/// Same as *tent_blur* however blur strength is set in terms of gaussian sigma,
+/// so switching between approximations keeps the same visual strength.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Gaussian sigma, radius is computed with *tent_blur_radius_for_sigma*
+/// * `channels` - Count of channels in the image
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn tent_blur_sigma(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    tent_blur(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        tent_blur_radius_for_sigma(sigma),
+        channels,
+        threading_policy,
+    );
+}
+
+/// Performs tent blur on the f32 image with radius derived from gaussian sigma.
+///
+/// Same as *tent_blur_f32* however blur strength is set in terms of gaussian sigma,
+/// so switching between approximations keeps the same visual strength.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Gaussian sigma, radius is computed with *tent_blur_radius_for_sigma*
+/// * `channels` - Count of channels in the image
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn tent_blur_sigma_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    tent_blur_f32(
+        src,
+        dst,
+        width,
+        height,
+        tent_blur_radius_for_sigma(sigma),
+        channels,
+        threading_policy,
+    );
+}
+
+/// Performs gaussian box blur approximation on the image with box sizes derived from gaussian sigma.
+///
+/// Unlike *gaussian_box_blur* three box passes may have different radii,
+/// they are chosen with "extended box" derivation so total variance matches requested sigma.
+/// See *gaussian_box_blur_radii_for_sigma*.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Gaussian sigma
+/// * `channels` - Count of channels in the image
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn gaussian_box_blur_sigma(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    let radii = gaussian_box_blur_radii_for_sigma(sigma);
+    match channels {
+        FastBlurChannels::Channels3 => {
+            gaussian_box_blur_impl::<u8, 3>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                radii,
+                threading_policy,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            gaussian_box_blur_impl::<u8, 4>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                radii,
+                threading_policy,
+            );
+        }
+    }
+}
+
+/// Performs gaussian box blur approximation on the f32 image with box sizes derived from gaussian sigma.
+///
+/// Unlike *gaussian_box_blur_f32* three box passes may have different radii,
+/// they are chosen with "extended box" derivation so total variance matches requested sigma.
+/// See *gaussian_box_blur_radii_for_sigma*.
+///
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Gaussian sigma
+/// * `channels` - Count of channels in the image
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn gaussian_box_blur_sigma_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    let stride = width * channels.get_channels() as u32;
+    let radii = gaussian_box_blur_radii_for_sigma(sigma);
+    match channels {
+        FastBlurChannels::Channels3 => {
+            gaussian_box_blur_impl::<f32, 3>(
+                src,
+                stride,
+                dst,
+                stride,
+                width,
+                height,
+                radii,
+                threading_policy,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            gaussian_box_blur_impl::<f32, 4>(
+                src,
+                stride,
+                dst,
+                stride,
+                width,
+                height,
+                radii,
+                threading_policy,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type BoxFilter =
+        fn(&[u8], u32, &mut [u8], u32, u32, u32, u32, FastBlurChannels, ThreadingPolicy);
+
+    fn assert_strides_match(filter: BoxFilter) {
+        let width = 21usize;
+        let height = 13usize;
+        let row = width * 3;
+        let src: Vec<u8> = (0..row * height).map(|i| (i * 37 % 251) as u8).collect();
+        for threading_policy in [ThreadingPolicy::Single, ThreadingPolicy::Fixed(3)] {
+            let mut expected = vec![0u8; row * height];
+            filter(
+                &src,
+                row as u32,
+                &mut expected,
+                row as u32,
+                width as u32,
+                height as u32,
+                3,
+                FastBlurChannels::Channels3,
+                threading_policy,
+            );
+            for (src_stride, dst_stride) in [(row + 7, row + 2), (row + 2, row + 9)] {
+                let mut padded = vec![0u8; src_stride * height];
+                for (dst, src) in padded
+                    .chunks_exact_mut(src_stride)
+                    .zip(src.chunks_exact(row))
+                {
+                    dst[..row].copy_from_slice(src);
+                }
+                let mut dst = vec![0u8; dst_stride * height];
+                filter(
+                    &padded,
+                    src_stride as u32,
+                    &mut dst,
+                    dst_stride as u32,
+                    width as u32,
+                    height as u32,
+                    3,
+                    FastBlurChannels::Channels3,
+                    threading_policy,
+                );
+                for y in 0..height {
+                    assert_eq!(
+                        &dst[y * dst_stride..y * dst_stride + row],
+                        &expected[y * row..(y + 1) * row],
+                        "Row {} differs for src stride {} and dst stride {}",
+                        y,
+                        src_stride,
+                        dst_stride
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn box_blur_supports_different_strides() {
+        assert_strides_match(box_blur);
+    }
+
+    #[test]
+    fn tent_blur_supports_different_strides() {
+        assert_strides_match(tent_blur);
+    }
+
+    #[test]
+    fn gaussian_box_blur_supports_different_strides() {
+        assert_strides_match(gaussian_box_blur);
+    }
+}
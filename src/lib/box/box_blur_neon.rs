@@ -31,7 +31,7 @@
 ))]
 pub mod neon_support {
     use crate::neon::{load_u8_u16, load_u8_u32_fast, vmulq_u32_f32};
-    use std::arch::aarch64::*;
+    use core::arch::aarch64::*;
 
     use crate::unsafe_slice::UnsafeSlice;
 
@@ -46,8 +46,8 @@ pub mod neon_support {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undefined_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undefined_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_dst) };
         let eraser_store: [u32; 4] = [1u32, 1u32, 1u32, 0u32];
         let eraser: uint32x4_t = unsafe { vld1q_u32(eraser_store.as_ptr()) };
 
@@ -87,7 +87,7 @@ pub mod neon_support {
                 store_3 = unsafe { vmulq_u32(edge_colors_3, v_edge_count) };
             }
 
-            for x in 1..std::cmp::min(half_kernel, width) {
+            for x in 1..core::cmp::min(half_kernel, width) {
                 let px = x as usize * CHANNEL_CONFIGURATION;
 
                 let s_ptr_0 = unsafe { src.as_ptr().add(y_src_shift + px) };
@@ -114,7 +114,7 @@ pub mod neon_support {
 
                 // subtract previous
                 {
-                    let previous_x = std::cmp::max(x as i64 - half_kernel as i64, 0) as usize;
+                    let previous_x = core::cmp::max(x as i64 - half_kernel as i64, 0) as usize;
                     let previous = previous_x * CHANNEL_CONFIGURATION;
 
                     let s_ptr_0 = unsafe { src.as_ptr().add(y_src_shift + previous) };
@@ -145,7 +145,7 @@ pub mod neon_support {
 
                 // add next
                 {
-                    let next_x = std::cmp::min(x + half_kernel, width - 1) as usize;
+                    let next_x = core::cmp::min(x + half_kernel, width - 1) as usize;
 
                     let next = next_x * CHANNEL_CONFIGURATION;
 
@@ -265,7 +265,7 @@ pub mod neon_support {
                 store = unsafe { vmulq_u32(edge_colors, v_edge_count) };
             }
 
-            for x in 1..std::cmp::min(half_kernel, width) {
+            for x in 1..core::cmp::min(half_kernel, width) {
                 let px = x as usize * CHANNEL_CONFIGURATION;
                 let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
                 let edge_colors = unsafe { load_u8_u16::<CHANNEL_CONFIGURATION>(s_ptr) };
@@ -277,7 +277,7 @@ pub mod neon_support {
 
                 // subtract previous
                 {
-                    let previous_x = std::cmp::max(x as i64 - half_kernel as i64, 0) as usize;
+                    let previous_x = core::cmp::max(x as i64 - half_kernel as i64, 0) as usize;
                     let previous = previous_x * CHANNEL_CONFIGURATION;
                     let s_ptr = unsafe { src.as_ptr().add(y_src_shift + previous) };
                     let edge_colors = unsafe { load_u8_u16::<CHANNEL_CONFIGURATION>(s_ptr) };
@@ -286,7 +286,7 @@ pub mod neon_support {
 
                 // add next
                 {
-                    let next_x = std::cmp::min(x + half_kernel, width - 1) as usize;
+                    let next_x = core::cmp::min(x + half_kernel, width - 1) as usize;
 
                     let next = next_x * CHANNEL_CONFIGURATION;
 
@@ -335,8 +335,9 @@ pub mod neon_support {
         start_x: u32,
         end_x: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undefined_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undefined_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> =
+            unsafe { core::mem::transmute(undefined_unsafe_dst) };
         let eraser_store: [u32; 4] = [1u32, 1u32, 1u32, 0u32];
         let eraser: uint32x4_t = unsafe { vld1q_u32(eraser_store.as_ptr()) };
 
@@ -365,7 +366,7 @@ pub mod neon_support {
                 store_1 = unsafe { vmulq_u32(edge_colors_1, v_edge_count) };
             }
 
-            for y in 1..std::cmp::min(half_kernel, height) {
+            for y in 1..core::cmp::min(half_kernel, height) {
                 let y_src_shift = y as usize * src_stride as usize;
                 let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
                 let edge_colors_0 = unsafe { load_u8_u16::<CHANNEL_CONFIGURATION>(s_ptr) };
@@ -379,9 +380,9 @@ pub mod neon_support {
             for y in 0..height {
                 // preload edge pixels
                 let next =
-                    std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
+                    core::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
                 let previous =
-                    std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+                    core::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
                 let y_dst_shift = dst_stride as usize * y as usize;
 
                 // subtract previous
@@ -470,7 +471,7 @@ pub mod neon_support {
                 store = unsafe { vmulq_u32(edge_colors, v_edge_count) };
             }
 
-            for y in 1..std::cmp::min(half_kernel, height) {
+            for y in 1..core::cmp::min(half_kernel, height) {
                 let y_src_shift = y as usize * src_stride as usize;
                 let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
                 let edge_colors = unsafe { load_u8_u16::<CHANNEL_CONFIGURATION>(s_ptr) };
@@ -480,9 +481,9 @@ pub mod neon_support {
             for y in 0..height {
                 // preload edge pixels
                 let next =
-                    std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
+                    core::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
                 let previous =
-                    std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+                    core::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
                 let y_dst_shift = dst_stride as usize * y as usize;
 
                 // subtract previous
@@ -31,9 +31,9 @@
 ))]
 pub mod sse_support {
     #[cfg(target_arch = "x86")]
-    use std::arch::x86::*;
+    use core::arch::x86::*;
     #[cfg(target_arch = "x86_64")]
-    use std::arch::x86_64::*;
+    use core::arch::x86_64::*;
 
     use crate::sse::{_mm_mul_ps_epi32, load_u8_s32_fast};
     use crate::unsafe_slice::UnsafeSlice;
@@ -48,8 +48,9 @@ pub mod sse_support {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undefined_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undefined_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> =
+            unsafe { core::mem::transmute(undefined_unsafe_dst) };
         let eraser_store: [i32; 4] = if CHANNELS == 3 {
             [1i32, 1i32, 1i32, 0i32]
         } else {
@@ -76,7 +77,7 @@ pub mod sse_support {
                 store = unsafe { _mm_mullo_epi32(edge_colors, v_edge_count) };
             }
 
-            for x in 1..std::cmp::min(half_kernel, width) {
+            for x in 1..core::cmp::min(half_kernel, width) {
                 let px = x as usize * CHANNELS;
                 let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
                 let edge_colors = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
@@ -88,7 +89,7 @@ pub mod sse_support {
 
                 // subtract previous
                 {
-                    let previous_x = std::cmp::max(x as i64 - half_kernel as i64, 0) as usize;
+                    let previous_x = core::cmp::max(x as i64 - half_kernel as i64, 0) as usize;
                     let previous = previous_x * CHANNELS;
                     let s_ptr = unsafe { src.as_ptr().add(y_src_shift + previous) };
                     let edge_colors = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
@@ -97,7 +98,7 @@ pub mod sse_support {
 
                 // add next
                 {
-                    let next_x = std::cmp::min(x + half_kernel, width - 1) as usize;
+                    let next_x = core::cmp::min(x + half_kernel, width - 1) as usize;
 
                     let next = next_x * CHANNELS;
 
@@ -146,8 +147,9 @@ pub mod sse_support {
         start_x: u32,
         end_x: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undefined_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undefined_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> =
+            unsafe { core::mem::transmute(undefined_unsafe_dst) };
         let eraser_store: [i32; 4] = if CHANNELS == 3 {
             [1i32, 1i32, 1i32, 0i32]
         } else {
@@ -179,7 +181,7 @@ pub mod sse_support {
                 store_1 = unsafe { _mm_mullo_epi32(edge_colors_1, v_edge_count) };
             }
 
-            for y in 1..std::cmp::min(half_kernel, height) {
+            for y in 1..core::cmp::min(half_kernel, height) {
                 let y_src_shift = y as usize * src_stride as usize;
                 let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
                 let edge_colors_0 = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
@@ -191,9 +193,9 @@ pub mod sse_support {
             for y in 0..height {
                 // preload edge pixels
                 let next =
-                    std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
+                    core::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
                 let previous =
-                    std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+                    core::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
                 let y_dst_shift = dst_stride as usize * y as usize;
 
                 // subtract previous
@@ -264,7 +266,7 @@ pub mod sse_support {
                     let unsafe_offset = y_dst_shift + px;
                     let ptr = unsafe { unsafe_dst.slice.get_unchecked(unsafe_offset).get() };
                     unsafe {
-                        std::ptr::copy_nonoverlapping(&px_8 as *const _ as *mut u8, ptr, 8);
+                        core::ptr::copy_nonoverlapping(&px_8 as *const _ as *mut u8, ptr, 8);
                     }
                 }
             }
@@ -282,7 +284,7 @@ pub mod sse_support {
                 store = unsafe { _mm_mullo_epi32(edge_colors, v_edge_count) };
             }
 
-            for y in 1..std::cmp::min(half_kernel, height) {
+            for y in 1..core::cmp::min(half_kernel, height) {
                 let y_src_shift = y as usize * src_stride as usize;
                 let s_ptr = unsafe { src.as_ptr().add(y_src_shift + px) };
                 let edge_colors = unsafe { load_u8_s32_fast::<CHANNELS>(s_ptr) };
@@ -292,9 +294,9 @@ pub mod sse_support {
             for y in 0..height {
                 // preload edge pixels
                 let next =
-                    std::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
+                    core::cmp::min(y + half_kernel, height - 1) as usize * src_stride as usize;
                 let previous =
-                    std::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
+                    core::cmp::max(y as i64 - half_kernel as i64, 0) as usize * src_stride as usize;
                 let y_dst_shift = dst_stride as usize * y as usize;
 
                 // subtract previous
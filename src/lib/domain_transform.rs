@@ -0,0 +1,676 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::ThreadingPolicy;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Declares variant of the domain transform filter
+pub enum DomainTransformMode {
+    /// Box filter in the transformed domain, smoothest result
+    NormalizedConvolution = 0,
+    /// Recursive exponential filter in the transformed domain, fastest one
+    #[default]
+    RecursiveFiltering = 1,
+}
+
+pub(crate) trait DomainTransformValue: Copy + Send + Sync + 'static {
+    fn to_f32(self) -> f32;
+    fn from_f32(value: f32) -> Self;
+}
+
+impl DomainTransformValue for u8 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value.round().clamp(0f32, 255f32) as u8
+    }
+}
+
+impl DomainTransformValue for f32 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+/// Filters one line in the transformed domain,
+/// `derivative[i]` is a distance between samples `i - 1` and `i`,
+/// `parameter` is box radius for normalized convolution and `ln(a)` for recursive filtering
+fn domain_transform_line<const CHANNELS_CONFIGURATION: usize, const MODE: usize>(
+    line: &mut [f32],
+    derivative: &[f32],
+    parameter: f32,
+    coordinates: &mut Vec<f32>,
+    sums: &mut Vec<f64>,
+) {
+    let length = derivative.len();
+    if MODE == DomainTransformMode::RecursiveFiltering as usize {
+        for i in 1..length {
+            let weight = (parameter * derivative[i]).exp();
+            let (previous, current) = line.split_at_mut(i * CHANNELS_CONFIGURATION);
+            let previous = &previous[(i - 1) * CHANNELS_CONFIGURATION..];
+            for (dst, &src) in current[..CHANNELS_CONFIGURATION]
+                .iter_mut()
+                .zip(previous.iter())
+            {
+                *dst += weight * (src - *dst);
+            }
+        }
+        for i in (1..length).rev() {
+            let weight = (parameter * derivative[i]).exp();
+            let (previous, current) = line.split_at_mut(i * CHANNELS_CONFIGURATION);
+            let previous = &mut previous[(i - 1) * CHANNELS_CONFIGURATION..];
+            for (dst, &src) in previous.iter_mut().zip(current.iter()) {
+                *dst += weight * (src - *dst);
+            }
+        }
+    } else {
+        coordinates.clear();
+        sums.clear();
+        sums.resize((length + 1) * CHANNELS_CONFIGURATION, 0f64);
+        let mut position = 0f32;
+        for i in 0..length {
+            if i > 0 {
+                position += derivative[i];
+            }
+            coordinates.push(position);
+            let (previous, current) = sums.split_at_mut((i + 1) * CHANNELS_CONFIGURATION);
+            let previous = &previous[i * CHANNELS_CONFIGURATION..];
+            let values = &line[i * CHANNELS_CONFIGURATION..(i + 1) * CHANNELS_CONFIGURATION];
+            for ((dst, &sum), &value) in current[..CHANNELS_CONFIGURATION]
+                .iter_mut()
+                .zip(previous.iter())
+                .zip(values.iter())
+            {
+                *dst = sum + value as f64;
+            }
+        }
+        let mut lower = 0usize;
+        let mut upper = 0usize;
+        for i in 0..length {
+            let center = coordinates[i];
+            while coordinates[lower] < center - parameter {
+                lower += 1;
+            }
+            while upper + 1 < length && coordinates[upper + 1] <= center + parameter {
+                upper += 1;
+            }
+            let scale = 1f64 / (upper - lower + 1) as f64;
+            let values = &mut line[i * CHANNELS_CONFIGURATION..(i + 1) * CHANNELS_CONFIGURATION];
+            for (c, dst) in values.iter_mut().enumerate() {
+                let sum = sums[(upper + 1) * CHANNELS_CONFIGURATION + c]
+                    - sums[lower * CHANNELS_CONFIGURATION + c];
+                *dst = (sum * scale) as f32;
+            }
+        }
+    }
+}
+
+fn domain_transform_horizontal_pass<const CHANNELS_CONFIGURATION: usize, const MODE: usize>(
+    unsafe_transient: &UnsafeSlice<f32>,
+    horizontal_derivative: &[f32],
+    width: u32,
+    parameter: f32,
+    start_y: u32,
+    end_y: u32,
+) {
+    let width = width as usize;
+    let row_length = width * CHANNELS_CONFIGURATION;
+    let mut line = vec![0f32; row_length];
+    let mut coordinates = Vec::new();
+    let mut sums = Vec::new();
+    for y in start_y as usize..end_y as usize {
+        let offset = y * row_length;
+        for (i, dst) in line.iter_mut().enumerate() {
+            *dst = unsafe_transient[offset + i];
+        }
+        domain_transform_line::<CHANNELS_CONFIGURATION, MODE>(
+            &mut line,
+            &horizontal_derivative[y * width..(y + 1) * width],
+            parameter,
+            &mut coordinates,
+            &mut sums,
+        );
+        for (i, &v) in line.iter().enumerate() {
+            unsafe {
+                unsafe_transient.write(offset + i, v);
+            }
+        }
+    }
+}
+
+fn domain_transform_vertical_pass<const CHANNELS_CONFIGURATION: usize, const MODE: usize>(
+    unsafe_transient: &UnsafeSlice<f32>,
+    vertical_derivative: &[f32],
+    width: u32,
+    height: u32,
+    parameter: f32,
+    start_x: u32,
+    end_x: u32,
+) {
+    let width = width as usize;
+    let height = height as usize;
+    let row_length = width * CHANNELS_CONFIGURATION;
+    let mut line = vec![0f32; height * CHANNELS_CONFIGURATION];
+    let mut derivative = vec![0f32; height];
+    let mut coordinates = Vec::new();
+    let mut sums = Vec::new();
+    for x in start_x as usize..end_x as usize {
+        let px = x * CHANNELS_CONFIGURATION;
+        for (y, (dst, d)) in line
+            .chunks_exact_mut(CHANNELS_CONFIGURATION)
+            .zip(derivative.iter_mut())
+            .enumerate()
+        {
+            *d = vertical_derivative[y * width + x];
+            for (c, dst) in dst.iter_mut().enumerate() {
+                *dst = unsafe_transient[y * row_length + px + c];
+            }
+        }
+        domain_transform_line::<CHANNELS_CONFIGURATION, MODE>(
+            &mut line,
+            &derivative,
+            parameter,
+            &mut coordinates,
+            &mut sums,
+        );
+        for (y, src) in line.chunks_exact(CHANNELS_CONFIGURATION).enumerate() {
+            for (c, &v) in src.iter().enumerate() {
+                unsafe {
+                    unsafe_transient.write(y * row_length + px + c, v);
+                }
+            }
+        }
+    }
+}
+
+fn domain_transform_passes<const CHANNELS_CONFIGURATION: usize, const MODE: usize>(
+    transient: &mut [f32],
+    horizontal_derivative: &[f32],
+    vertical_derivative: &[f32],
+    width: u32,
+    height: u32,
+    sigma_spatial: f32,
+    iterations: u32,
+    pool: &BlurPool,
+    thread_count: u32,
+) {
+    let unsafe_transient = UnsafeSlice::new(transient);
+    let unsafe_transient = &unsafe_transient;
+    for iteration in 0..iterations {
+        // Sigma of each iteration is chosen so total variance is equal to sigma_spatial^2
+        let sigma =
+            sigma_spatial as f64 * 3f64.sqrt() * 2f64.powi((iterations - iteration - 1) as i32)
+                / (4f64.powi(iterations as i32) - 1f64).sqrt();
+        let parameter = if MODE == DomainTransformMode::RecursiveFiltering as usize {
+            (-(2f64.sqrt()) / sigma) as f32
+        } else {
+            (sigma * 3f64.sqrt()) as f32
+        };
+        pool.scope(|scope| {
+            let segment_size = height / thread_count;
+            for i in 0..thread_count {
+                let start_y = i * segment_size;
+                let mut end_y = (i + 1) * segment_size;
+                if i == thread_count - 1 {
+                    end_y = height;
+                }
+                scope.spawn(move |_| {
+                    domain_transform_horizontal_pass::<CHANNELS_CONFIGURATION, MODE>(
+                        unsafe_transient,
+                        horizontal_derivative,
+                        width,
+                        parameter,
+                        start_y,
+                        end_y,
+                    );
+                });
+            }
+        });
+        pool.scope(|scope| {
+            let segment_size = width / thread_count;
+            for i in 0..thread_count {
+                let start_x = i * segment_size;
+                let mut end_x = (i + 1) * segment_size;
+                if i == thread_count - 1 {
+                    end_x = width;
+                }
+                scope.spawn(move |_| {
+                    domain_transform_vertical_pass::<CHANNELS_CONFIGURATION, MODE>(
+                        unsafe_transient,
+                        vertical_derivative,
+                        width,
+                        height,
+                        parameter,
+                        start_x,
+                        end_x,
+                    );
+                });
+            }
+        });
+    }
+}
+
+fn domain_transform_impl<T: DomainTransformValue, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    sigma_spatial: f32,
+    sigma_range: f32,
+    iterations: u32,
+    mode: DomainTransformMode,
+    threading_policy: ThreadingPolicy,
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let row_length = width as usize * CHANNELS_CONFIGURATION;
+    if sigma_spatial <= 0f32 || sigma_range <= 0f32 || iterations == 0 {
+        for (dst, src) in dst
+            .chunks_mut(dst_stride as usize)
+            .zip(src.chunks(src_stride as usize))
+        {
+            dst[..row_length].copy_from_slice(&src[..row_length]);
+        }
+        return;
+    }
+
+    let mut transient = vec![0f32; row_length * height as usize];
+    for (dst, src) in transient
+        .chunks_exact_mut(row_length)
+        .zip(src.chunks(src_stride as usize))
+    {
+        for (dst, &src) in dst.iter_mut().zip(src[..row_length].iter()) {
+            *dst = src.to_f32();
+        }
+    }
+
+    // Derivatives of the domain transform are taken from the source image
+    let ratio = sigma_spatial / sigma_range;
+    let distance = |a: &[f32], b: &[f32]| -> f32 {
+        let mut sum = 0f32;
+        for (&a, &b) in a.iter().zip(b.iter()) {
+            sum += (a - b).abs();
+        }
+        1f32 + ratio * sum
+    };
+    let mut horizontal_derivative = vec![1f32; width as usize * height as usize];
+    let mut vertical_derivative = vec![1f32; width as usize * height as usize];
+    for (y, (row, derivative)) in transient
+        .chunks_exact(row_length)
+        .zip(horizontal_derivative.chunks_exact_mut(width as usize))
+        .enumerate()
+    {
+        for x in 1..width as usize {
+            derivative[x] = distance(
+                &row[x * CHANNELS_CONFIGURATION..(x + 1) * CHANNELS_CONFIGURATION],
+                &row[(x - 1) * CHANNELS_CONFIGURATION..x * CHANNELS_CONFIGURATION],
+            );
+        }
+        if y > 0 {
+            let previous = &transient[(y - 1) * row_length..y * row_length];
+            for (x, d) in vertical_derivative[y * width as usize..(y + 1) * width as usize]
+                .iter_mut()
+                .enumerate()
+            {
+                *d = distance(
+                    &row[x * CHANNELS_CONFIGURATION..(x + 1) * CHANNELS_CONFIGURATION],
+                    &previous[x * CHANNELS_CONFIGURATION..(x + 1) * CHANNELS_CONFIGURATION],
+                );
+            }
+        }
+    }
+
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        2f32 * iterations as f32 * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    let executor = match mode {
+        DomainTransformMode::NormalizedConvolution => {
+            domain_transform_passes::<
+                CHANNELS_CONFIGURATION,
+                { DomainTransformMode::NormalizedConvolution as usize },
+            >
+        }
+        DomainTransformMode::RecursiveFiltering => {
+            domain_transform_passes::<
+                CHANNELS_CONFIGURATION,
+                { DomainTransformMode::RecursiveFiltering as usize },
+            >
+        }
+    };
+    executor(
+        &mut transient,
+        &horizontal_derivative,
+        &vertical_derivative,
+        width,
+        height,
+        sigma_spatial,
+        iterations,
+        &pool,
+        thread_count,
+    );
+
+    for (dst, src) in dst
+        .chunks_mut(dst_stride as usize)
+        .zip(transient.chunks_exact(row_length))
+    {
+        for (dst, &src) in dst[..row_length].iter_mut().zip(src.iter()) {
+            *dst = T::from_f32(src);
+        }
+    }
+}
+
+/// Performs domain transform edge-aware filter on the image.
+///
+/// E. Gastal, M. Oliveira, Domain Transform for Edge-Aware Image and Video Processing, 2011.
+/// Image is smoothed with separable 1D passes in the transformed domain where distances grow across edges.
+/// O(1) complexity on each pixel regardless of sigmas.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma_spatial` - Spatial sigma in pixels
+/// * `sigma_range` - Range sigma in levels 0..255, edges much stronger than it are preserved
+/// * `iterations` - Count of horizontal and vertical pass pairs, 3 is usually enough
+/// * `mode` - Filter variant, see [DomainTransformMode] for more info
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn domain_transform(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    sigma_spatial: f32,
+    sigma_range: f32,
+    iterations: u32,
+    mode: DomainTransformMode,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    let executor = match channels {
+        FastBlurChannels::Channels3 => domain_transform_impl::<u8, 3>,
+        FastBlurChannels::Channels4 => domain_transform_impl::<u8, 4>,
+    };
+    executor(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        sigma_spatial,
+        sigma_range,
+        iterations,
+        mode,
+        threading_policy,
+    );
+}
+
+/// Performs domain transform edge-aware filter on the f32 image.
+///
+/// E. Gastal, M. Oliveira, Domain Transform for Edge-Aware Image and Video Processing, 2011.
+/// Image is smoothed with separable 1D passes in the transformed domain where distances grow across edges.
+/// O(1) complexity on each pixel regardless of sigmas.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma_spatial` - Spatial sigma in pixels
+/// * `sigma_range` - Range sigma in the units of the image, edges much stronger than it are preserved
+/// * `iterations` - Count of horizontal and vertical pass pairs, 3 is usually enough
+/// * `mode` - Filter variant, see [DomainTransformMode] for more info
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is width/height/channel configuration do not match provided
+pub fn domain_transform_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    sigma_spatial: f32,
+    sigma_range: f32,
+    iterations: u32,
+    mode: DomainTransformMode,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    let stride = width * channels.get_channels() as u32;
+    let executor = match channels {
+        FastBlurChannels::Channels3 => domain_transform_impl::<f32, 3>,
+        FastBlurChannels::Channels4 => domain_transform_impl::<f32, 4>,
+    };
+    executor(
+        src,
+        stride,
+        dst,
+        stride,
+        width,
+        height,
+        sigma_spatial,
+        sigma_range,
+        iterations,
+        mode,
+        threading_policy,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NC: usize = DomainTransformMode::NormalizedConvolution as usize;
+    const RF: usize = DomainTransformMode::RecursiveFiltering as usize;
+
+    #[test]
+    fn normalized_convolution_line_matches_brute_force() {
+        let derivative = [1f32, 1.5, 1., 4., 1., 1., 2.5, 1., 1., 1., 7., 1.];
+        let src = (0..derivative.len() * 2)
+            .map(|i| ((i * 37) % 11) as f32)
+            .collect::<Vec<_>>();
+        let mut coordinates = Vec::new();
+        let mut sums = Vec::new();
+        for radius in [0.5f32, 2f32, 3.7f32, 40f32] {
+            let mut line = src.clone();
+            domain_transform_line::<2, NC>(
+                &mut line,
+                &derivative,
+                radius,
+                &mut coordinates,
+                &mut sums,
+            );
+            let positions = (0..derivative.len())
+                .map(|i| derivative[1..=i].iter().sum::<f32>())
+                .collect::<Vec<_>>();
+            for (i, &center) in positions.iter().enumerate() {
+                for c in 0..2 {
+                    let inside = positions
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &p)| (p - center).abs() <= radius)
+                        .map(|(j, _)| src[j * 2 + c])
+                        .collect::<Vec<_>>();
+                    let expected = inside.iter().sum::<f32>() / inside.len() as f32;
+                    assert!((line[i * 2 + c] - expected).abs() < 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn recursive_line_keeps_constant_and_stops_at_edges() {
+        let mut coordinates = Vec::new();
+        let mut sums = Vec::new();
+        let mut line = vec![3f32; 9];
+        domain_transform_line::<1, RF>(&mut line, &[1f32; 9], -0.2f32, &mut coordinates, &mut sums);
+        assert!(line.iter().all(|&v| (v - 3f32).abs() < 1e-5));
+
+        // Very large distance between samples 4 and 5 splits the line into independent parts
+        let mut line = vec![0f32, 0., 0., 0., 0., 9., 9., 9., 9.];
+        let mut derivative = [1f32; 9];
+        derivative[5] = 1e4;
+        domain_transform_line::<1, RF>(
+            &mut line,
+            &derivative,
+            -0.2f32,
+            &mut coordinates,
+            &mut sums,
+        );
+        assert!(line[..5].iter().all(|&v| v.abs() < 1e-5));
+        assert!(line[5..].iter().all(|&v| (v - 9f32).abs() < 1e-5));
+    }
+
+    fn test_image(width: usize, height: usize, channels: usize) -> Vec<u8> {
+        (0..width * height * channels)
+            .map(|i| {
+                let x = (i / channels) % width;
+                let base = if x < width / 2 { 30 } else { 210 };
+                (base + (i * 7919 + 7) % 21) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn smooths_flat_areas_and_keeps_edge() {
+        let (width, height) = (32usize, 20usize);
+        for mode in [
+            DomainTransformMode::NormalizedConvolution,
+            DomainTransformMode::RecursiveFiltering,
+        ] {
+            for channels in [FastBlurChannels::Channels3, FastBlurChannels::Channels4] {
+                let cn = channels.get_channels();
+                let src = test_image(width, height, cn);
+                let stride = width * cn + 5;
+                let mut padded = vec![0u8; stride * height];
+                for (dst, src) in padded.chunks_mut(stride).zip(src.chunks(width * cn)) {
+                    dst[..width * cn].copy_from_slice(src);
+                }
+                let mut results = Vec::new();
+                for threading_policy in [ThreadingPolicy::Single, ThreadingPolicy::Fixed(3)] {
+                    let mut dst = vec![0u8; stride * height];
+                    domain_transform(
+                        &padded,
+                        stride as u32,
+                        &mut dst,
+                        stride as u32,
+                        width as u32,
+                        height as u32,
+                        6f32,
+                        80f32,
+                        3,
+                        mode,
+                        channels,
+                        threading_policy,
+                    );
+                    results.push(dst);
+                }
+                assert_eq!(results[0], results[1]);
+                let spread = |image: &[u8], left: bool| -> f32 {
+                    let values = image
+                        .chunks(stride)
+                        .flat_map(|row| row[..width * cn].chunks(cn).enumerate())
+                        .filter(|(x, _)| (*x < width / 2) == left)
+                        .flat_map(|(_, pixel)| pixel.iter().map(|&v| v as f32))
+                        .collect::<Vec<_>>();
+                    let mean = values.iter().sum::<f32>() / values.len() as f32;
+                    values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>()
+                        / values.len() as f32
+                };
+                for left in [true, false] {
+                    assert!(spread(&results[0], left) < spread(&padded, left) * 0.5f32);
+                }
+                for row in results[0].chunks(stride) {
+                    for (x, pixel) in row[..width * cn].chunks(cn).enumerate() {
+                        for &v in pixel {
+                            if x < width / 2 {
+                                assert!((30..=50).contains(&v), "{:?} {}", mode, v);
+                            } else {
+                                assert!((210..=230).contains(&v), "{:?} {}", mode, v);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn f32_constant_image_stays_constant() {
+        let src = vec![0.375f32; 13 * 11 * 4];
+        for mode in [
+            DomainTransformMode::NormalizedConvolution,
+            DomainTransformMode::RecursiveFiltering,
+        ] {
+            let mut dst = vec![0f32; src.len()];
+            domain_transform_f32(
+                &src,
+                &mut dst,
+                13,
+                11,
+                10f32,
+                0.1f32,
+                4,
+                mode,
+                FastBlurChannels::Channels4,
+                ThreadingPolicy::Adaptive,
+            );
+            assert!(dst.iter().all(|&v| (v - 0.375f32).abs() < 1e-5));
+        }
+    }
+}
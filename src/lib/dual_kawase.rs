@@ -0,0 +1,579 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use alloc::vec;
+use alloc::vec::Vec;
+use num_traits::AsPrimitive;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::ThreadingPolicy;
+
+/// Cost of the pixel relative to stack blur for *ThreadingPolicy::get_threads_count_for_work*,
+/// every level is resampled down and up with several taps
+const DUAL_KAWASE_WORK_PER_PIXEL: f32 = 5f32;
+
+/// Samples image with bilinear interpolation, coordinates are expected in pixel space where pixel center is `i + 0.5`
+#[inline(always)]
+fn sample_bilinear<T, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: usize,
+    width: u32,
+    height: u32,
+    fx: f32,
+    fy: f32,
+) -> [f32; 4]
+where
+    T: Copy + AsPrimitive<f32>,
+{
+    let px = fx - 0.5f32;
+    let py = fy - 0.5f32;
+    let x0 = px.floor();
+    let y0 = py.floor();
+    let dx = px - x0;
+    let dy = py - y0;
+    let max_x = width as i64 - 1;
+    let max_y = height as i64 - 1;
+    let x0 = x0 as i64;
+    let y0 = y0 as i64;
+    let ix0 = x0.max(0).min(max_x) as usize * CHANNELS_CONFIGURATION;
+    let ix1 = (x0 + 1).max(0).min(max_x) as usize * CHANNELS_CONFIGURATION;
+    let iy0 = y0.max(0).min(max_y) as usize * src_stride;
+    let iy1 = (y0 + 1).max(0).min(max_y) as usize * src_stride;
+    let mut result = [0f32; 4];
+    for (c, dst) in result.iter_mut().take(CHANNELS_CONFIGURATION).enumerate() {
+        let p00: f32 = unsafe { *src.get_unchecked(iy0 + ix0 + c) }.as_();
+        let p01: f32 = unsafe { *src.get_unchecked(iy0 + ix1 + c) }.as_();
+        let p10: f32 = unsafe { *src.get_unchecked(iy1 + ix0 + c) }.as_();
+        let p11: f32 = unsafe { *src.get_unchecked(iy1 + ix1 + c) }.as_();
+        let top = p00 + (p01 - p00) * dx;
+        let bottom = p10 + (p11 - p10) * dx;
+        *dst = top + (bottom - top) * dy;
+    }
+    result
+}
+
+/// Downsample step of dual kawase, `dst` expected to be a half of `src`
+fn dual_kawase_downsample<T, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    src_width: u32,
+    src_height: u32,
+    unsafe_dst: &UnsafeSlice<f32>,
+    dst_stride: u32,
+    dst_width: u32,
+    dst_height: u32,
+    offset: f32,
+    start_y: u32,
+    end_y: u32,
+) where
+    T: Copy + AsPrimitive<f32>,
+{
+    let scale_x = src_width as f32 / dst_width as f32;
+    let scale_y = src_height as f32 / dst_height as f32;
+    for y in start_y..end_y {
+        let cy = (y as f32 + 0.5f32) * scale_y;
+        let y_dst_shift = y as usize * dst_stride as usize;
+        for x in 0..dst_width {
+            let cx = (x as f32 + 0.5f32) * scale_x;
+            let center = sample_bilinear::<T, CHANNELS_CONFIGURATION>(
+                src,
+                src_stride as usize,
+                src_width,
+                src_height,
+                cx,
+                cy,
+            );
+            let mut accumulator = center.map(|v| v * 4f32);
+            for (ox, oy) in [
+                (-offset, -offset),
+                (offset, -offset),
+                (-offset, offset),
+                (offset, offset),
+            ] {
+                let sample = sample_bilinear::<T, CHANNELS_CONFIGURATION>(
+                    src,
+                    src_stride as usize,
+                    src_width,
+                    src_height,
+                    cx + ox,
+                    cy + oy,
+                );
+                for (dst, src) in accumulator.iter_mut().zip(sample.iter()) {
+                    *dst += *src;
+                }
+            }
+            let px = y_dst_shift + x as usize * CHANNELS_CONFIGURATION;
+            for (c, &v) in accumulator.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+                unsafe {
+                    unsafe_dst.write(px + c, v * (1f32 / 8f32));
+                }
+            }
+        }
+    }
+}
+
+/// Upsample step of dual kawase, `dst` expected to be twice of `src`
+fn dual_kawase_upsample<T, const CHANNELS_CONFIGURATION: usize>(
+    src: &[f32],
+    src_stride: u32,
+    src_width: u32,
+    src_height: u32,
+    unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    dst_width: u32,
+    dst_height: u32,
+    offset: f32,
+    start_y: u32,
+    end_y: u32,
+) where
+    T: Copy + 'static,
+    f32: ToStorage<T>,
+{
+    let scale_x = src_width as f32 / dst_width as f32;
+    let scale_y = src_height as f32 / dst_height as f32;
+    let half_offset = offset * 0.5f32;
+    for y in start_y..end_y {
+        let cy = (y as f32 + 0.5f32) * scale_y;
+        let y_dst_shift = y as usize * dst_stride as usize;
+        for x in 0..dst_width {
+            let cx = (x as f32 + 0.5f32) * scale_x;
+            let mut accumulator = [0f32; 4];
+            for (ox, oy, weight) in [
+                (-offset, 0f32, 1f32),
+                (offset, 0f32, 1f32),
+                (0f32, -offset, 1f32),
+                (0f32, offset, 1f32),
+                (-half_offset, -half_offset, 2f32),
+                (half_offset, -half_offset, 2f32),
+                (-half_offset, half_offset, 2f32),
+                (half_offset, half_offset, 2f32),
+            ] {
+                let sample = sample_bilinear::<f32, CHANNELS_CONFIGURATION>(
+                    src,
+                    src_stride as usize,
+                    src_width,
+                    src_height,
+                    cx + ox,
+                    cy + oy,
+                );
+                for (dst, src) in accumulator.iter_mut().zip(sample.iter()) {
+                    *dst += *src * weight;
+                }
+            }
+            let px = y_dst_shift + x as usize * CHANNELS_CONFIGURATION;
+            for (c, &v) in accumulator.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+                unsafe {
+                    unsafe_dst.write(px + c, (v * (1f32 / 12f32)).to_());
+                }
+            }
+        }
+    }
+}
+
+fn dual_kawase_dispatch<F>(pool: &BlurPool, thread_count: u32, height: u32, worker: F)
+where
+    F: Fn(u32, u32) + Send + Sync,
+{
+    if thread_count == 1 {
+        worker(0, height);
+        return;
+    }
+    let worker = &worker;
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| {
+                worker(start_y, end_y);
+            });
+        }
+    });
+}
+
+fn dual_kawase_impl<T, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    iterations: u32,
+    offset: f32,
+    threading_policy: ThreadingPolicy,
+) where
+    T: Copy + Default + Send + Sync + AsPrimitive<f32>,
+    f32: ToStorage<T>,
+{
+    let row_length = width as usize * CHANNELS_CONFIGURATION;
+    assert!(
+        src_stride as usize >= row_length && dst_stride as usize >= row_length,
+        "Stride must be at least width * channels"
+    );
+    assert!(
+        src.len() >= src_stride as usize * height as usize,
+        "Source buffer is smaller than stride * height"
+    );
+    assert!(
+        dst.len() >= dst_stride as usize * height as usize,
+        "Destination buffer is smaller than stride * height"
+    );
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    if iterations == 0 {
+        for y in 0..height as usize {
+            let src_row = &src[y * src_stride as usize..y * src_stride as usize + row_length];
+            let dst_row = &mut dst[y * dst_stride as usize..y * dst_stride as usize + row_length];
+            dst_row.copy_from_slice(src_row);
+        }
+        return;
+    }
+
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        DUAL_KAWASE_WORK_PER_PIXEL * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+
+    let mut dimensions: Vec<(u32, u32)> = vec![(width, height)];
+    for _ in 0..iterations {
+        let (level_width, level_height) = *dimensions.last().unwrap();
+        dimensions.push((
+            core::cmp::max(level_width / 2, 1),
+            core::cmp::max(level_height / 2, 1),
+        ));
+    }
+
+    let mut levels: Vec<Vec<f32>> = dimensions[1..]
+        .iter()
+        .map(|&(w, h)| vec![0f32; w as usize * h as usize * CHANNELS_CONFIGURATION])
+        .collect();
+
+    let level_stride = |level: usize| dimensions[level].0 * CHANNELS_CONFIGURATION as u32;
+
+    {
+        let (dst_width, dst_height) = dimensions[1];
+        let unsafe_level = UnsafeSlice::new(&mut levels[0]);
+        dual_kawase_dispatch(&pool, thread_count, dst_height, |start_y, end_y| {
+            dual_kawase_downsample::<T, CHANNELS_CONFIGURATION>(
+                src,
+                src_stride,
+                width,
+                height,
+                &unsafe_level,
+                level_stride(1),
+                dst_width,
+                dst_height,
+                offset,
+                start_y,
+                end_y,
+            );
+        });
+    }
+
+    for i in 1..iterations as usize {
+        let (previous, next) = levels.split_at_mut(i);
+        let source = &previous[i - 1];
+        let (src_width, src_height) = dimensions[i];
+        let (dst_width, dst_height) = dimensions[i + 1];
+        let unsafe_level = UnsafeSlice::new(&mut next[0]);
+        dual_kawase_dispatch(&pool, thread_count, dst_height, |start_y, end_y| {
+            dual_kawase_downsample::<f32, CHANNELS_CONFIGURATION>(
+                source,
+                level_stride(i),
+                src_width,
+                src_height,
+                &unsafe_level,
+                level_stride(i + 1),
+                dst_width,
+                dst_height,
+                offset,
+                start_y,
+                end_y,
+            );
+        });
+    }
+
+    for i in (1..iterations as usize).rev() {
+        let (previous, next) = levels.split_at_mut(i);
+        let source = &next[0];
+        let (src_width, src_height) = dimensions[i + 1];
+        let (dst_width, dst_height) = dimensions[i];
+        let unsafe_level = UnsafeSlice::new(&mut previous[i - 1]);
+        dual_kawase_dispatch(&pool, thread_count, dst_height, |start_y, end_y| {
+            dual_kawase_upsample::<f32, CHANNELS_CONFIGURATION>(
+                source,
+                level_stride(i + 1),
+                src_width,
+                src_height,
+                &unsafe_level,
+                level_stride(i),
+                dst_width,
+                dst_height,
+                offset,
+                start_y,
+                end_y,
+            );
+        });
+    }
+
+    let (src_width, src_height) = dimensions[1];
+    let source = &levels[0];
+    let unsafe_dst = UnsafeSlice::new(dst);
+    dual_kawase_dispatch(&pool, thread_count, height, |start_y, end_y| {
+        dual_kawase_upsample::<T, CHANNELS_CONFIGURATION>(
+            source,
+            level_stride(1),
+            src_width,
+            src_height,
+            &unsafe_dst,
+            dst_stride,
+            width,
+            height,
+            offset,
+            start_y,
+            end_y,
+        );
+    });
+}
+
+/// Performs dual kawase blur on the image.
+///
+/// Dual kawase blur downsamples image `iterations` times with a small kernel and then upsamples it back.
+/// Produces very smooth blur with a huge effective radius at a very low cost, strength grows exponentially with `iterations`.
+/// This is a common choice for UI backdrop blur when precision of gaussian is not required.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `iterations` - Count of downsample/upsample steps, each step approximately doubles the blur radius
+/// * `offset` - Sampling offset in pixels of the current level, 1.0 is a default, larger values make blur stronger but may produce artifacts
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn dual_kawase_blur(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    iterations: u32,
+    offset: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    match channels {
+        FastBlurChannels::Channels3 => {
+            dual_kawase_impl::<u8, 3>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                iterations,
+                offset,
+                threading_policy,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            dual_kawase_impl::<u8, 4>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                iterations,
+                offset,
+                threading_policy,
+            );
+        }
+    }
+}
+
+/// Performs dual kawase blur on the image.
+///
+/// Dual kawase blur downsamples image `iterations` times with a small kernel and then upsamples it back.
+/// Produces very smooth blur with a huge effective radius at a very low cost, strength grows exponentially with `iterations`.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `iterations` - Count of downsample/upsample steps, each step approximately doubles the blur radius
+/// * `offset` - Sampling offset in pixels of the current level, 1.0 is a default, larger values make blur stronger but may produce artifacts
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+///
+/// # Panics
+/// Panic is width/height/channel configuration do not match provided
+pub fn dual_kawase_blur_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    iterations: u32,
+    offset: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    let stride = width * channels.get_channels() as u32;
+    match channels {
+        FastBlurChannels::Channels3 => {
+            dual_kawase_impl::<f32, 3>(
+                src,
+                stride,
+                dst,
+                stride,
+                width,
+                height,
+                iterations,
+                offset,
+                threading_policy,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            dual_kawase_impl::<f32, 4>(
+                src,
+                stride,
+                dst,
+                stride,
+                width,
+                height,
+                iterations,
+                offset,
+                threading_policy,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_image_stays_constant() {
+        let width = 37u32;
+        let height = 23u32;
+        let src = vec![126u8; width as usize * height as usize * 4];
+        let mut dst = vec![0u8; src.len()];
+        dual_kawase_blur(
+            &src,
+            width * 4,
+            &mut dst,
+            width * 4,
+            width,
+            height,
+            3,
+            1f32,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Single,
+        );
+        assert!(dst.iter().all(|&v| v == 126));
+    }
+
+    #[test]
+    fn impulse_is_spread() {
+        let width = 32u32;
+        let height = 32u32;
+        let mut src = vec![0f32; width as usize * height as usize * 3];
+        let center = (16 * width as usize + 16) * 3;
+        src[center] = 1000f32;
+        let mut dst = vec![0f32; src.len()];
+        dual_kawase_blur_f32(
+            &src,
+            &mut dst,
+            width,
+            height,
+            2,
+            1f32,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+        );
+        assert!(dst[center] < 1000f32);
+        assert!(dst[center + 3] > 0f32);
+        let total: f32 = dst.iter().step_by(3).sum();
+        assert!((total - 1000f32).abs() < 50f32);
+    }
+
+    #[test]
+    fn empty_image_is_noop() {
+        let mut dst: [u8; 0] = [];
+        dual_kawase_blur(
+            &[],
+            0,
+            &mut dst,
+            0,
+            0,
+            0,
+            2,
+            1f32,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Single,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_source_panics() {
+        let src = vec![0u8; 10 * 10 * 4 - 1];
+        let mut dst = vec![0u8; 10 * 10 * 4];
+        dual_kawase_blur(
+            &src,
+            40,
+            &mut dst,
+            40,
+            10,
+            10,
+            2,
+            1f32,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Single,
+        );
+    }
+}
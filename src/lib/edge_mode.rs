@@ -25,8 +25,6 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use num_traits::{AsPrimitive, Euclid, FromPrimitive, Signed};
-
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
 /// Declares an edge handling mode
 pub enum EdgeMode {
@@ -59,110 +57,288 @@ impl From<usize> for EdgeMode {
     }
 }
 
-#[inline(always)]
-pub(crate) fn reflect_index<
-    T: Copy
-        + 'static
-        + PartialOrd
-        + PartialEq
-        + std::ops::Sub<Output = T>
-        + std::ops::Mul<Output = T>
-        + Euclid
-        + FromPrimitive
-        + Signed
-        + AsPrimitive<usize>,
->(
-    i: T,
-    n: T,
-) -> usize
-where
-    i64: AsPrimitive<T>,
-{
-    let i = (i - n).rem_euclid(&(2i64.as_() * n));
-    let i = (i - n).abs();
-    return i.as_();
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Declares an edge handling mode for median and rank filters, same as *EdgeMode* with additional constant border
+pub enum RankEdgeMode {
+    /// If kernel goes out of bounds it will be clipped to an edge and edge pixel replicated across filter
+    #[default]
+    Clamp = 0,
+    /// Out of bounds pixels are not counted, window near the edge holds less pixels
+    KernelClip = 1,
+    /// If filter goes out of bounds image will be replicated with rule `cdefgh|abcdefgh|abcdefg`
+    Wrap = 2,
+    /// If filter goes out of bounds image will be replicated with rule `fedcba|abcdefgh|hgfedcb`
+    Reflect = 3,
+    /// If filter goes out of bounds image will be replicated with rule `gfedcb|abcdefgh|gfedcba`
+    Reflect101 = 4,
+    /// If filter goes out of bounds image will be padded with zeroes `000000|abcdefgh|0000000`
+    Constant = 5,
 }
 
-#[inline(always)]
-#[allow(dead_code)]
-pub(crate) fn reflect_index_101<
-    T: Copy
-        + 'static
-        + PartialOrd
-        + PartialEq
-        + std::ops::Sub<Output = T>
-        + std::ops::Mul<Output = T>
-        + Euclid
-        + FromPrimitive
-        + Signed
-        + AsPrimitive<usize>
-        + Ord,
->(
-    i: T,
-    n: T,
-) -> usize
-where
-    i64: AsPrimitive<T>,
-{
-    if i < T::from_i32(0i32).unwrap() {
-        let i = (i - n).rem_euclid(&(2i64.as_() * n));
-        let i = (i - n).abs();
-        return (i + T::from_i32(1).unwrap()).min(n).as_();
+impl From<EdgeMode> for RankEdgeMode {
+    fn from(value: EdgeMode) -> Self {
+        match value {
+            EdgeMode::Clamp => RankEdgeMode::Clamp,
+            EdgeMode::KernelClip => RankEdgeMode::KernelClip,
+            EdgeMode::Wrap => RankEdgeMode::Wrap,
+            EdgeMode::Reflect => RankEdgeMode::Reflect,
+            EdgeMode::Reflect101 => RankEdgeMode::Reflect101,
+        }
     }
-    if i > n {
-        let i = (i - n).rem_euclid(&(2i64.as_() * n));
-        let i = (i - n).abs();
-        return (i - T::from_i32(1i32).unwrap())
-            .max(T::from_i32(0i32).unwrap())
-            .as_();
+}
+
+impl RankEdgeMode {
+    /// Edge mode for *border_position*, *Constant* drops out of bounds pixels as *KernelClip* does
+    /// and filters account them as zeroes
+    #[inline(always)]
+    pub(crate) fn border(self) -> EdgeMode {
+        match self {
+            RankEdgeMode::Clamp => EdgeMode::Clamp,
+            RankEdgeMode::KernelClip | RankEdgeMode::Constant => EdgeMode::KernelClip,
+            RankEdgeMode::Wrap => EdgeMode::Wrap,
+            RankEdgeMode::Reflect => EdgeMode::Reflect,
+            RankEdgeMode::Reflect101 => EdgeMode::Reflect101,
+        }
     }
-    return i.as_();
 }
 
-#[macro_export]
-macro_rules! reflect_101 {
-    ($i:expr, $n:expr) => {{
-        if $i < 0 {
-            let i = ($i - $n).rem_euclid(2i64 * $n as i64);
-            let i = (i - $n).abs();
-            (i + 1).min($n) as usize
-        } else if $i > $n {
-            let i = ($i - $n).rem_euclid(2i64 * $n as i64);
-            let i = (i - $n).abs();
-            (i - 1).max(0) as usize
-        } else {
-            $i as usize
+/// Maps position into `0..length` according to edge mode,
+/// returns `None` if sample must be dropped for *KernelClip*
+#[inline(always)]
+pub(crate) fn border_position(position: i64, length: usize, edge_mode: EdgeMode) -> Option<usize> {
+    let length = length as i64;
+    if position >= 0 && position < length {
+        return Some(position as usize);
+    }
+    match edge_mode {
+        EdgeMode::Clamp => Some(position.clamp(0, length - 1) as usize),
+        EdgeMode::KernelClip => None,
+        EdgeMode::Wrap => Some(position.rem_euclid(length) as usize),
+        EdgeMode::Reflect => {
+            let period = 2 * length;
+            let i = position.rem_euclid(period);
+            Some(if i < length { i } else { period - 1 - i } as usize)
         }
-    }};
+        EdgeMode::Reflect101 => {
+            if length == 1 {
+                return Some(0);
+            }
+            let period = 2 * length - 2;
+            let i = position.rem_euclid(period);
+            Some(if i < length { i } else { period - i } as usize)
+        }
+    }
 }
 
-/**
+/*
     RRRRRR  OOOOO  U     U TTTTTTT IIIII NN   N EEEEEEE SSSSS
     R     R O     O U     U   T     I   I N N  N E       S
    RRRRRR  O     O U     U   T     I   I N  N N EEEEE    SSS
    R   R   O     O U     U   T     I   I N   NN E            S
    R    R   OOOOO   UUUUU    T    IIIII N    N EEEEEEE  SSSSS
-**/
+*/
 
+/// Maps position in `min..=max` range according to edge mode, *KernelClip* is treated as clamp
 #[macro_export]
 macro_rules! clamp_edge {
     ($edge_mode:expr, $value:expr, $min:expr, $max:expr) => {{
-        match $edge_mode {
-            EdgeMode::Clamp | EdgeMode::KernelClip => {
-                (std::cmp::min(std::cmp::max($value, $min), $max) as u32) as usize
+        let value: i64 = $value;
+        match $crate::edge_mode::border_position(
+            value - $min,
+            ($max - $min + 1) as usize,
+            $edge_mode,
+        ) {
+            Some(position) => position + $min as usize,
+            None => core::cmp::min(core::cmp::max(value, $min), $max) as usize,
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fast_gaussian, fast_gaussian_next, gaussian_blur, FastBlurChannels, ThreadingPolicy,
+    };
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn extend(edge_mode: EdgeMode) -> String {
+        let line = b"abcdefgh";
+        (-6i64..14)
+            .map(|i| match border_position(i, line.len(), edge_mode) {
+                Some(x) => line[x] as char,
+                None => '0',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn border_position_matches_documented_rules() {
+        assert_eq!(extend(EdgeMode::Clamp), "aaaaaaabcdefghhhhhhh");
+        assert_eq!(extend(EdgeMode::KernelClip), "000000abcdefgh000000");
+        assert_eq!(extend(EdgeMode::Wrap), "cdefghabcdefghabcdef");
+        assert_eq!(extend(EdgeMode::Reflect), "fedcbaabcdefghhgfedc");
+        assert_eq!(extend(EdgeMode::Reflect101), "gfedcbabcdefghgfedcb");
+    }
+
+    #[test]
+    fn clamp_edge_uses_border_position() {
+        for edge_mode in [
+            EdgeMode::Clamp,
+            EdgeMode::Wrap,
+            EdgeMode::Reflect,
+            EdgeMode::Reflect101,
+        ] {
+            for length in 1i64..6 {
+                for position in -20i64..20 {
+                    let mapped = clamp_edge!(edge_mode, position, 0, length - 1);
+                    assert_eq!(
+                        Some(mapped),
+                        border_position(position, length as usize, edge_mode)
+                    );
+                }
             }
-            EdgeMode::Wrap => {
-                let cx = $value.rem_euclid($max);
-                cx as usize
+        }
+        assert_eq!(clamp_edge!(EdgeMode::KernelClip, -3i64, 0, 4), 0);
+        assert_eq!(clamp_edge!(EdgeMode::KernelClip, 9i64, 0, 4), 4);
+    }
+
+    /// Extends the image by `pad` pixels on each side with *border_position*
+    fn pad_image(
+        src: &[u8],
+        width: usize,
+        height: usize,
+        pad: usize,
+        edge_mode: EdgeMode,
+    ) -> Vec<u8> {
+        let padded_width = width + 2 * pad;
+        let mut dst = vec![0u8; padded_width * (height + 2 * pad) * 3];
+        for y in 0..height + 2 * pad {
+            let sy = border_position(y as i64 - pad as i64, height, edge_mode).unwrap();
+            for x in 0..padded_width {
+                let sx = border_position(x as i64 - pad as i64, width, edge_mode).unwrap();
+                let dst_offset = (y * padded_width + x) * 3;
+                let src_offset = (sy * width + sx) * 3;
+                dst[dst_offset..dst_offset + 3].copy_from_slice(&src[src_offset..src_offset + 3]);
             }
-            EdgeMode::Reflect => {
-                let cx = reflect_index($value, $max);
-                cx as usize
+        }
+        dst
+    }
+
+    fn crop_image(padded: &[u8], width: usize, height: usize, pad: usize) -> Vec<u8> {
+        let padded_width = width + 2 * pad;
+        let mut dst = Vec::with_capacity(width * height * 3);
+        for y in pad..pad + height {
+            let offset = (y * padded_width + pad) * 3;
+            dst.extend_from_slice(&padded[offset..offset + width * 3]);
+        }
+        dst
+    }
+
+    /// Blurs the image with `edge_mode` and the same image padded with *border_position* with clamp,
+    /// inner part of the padded result must match since padding is wider than the kernel
+    fn assert_matches_padded_reference(
+        edge_mode: EdgeMode,
+        pad: usize,
+        blur: impl Fn(&[u8], &mut [u8], usize, usize, EdgeMode),
+    ) {
+        let width = 23usize;
+        let height = 17usize;
+        let src: Vec<u8> = (0..width * height * 3)
+            .map(|i| ((i * 7919 + i / 3 * 31) % 251) as u8)
+            .collect();
+        let mut dst = vec![0u8; src.len()];
+        blur(&src, &mut dst, width, height, edge_mode);
+
+        let padded = pad_image(&src, width, height, pad, edge_mode);
+        let mut padded_dst = vec![0u8; padded.len()];
+        blur(
+            &padded,
+            &mut padded_dst,
+            width + 2 * pad,
+            height + 2 * pad,
+            EdgeMode::Clamp,
+        );
+        let reference = crop_image(&padded_dst, width, height, pad);
+        for (i, (&a, &b)) in dst.iter().zip(reference.iter()).enumerate() {
+            assert!(
+                (a as i32 - b as i32).abs() <= 1,
+                "{:?} at {} got {} expected {}",
+                edge_mode,
+                i,
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn fast_gaussian_edges_match_border_position() {
+        for edge_mode in [EdgeMode::Wrap, EdgeMode::Reflect, EdgeMode::Reflect101] {
+            for radius in [3u32, 8] {
+                let pad = 3 * radius as usize;
+                assert_matches_padded_reference(edge_mode, pad, |src, dst, width, height, mode| {
+                    dst.copy_from_slice(src);
+                    fast_gaussian(
+                        dst,
+                        width as u32 * 3,
+                        width as u32,
+                        height as u32,
+                        radius,
+                        FastBlurChannels::Channels3,
+                        ThreadingPolicy::Single,
+                        mode,
+                    );
+                });
+                assert_matches_padded_reference(edge_mode, pad, |src, dst, width, height, mode| {
+                    dst.copy_from_slice(src);
+                    fast_gaussian_next(
+                        dst,
+                        width as u32 * 3,
+                        width as u32,
+                        height as u32,
+                        radius,
+                        FastBlurChannels::Channels3,
+                        ThreadingPolicy::Single,
+                        mode,
+                    );
+                });
             }
-            EdgeMode::Reflect101 => {
-                let cx = reflect_101!($value, $max);
-                cx as usize
+        }
+    }
+
+    #[test]
+    fn gaussian_edges_match_border_position() {
+        for edge_mode in [EdgeMode::Wrap, EdgeMode::Reflect, EdgeMode::Reflect101] {
+            for kernel_size in [5u32, 15] {
+                let pad = kernel_size as usize;
+                assert_matches_padded_reference(edge_mode, pad, |src, dst, width, height, mode| {
+                    gaussian_blur(
+                        src,
+                        width as u32 * 3,
+                        dst,
+                        width as u32 * 3,
+                        width as u32,
+                        height as u32,
+                        kernel_size,
+                        0f32,
+                        FastBlurChannels::Channels3,
+                        mode,
+                        ThreadingPolicy::Single,
+                    );
+                });
             }
         }
-    }};
+    }
+
+    #[test]
+    fn rank_edge_mode_constant_drops_samples() {
+        assert_eq!(RankEdgeMode::Constant.border(), EdgeMode::KernelClip);
+        assert_eq!(
+            RankEdgeMode::from(EdgeMode::Reflect101).border(),
+            EdgeMode::Reflect101
+        );
+    }
 }
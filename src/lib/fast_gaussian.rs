@@ -26,21 +26,27 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::channels_configuration::FastBlurChannels;
-use crate::edge_mode::reflect_index;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::neon::{
     fast_gaussian_horizontal_pass_neon_f32, fast_gaussian_horizontal_pass_neon_u8,
     fast_gaussian_vertical_pass_neon_f32, fast_gaussian_vertical_pass_neon_u8,
 };
+use crate::sigma::fast_gaussian_radius_for_sigma;
 #[cfg(all(
     any(target_arch = "x86_64", target_arch = "x86"),
     target_feature = "sse4.1"
 ))]
 use crate::sse::{fast_gaussian_horizontal_pass_sse_u8, fast_gaussian_vertical_pass_sse_u8};
 use crate::threading_policy::ThreadingPolicy;
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{clamp_edge, reflect_101, EdgeMode};
+use crate::BlurAlgorithm;
+use crate::{clamp_edge, EdgeMode};
+#[cfg(feature = "colorutils")]
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "colorutils")]
 use colorutils_rs::{
     linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
 };
@@ -96,7 +102,7 @@ macro_rules! write_out_blurred {
 macro_rules! impl_generic_call {
     ($store_type:ty, $channels_type:expr, $edge_mode:expr,
         $bytes:expr, $stride:expr, $width:expr, $height:expr,
-        $radius:expr, $threading_policy:expr) => {
+        $radius:expr, $pool:expr, $thread_count:expr, $edge_source:expr) => {
         match $channels_type {
             FastBlurChannels::Channels3 => {
                 fast_gaussian_impl::<$store_type, 3, $edge_mode>(
@@ -105,7 +111,9 @@ macro_rules! impl_generic_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy,
+                    $pool,
+                    $thread_count,
+                    $edge_source,
                 );
             }
             FastBlurChannels::Channels4 => {
@@ -115,7 +123,9 @@ macro_rules! impl_generic_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy,
+                    $pool,
+                    $thread_count,
+                    $edge_source,
                 );
             }
         }
@@ -125,7 +135,7 @@ macro_rules! impl_generic_call {
 macro_rules! impl_margin_call {
     ($store_type:ty, $channels_type:expr, $edge_mode:expr,
         $bytes:expr, $stride:expr, $width:expr, $height:expr,
-        $radius:expr, $threading_policy:expr) => {
+        $radius:expr, $pool:expr, $thread_count:expr, $edge_source:expr) => {
         match $edge_mode {
             EdgeMode::Clamp => {
                 impl_generic_call!(
@@ -137,7 +147,9 @@ macro_rules! impl_margin_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy
+                    $pool,
+                    $thread_count,
+                    $edge_source
                 );
             }
             EdgeMode::KernelClip => {
@@ -153,7 +165,9 @@ macro_rules! impl_margin_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy
+                    $pool,
+                    $thread_count,
+                    $edge_source
                 );
             }
             EdgeMode::Reflect => {
@@ -166,7 +180,9 @@ macro_rules! impl_margin_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy
+                    $pool,
+                    $thread_count,
+                    $edge_source
                 );
             }
             EdgeMode::Reflect101 => {
@@ -179,7 +195,9 @@ macro_rules! impl_margin_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy
+                    $pool,
+                    $thread_count,
+                    $edge_source
                 );
             }
         }
@@ -232,6 +250,7 @@ fn fast_gaussian_vertical_pass<
     const EDGE_MODE: usize,
 >(
     bytes: &UnsafeSlice<T>,
+    src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -239,9 +258,9 @@ fn fast_gaussian_vertical_pass<
     start: u32,
     end: u32,
 ) where
-    T: std::ops::AddAssign
+    T: core::ops::AddAssign
         + 'static
-        + std::ops::SubAssign
+        + core::ops::SubAssign
         + Copy
         + FromPrimitive
         + Default
@@ -250,13 +269,13 @@ fn fast_gaussian_vertical_pass<
     J: Copy
         + FromPrimitive
         + Default
-        + std::ops::Mul<Output = J>
-        + std::ops::Sub<Output = J>
-        + std::ops::Add<Output = J>
-        + std::ops::AddAssign
-        + std::ops::SubAssign
+        + core::ops::Mul<Output = J>
+        + core::ops::Sub<Output = J>
+        + core::ops::Add<Output = J>
+        + core::ops::AddAssign
+        + core::ops::SubAssign
         + AsPrimitive<M>,
-    M: Copy + FromPrimitive + std::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
+    M: Copy + FromPrimitive + core::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
     i32: AsPrimitive<J>,
 {
     let edge_mode: EdgeMode = EDGE_MODE.into();
@@ -268,7 +287,7 @@ fn fast_gaussian_vertical_pass<
     let height_wide = height as i64;
     let initial = J::from_i64(T::get_initial(radius as usize)).unwrap();
     let weight = M::from_f64(1f64 / (radius as f64 * radius as f64)).unwrap();
-    for x in start..std::cmp::min(width, end) {
+    for x in start..core::cmp::min(width, end) {
         let mut dif_r: J = 0i32.as_();
         let mut sum_r: J = initial;
         let mut dif_g: J = 0i32.as_();
@@ -319,12 +338,12 @@ fn fast_gaussian_vertical_pass<
 
             let arr_index = ((y + radius_64) & 1023) as usize;
 
-            update_sum_in!(bytes, px_idx, dif_r, sum_r, buffer_r, arr_index);
-            update_sum_in!(bytes, px_idx + 1, dif_g, sum_g, buffer_g, arr_index);
-            update_sum_in!(bytes, px_idx + 2, dif_b, sum_b, buffer_b, arr_index);
+            update_sum_in!(src, px_idx, dif_r, sum_r, buffer_r, arr_index);
+            update_sum_in!(src, px_idx + 1, dif_g, sum_g, buffer_g, arr_index);
+            update_sum_in!(src, px_idx + 2, dif_b, sum_b, buffer_b, arr_index);
 
             if CHANNELS_CONFIGURATION == 4 {
-                update_sum_in!(bytes, px_idx + 3, dif_a, sum_a, buffer_a, arr_index);
+                update_sum_in!(src, px_idx + 3, dif_a, sum_a, buffer_a, arr_index);
             }
         }
     }
@@ -342,6 +361,7 @@ fn fast_gaussian_horizontal_pass<
     const EDGE_MODE: usize,
 >(
     bytes: &UnsafeSlice<T>,
+    src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -349,9 +369,9 @@ fn fast_gaussian_horizontal_pass<
     start: u32,
     end: u32,
 ) where
-    T: std::ops::AddAssign
+    T: core::ops::AddAssign
         + 'static
-        + std::ops::SubAssign
+        + core::ops::SubAssign
         + Copy
         + FromPrimitive
         + Default
@@ -360,13 +380,13 @@ fn fast_gaussian_horizontal_pass<
     J: Copy
         + FromPrimitive
         + Default
-        + std::ops::Mul<Output = J>
-        + std::ops::Sub<Output = J>
-        + std::ops::Add<Output = J>
-        + std::ops::AddAssign
-        + std::ops::SubAssign
+        + core::ops::Mul<Output = J>
+        + core::ops::Sub<Output = J>
+        + core::ops::Add<Output = J>
+        + core::ops::AddAssign
+        + core::ops::SubAssign
         + AsPrimitive<M>,
-    M: Copy + FromPrimitive + std::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
+    M: Copy + FromPrimitive + core::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
     i32: AsPrimitive<J>,
 {
     let edge_mode: EdgeMode = EDGE_MODE.into();
@@ -383,7 +403,7 @@ fn fast_gaussian_horizontal_pass<
         FastBlurChannels::Channels4 => 4,
     };
     let initial = J::from_i64(T::get_initial(radius as usize)).unwrap();
-    for y in start..std::cmp::min(height, end) {
+    for y in start..core::cmp::min(height, end) {
         let mut dif_r: J = 0i32.as_();
         let mut sum_r: J = initial;
         let mut dif_g: J = 0i32.as_();
@@ -435,17 +455,21 @@ fn fast_gaussian_horizontal_pass<
 
             let arr_index = ((x + radius_64) & 1023) as usize;
 
-            update_sum_in!(bytes, bytes_offset, dif_r, sum_r, buffer_r, arr_index);
-            update_sum_in!(bytes, bytes_offset + 1, dif_g, sum_g, buffer_g, arr_index);
-            update_sum_in!(bytes, bytes_offset + 2, dif_b, sum_b, buffer_b, arr_index);
+            update_sum_in!(src, bytes_offset, dif_r, sum_r, buffer_r, arr_index);
+            update_sum_in!(src, bytes_offset + 1, dif_g, sum_g, buffer_g, arr_index);
+            update_sum_in!(src, bytes_offset + 2, dif_b, sum_b, buffer_b, arr_index);
 
             if CHANNELS_CONFIGURATION == 4 {
-                update_sum_in!(bytes, bytes_offset + 3, dif_a, sum_a, buffer_a, arr_index);
+                update_sum_in!(src, bytes_offset + 3, dif_a, sum_a, buffer_a, arr_index);
             }
         }
     }
 }
 
+/// Pass over the rows or columns from `start` to `end` writing into the image and reading
+/// pixels entering the kernel from the source
+type FastGaussianPass<T> = fn(&UnsafeSlice<T>, &UnsafeSlice<T>, u32, u32, u32, u32, u32, u32);
+
 fn fast_gaussian_impl<
     T: FromPrimitive + Default + Send + Sync,
     const CHANNEL_CONFIGURATION: usize,
@@ -456,10 +480,12 @@ fn fast_gaussian_impl<
     width: u32,
     height: u32,
     radius: u32,
-    threading_policy: ThreadingPolicy,
+    pool: &BlurPool,
+    thread_count: u32,
+    edge_source: Option<&mut [T]>,
 ) where
-    T: std::ops::AddAssign
-        + std::ops::SubAssign
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
         + Copy
         + AsPrimitive<i32>
         + AsPrimitive<i64>
@@ -469,34 +495,19 @@ fn fast_gaussian_impl<
     f32: AsPrimitive<T> + ToStorage<T>,
     f64: AsPrimitive<T> + ToStorage<T>,
 {
-    let unsafe_image = UnsafeSlice::new(bytes);
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-    let mut _dispatcher_vertical: fn(
-        bytes: &UnsafeSlice<T>,
-        stride: u32,
-        width: u32,
-        height: u32,
-        radius: u32,
-        start: u32,
-        end: u32,
-    ) = if BASE_RADIUS_I64_CUTOFF > radius {
+    let mut _dispatcher_vertical: FastGaussianPass<T> = if BASE_RADIUS_I64_CUTOFF > radius {
         fast_gaussian_vertical_pass::<T, i32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
     } else {
         fast_gaussian_vertical_pass::<T, i64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
     };
-    let mut _dispatcher_horizontal: fn(&UnsafeSlice<T>, u32, u32, u32, u32, u32, u32) =
-        if BASE_RADIUS_I64_CUTOFF > radius {
-            fast_gaussian_horizontal_pass::<T, i32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
-        } else {
-            fast_gaussian_horizontal_pass::<T, i64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
-        };
-    if std::any::type_name::<T>() == "f32"
-        || std::any::type_name::<T>() == "f16"
-        || std::any::type_name::<T>() == "half::f16"
+    let mut _dispatcher_horizontal: FastGaussianPass<T> = if BASE_RADIUS_I64_CUTOFF > radius {
+        fast_gaussian_horizontal_pass::<T, i32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
+    } else {
+        fast_gaussian_horizontal_pass::<T, i64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
+    };
+    if core::any::type_name::<T>() == "f32"
+        || core::any::type_name::<T>() == "f16"
+        || core::any::type_name::<T>() == "half::f16"
     {
         _dispatcher_vertical = if BASE_RADIUS_I64_CUTOFF > radius {
             fast_gaussian_vertical_pass::<T, f32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
@@ -518,7 +529,7 @@ fn fast_gaussian_impl<
     }
     #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
     {
-        if std::any::type_name::<T>() == "u8" {
+        if core::any::type_name::<T>() == "u8" {
             if BASE_RADIUS_I64_CUTOFF > radius {
                 _dispatcher_vertical =
                     fast_gaussian_vertical_pass_neon_u8::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
@@ -532,7 +543,7 @@ fn fast_gaussian_impl<
         target_feature = "sse4.1"
     ))]
     {
-        if std::any::type_name::<T>() == "u8" {
+        if core::any::type_name::<T>() == "u8" {
             if BASE_RADIUS_I64_CUTOFF > radius {
                 _dispatcher_vertical =
                     fast_gaussian_vertical_pass_sse_u8::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
@@ -541,54 +552,113 @@ fn fast_gaussian_impl<
             }
         }
     }
-    if thread_count == 1 {
-        _dispatcher_vertical(&unsafe_image, stride, width, height, radius, 0, width);
-        _dispatcher_horizontal(&unsafe_image, stride, width, height, radius, 0, height);
-    } else {
-        pool.scope(|scope| {
-            let segment_size = width / thread_count;
-
-            for i in 0..thread_count {
-                let start_x = i * segment_size;
-                let mut end_x = (i + 1) * segment_size;
-                if i == thread_count - 1 {
-                    end_x = width;
-                }
-                scope.spawn(move |_| {
-                    _dispatcher_vertical(
-                        &unsafe_image,
-                        stride,
-                        width,
-                        height,
-                        radius,
-                        start_x,
-                        end_x,
-                    );
-                });
+    // Wrap and reflect read back pixels of the far edge which in place pass has already blurred,
+    // so for them pixels entering the kernel are read from a copy made before each pass,
+    // the copy is kept in `edge_source` when caller provides it
+    let edge_copy = EDGE_MODE != EdgeMode::Clamp as usize;
+    let mut owned_source: Vec<T> = Vec::new();
+    let source: &mut [T] = match edge_source {
+        Some(edge_source) if edge_copy => &mut edge_source[..bytes.len()],
+        _ => {
+            if edge_copy {
+                owned_source.resize(bytes.len(), T::default());
             }
-        });
-        pool.scope(|scope| {
-            let segment_size = height / thread_count;
-
-            for i in 0..thread_count {
-                let start_y = i * segment_size;
-                let mut end_y = (i + 1) * segment_size;
-                if i == thread_count - 1 {
-                    end_y = height;
+            &mut owned_source
+        }
+    };
+    if edge_copy {
+        source.copy_from_slice(bytes);
+    }
+    {
+        let unsafe_image = UnsafeSlice::new(bytes);
+        let unsafe_source = if edge_copy {
+            UnsafeSlice::new(source)
+        } else {
+            unsafe_image
+        };
+        if thread_count == 1 {
+            _dispatcher_vertical(
+                &unsafe_image,
+                &unsafe_source,
+                stride,
+                width,
+                height,
+                radius,
+                0,
+                width,
+            );
+        } else {
+            pool.scope(|scope| {
+                let segment_size = width / thread_count;
+
+                for i in 0..thread_count {
+                    let start_x = i * segment_size;
+                    let mut end_x = (i + 1) * segment_size;
+                    if i == thread_count - 1 {
+                        end_x = width;
+                    }
+                    scope.spawn(move |_| {
+                        _dispatcher_vertical(
+                            &unsafe_image,
+                            &unsafe_source,
+                            stride,
+                            width,
+                            height,
+                            radius,
+                            start_x,
+                            end_x,
+                        );
+                    });
                 }
-                scope.spawn(move |_| {
-                    _dispatcher_horizontal(
-                        &unsafe_image,
-                        stride,
-                        width,
-                        height,
-                        radius,
-                        start_y,
-                        end_y,
-                    );
-                });
-            }
-        });
+            });
+        }
+    }
+    if edge_copy {
+        source.copy_from_slice(bytes);
+    }
+    {
+        let unsafe_image = UnsafeSlice::new(bytes);
+        let unsafe_source = if edge_copy {
+            UnsafeSlice::new(source)
+        } else {
+            unsafe_image
+        };
+        if thread_count == 1 {
+            _dispatcher_horizontal(
+                &unsafe_image,
+                &unsafe_source,
+                stride,
+                width,
+                height,
+                radius,
+                0,
+                height,
+            );
+        } else {
+            pool.scope(|scope| {
+                let segment_size = height / thread_count;
+
+                for i in 0..thread_count {
+                    let start_y = i * segment_size;
+                    let mut end_y = (i + 1) * segment_size;
+                    if i == thread_count - 1 {
+                        end_y = height;
+                    }
+                    scope.spawn(move |_| {
+                        _dispatcher_horizontal(
+                            &unsafe_image,
+                            &unsafe_source,
+                            stride,
+                            width,
+                            height,
+                            radius,
+                            start_y,
+                            end_y,
+                        );
+                    });
+                }
+            });
+        }
     }
 }
 
@@ -623,7 +693,41 @@ pub fn fast_gaussian(
     threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
 ) {
-    let radius = std::cmp::min(radius, 319);
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::FastGaussian.work_per_pixel(radius) * type_work_per_pixel::<u8>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    fast_gaussian_in_pool(
+        bytes,
+        stride,
+        width,
+        height,
+        radius,
+        channels,
+        edge_mode,
+        &pool,
+        thread_count,
+        None,
+    );
+}
+
+/// Performs *fast_gaussian* on u8 image in the thread pool,
+/// copy of the image required by edge modes other than clamp is kept in `edge_source` when provided
+pub(crate) fn fast_gaussian_in_pool(
+    bytes: &mut [u8],
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    pool: &BlurPool,
+    thread_count: u32,
+    edge_source: Option<&mut [u8]>,
+) {
+    let radius = core::cmp::min(radius, 319);
     impl_margin_call!(
         u8,
         channels,
@@ -633,7 +737,9 @@ pub fn fast_gaussian(
         width,
         height,
         radius,
-        threading_policy
+        pool,
+        thread_count,
+        edge_source
     );
 }
 
@@ -664,7 +770,13 @@ pub fn fast_gaussian_u16(
     threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
 ) {
-    let radius = std::cmp::min(radius, 255);
+    let radius = core::cmp::min(radius, 255);
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::FastGaussian.work_per_pixel(radius) * type_work_per_pixel::<u16>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
     impl_margin_call!(
         u16,
         channels,
@@ -674,7 +786,9 @@ pub fn fast_gaussian_u16(
         width,
         height,
         radius,
-        threading_policy
+        &pool,
+        thread_count,
+        None
     );
 }
 
@@ -703,6 +817,38 @@ pub fn fast_gaussian_f32(
     channels: FastBlurChannels,
     threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
+) {
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::FastGaussian.work_per_pixel(radius) * type_work_per_pixel::<f32>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    fast_gaussian_f32_in_pool(
+        bytes,
+        width,
+        height,
+        radius,
+        channels,
+        edge_mode,
+        &pool,
+        thread_count,
+        None,
+    );
+}
+
+/// Performs *fast_gaussian_f32* in the thread pool,
+/// copy of the image required by edge modes other than clamp is kept in `edge_source` when provided
+pub(crate) fn fast_gaussian_f32_in_pool(
+    bytes: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    pool: &BlurPool,
+    thread_count: u32,
+    edge_source: Option<&mut [f32]>,
 ) {
     impl_margin_call!(
         f32,
@@ -713,7 +859,9 @@ pub fn fast_gaussian_f32(
         width,
         height,
         radius,
-        threading_policy
+        pool,
+        thread_count,
+        edge_source
     );
 }
 
@@ -736,6 +884,7 @@ pub fn fast_gaussian_f32(
 ///
 /// # Panics
 /// Panic is stride/width/height/channel configuration do not match provided
+#[cfg(feature = "colorutils")]
 pub fn fast_gaussian_in_linear(
     in_place: &mut [u8],
     stride: u32,
@@ -764,7 +913,7 @@ pub fn fast_gaussian_in_linear(
         &in_place,
         stride,
         &mut linear_data,
-        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width * core::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         width,
         height,
         transfer_function,
@@ -782,7 +931,7 @@ pub fn fast_gaussian_in_linear(
 
     inverse_transformer(
         &linear_data,
-        width * std::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
+        width * core::mem::size_of::<f32>() as u32 * channels.get_channels() as u32,
         in_place,
         stride,
         width,
@@ -817,15 +966,101 @@ pub fn fast_gaussian_f16(
     threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
 ) {
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::FastGaussian.work_per_pixel(radius) * type_work_per_pixel::<half::f16>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
     impl_margin_call!(
         half::f16,
         channels,
         edge_mode,
-        unsafe { std::mem::transmute(bytes) },
+        unsafe { core::mem::transmute(bytes) },
         width * channels.get_channels() as u32,
         width,
         height,
         radius,
-        threading_policy
+        &pool,
+        thread_count,
+        None
+    );
+}
+
+/// Performs gaussian approximation on the image with radius derived from gaussian sigma.
+///
+/// Same as *fast_gaussian* however blur strength is set in terms of gaussian sigma,
+/// so switching between approximations keeps the same visual strength.
+/// Radius derived from sigma is limited to 319.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Gaussian sigma, radius is computed with *fast_gaussian_radius_for_sigma*
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn fast_gaussian_sigma(
+    bytes: &mut [u8],
+    stride: u32,
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    fast_gaussian(
+        bytes,
+        stride,
+        width,
+        height,
+        fast_gaussian_radius_for_sigma(sigma),
+        channels,
+        threading_policy,
+        edge_mode,
+    );
+}
+
+/// Performs gaussian approximation on the f32 image with radius derived from gaussian sigma.
+///
+/// Same as *fast_gaussian_f32* however blur strength is set in terms of gaussian sigma,
+/// so switching between approximations keeps the same visual strength.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Gaussian sigma, radius is computed with *fast_gaussian_radius_for_sigma*
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn fast_gaussian_sigma_f32(
+    bytes: &mut [f32],
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    fast_gaussian_f32(
+        bytes,
+        width,
+        height,
+        fast_gaussian_radius_for_sigma(sigma),
+        channels,
+        threading_policy,
+        edge_mode,
     );
 }
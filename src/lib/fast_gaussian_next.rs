@@ -30,7 +30,7 @@ use crate::neon::{
     fast_gaussian_next_horizontal_pass_neon_f32, fast_gaussian_next_horizontal_pass_neon_u8,
     fast_gaussian_next_vertical_pass_neon_f32, fast_gaussian_next_vertical_pass_neon_u8,
 };
-use crate::reflect_index;
+use crate::sigma::fast_gaussian_next_radius_for_sigma;
 #[cfg(all(
     any(target_arch = "x86_64", target_arch = "x86"),
     target_feature = "sse4.1"
@@ -38,14 +38,21 @@ use crate::reflect_index;
 use crate::sse::{
     fast_gaussian_next_horizontal_pass_sse_u8, fast_gaussian_next_vertical_pass_sse_u8,
 };
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{clamp_edge, reflect_101, EdgeMode, FastBlurChannels, ThreadingPolicy};
+use crate::BlurAlgorithm;
+use crate::{clamp_edge, EdgeMode, FastBlurChannels, ThreadingPolicy};
+#[cfg(feature = "colorutils")]
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "colorutils")]
 use colorutils_rs::{
     linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
 };
+#[cfg(feature = "colorutils")]
+use core::mem::size_of;
 use num_traits::{AsPrimitive, Float, FromPrimitive};
-use std::mem::size_of;
 
 const BASE_RADIUS_I64_CUTOFF: u32 = 125;
 
@@ -58,7 +65,7 @@ R    R   OOOOO   UUUUU    T    IIIII N    N EEEEEEE  SSSSS
  **/
 
 macro_rules! impl_generic_call {
-    ($store_type:ty, $channels_type:expr, $edge_mode:expr, $bytes:expr, $stride:expr, $width:expr, $height:expr, $radius:expr, $threading_policy:expr) => {
+    ($store_type:ty, $channels_type:expr, $edge_mode:expr, $bytes:expr, $stride:expr, $width:expr, $height:expr, $radius:expr, $pool:expr, $thread_count:expr, $edge_source:expr) => {
         match $channels_type {
             FastBlurChannels::Channels3 => {
                 fast_gaussian_next_impl::<$store_type, 3, $edge_mode>(
@@ -67,7 +74,9 @@ macro_rules! impl_generic_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy,
+                    $pool,
+                    $thread_count,
+                    $edge_source,
                 );
             }
             FastBlurChannels::Channels4 => {
@@ -77,7 +86,9 @@ macro_rules! impl_generic_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy,
+                    $pool,
+                    $thread_count,
+                    $edge_source,
                 );
             }
         }
@@ -85,7 +96,7 @@ macro_rules! impl_generic_call {
 }
 
 macro_rules! impl_margin_call {
-    ($store_type:ty, $channels_type:expr, $edge_mode:expr, $bytes:expr, $stride:expr, $width:expr, $height:expr, $radius:expr, $threading_policy:expr) => {
+    ($store_type:ty, $channels_type:expr, $edge_mode:expr, $bytes:expr, $stride:expr, $width:expr, $height:expr, $radius:expr, $pool:expr, $thread_count:expr, $edge_source:expr) => {
         match $edge_mode {
             EdgeMode::Clamp => {
                 impl_generic_call!(
@@ -97,7 +108,9 @@ macro_rules! impl_margin_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy
+                    $pool,
+                    $thread_count,
+                    $edge_source
                 );
             }
             EdgeMode::KernelClip => {
@@ -113,7 +126,9 @@ macro_rules! impl_margin_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy
+                    $pool,
+                    $thread_count,
+                    $edge_source
                 );
             }
             EdgeMode::Reflect => {
@@ -126,7 +141,9 @@ macro_rules! impl_margin_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy
+                    $pool,
+                    $thread_count,
+                    $edge_source
                 );
             }
             EdgeMode::Reflect101 => {
@@ -139,7 +156,9 @@ macro_rules! impl_margin_call {
                     $width,
                     $height,
                     $radius,
-                    $threading_policy
+                    $pool,
+                    $thread_count,
+                    $edge_source
                 );
             }
         }
@@ -206,6 +225,7 @@ fn fast_gaussian_next_vertical_pass<
     const EDGE_MODE: usize,
 >(
     bytes: &UnsafeSlice<T>,
+    src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -213,9 +233,9 @@ fn fast_gaussian_next_vertical_pass<
     start: u32,
     end: u32,
 ) where
-    T: std::ops::AddAssign
+    T: core::ops::AddAssign
         + 'static
-        + std::ops::SubAssign
+        + core::ops::SubAssign
         + Copy
         + FromPrimitive
         + Default
@@ -223,13 +243,13 @@ fn fast_gaussian_next_vertical_pass<
     J: Copy
         + FromPrimitive
         + Default
-        + std::ops::Mul<Output = J>
-        + std::ops::Sub<Output = J>
-        + std::ops::Add<Output = J>
-        + std::ops::AddAssign
-        + std::ops::SubAssign
+        + core::ops::Mul<Output = J>
+        + core::ops::Sub<Output = J>
+        + core::ops::Add<Output = J>
+        + core::ops::AddAssign
+        + core::ops::SubAssign
         + AsPrimitive<M>,
-    M: Copy + FromPrimitive + std::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
+    M: Copy + FromPrimitive + core::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
     i32: AsPrimitive<J>,
 {
     let edge_mode: EdgeMode = EDGE_MODE.into();
@@ -241,7 +261,7 @@ fn fast_gaussian_next_vertical_pass<
     let height_wide = height as i64;
     let weight =
         M::from_f64(1.0f64 / ((radius as f64) * (radius as f64) * (radius as f64))).unwrap();
-    for x in start..std::cmp::min(width, end) {
+    for x in start..core::cmp::min(width, end) {
         let mut dif_r: J = 0i32.as_();
         let mut der_r: J = 0i32.as_();
         let mut sum_r: J = 0i32.as_();
@@ -305,12 +325,12 @@ fn fast_gaussian_next_vertical_pass<
             let px_idx = next_row_y + next_row_x;
 
             let arr_index = ((y + 2 * radius_64) & 1023) as usize;
-            update_sum_in!(bytes, px_idx, dif_r, der_r, sum_r, buffer_r, arr_index);
-            update_sum_in!(bytes, px_idx + 1, dif_g, der_g, sum_g, buffer_g, arr_index);
-            update_sum_in!(bytes, px_idx + 2, dif_b, der_b, sum_b, buffer_b, arr_index);
+            update_sum_in!(src, px_idx, dif_r, der_r, sum_r, buffer_r, arr_index);
+            update_sum_in!(src, px_idx + 1, dif_g, der_g, sum_g, buffer_g, arr_index);
+            update_sum_in!(src, px_idx + 2, dif_b, der_b, sum_b, buffer_b, arr_index);
 
             if CHANNEL_CONFIGURATION == 4 {
-                update_sum_in!(bytes, px_idx + 3, dif_a, der_a, sum_a, buffer_a, arr_index);
+                update_sum_in!(src, px_idx + 3, dif_a, der_a, sum_a, buffer_a, arr_index);
             }
         }
     }
@@ -328,6 +348,7 @@ fn fast_gaussian_next_horizontal_pass<
     const EDGE_MODE: usize,
 >(
     bytes: &UnsafeSlice<T>,
+    src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -335,9 +356,9 @@ fn fast_gaussian_next_horizontal_pass<
     start: u32,
     end: u32,
 ) where
-    T: std::ops::AddAssign
+    T: core::ops::AddAssign
         + 'static
-        + std::ops::SubAssign
+        + core::ops::SubAssign
         + Copy
         + FromPrimitive
         + Default
@@ -345,13 +366,13 @@ fn fast_gaussian_next_horizontal_pass<
     J: Copy
         + FromPrimitive
         + Default
-        + std::ops::Mul<Output = J>
-        + std::ops::Sub<Output = J>
-        + std::ops::Add<Output = J>
-        + std::ops::AddAssign
-        + std::ops::SubAssign
+        + core::ops::Mul<Output = J>
+        + core::ops::Sub<Output = J>
+        + core::ops::Add<Output = J>
+        + core::ops::AddAssign
+        + core::ops::SubAssign
         + AsPrimitive<M>,
-    M: Copy + FromPrimitive + std::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
+    M: Copy + FromPrimitive + core::ops::Mul<Output = M> + AsPrimitive<T> + Float + ToStorage<T>,
     f32: AsPrimitive<T>,
     i32: AsPrimitive<J>,
 {
@@ -364,7 +385,7 @@ fn fast_gaussian_next_horizontal_pass<
     let width_wide = width as i64;
     let weight =
         M::from_f64(1.0f64 / ((radius as f64) * (radius as f64) * (radius as f64))).unwrap();
-    for y in start..std::cmp::min(height, end) {
+    for y in start..core::cmp::min(height, end) {
         let mut dif_r: J = 0i32.as_();
         let mut der_r: J = 0i32.as_();
         let mut sum_r: J = 0i32.as_();
@@ -429,17 +450,21 @@ fn fast_gaussian_next_horizontal_pass<
 
             let arr_index = ((x + 2 * radius_64) & 1023) as usize;
 
-            update_sum_in!(bytes, px_off, dif_r, der_r, sum_r, buffer_r, arr_index);
-            update_sum_in!(bytes, px_off + 1, dif_g, der_g, sum_g, buffer_g, arr_index);
-            update_sum_in!(bytes, px_off + 2, dif_b, der_b, sum_b, buffer_b, arr_index);
+            update_sum_in!(src, px_off, dif_r, der_r, sum_r, buffer_r, arr_index);
+            update_sum_in!(src, px_off + 1, dif_g, der_g, sum_g, buffer_g, arr_index);
+            update_sum_in!(src, px_off + 2, dif_b, der_b, sum_b, buffer_b, arr_index);
 
             if CHANNEL_CONFIGURATION == 4 {
-                update_sum_in!(bytes, px_off + 3, dif_a, der_a, sum_a, buffer_a, arr_index);
+                update_sum_in!(src, px_off + 3, dif_a, der_a, sum_a, buffer_a, arr_index);
             }
         }
     }
 }
 
+/// Pass over the rows or columns from `start` to `end` writing into the image and reading
+/// pixels entering the kernel from the source
+type FastGaussianPass<T> = fn(&UnsafeSlice<T>, &UnsafeSlice<T>, u32, u32, u32, u32, u32, u32);
+
 fn fast_gaussian_next_impl<
     T: FromPrimitive + Default + Send + Sync,
     const CHANNEL_CONFIGURATION: usize,
@@ -450,10 +475,12 @@ fn fast_gaussian_next_impl<
     width: u32,
     height: u32,
     radius: u32,
-    threading_policy: ThreadingPolicy,
+    pool: &BlurPool,
+    thread_count: u32,
+    edge_source: Option<&mut [T]>,
 ) where
-    T: std::ops::AddAssign
-        + std::ops::SubAssign
+    T: core::ops::AddAssign
+        + core::ops::SubAssign
         + Copy
         + AsPrimitive<f32>
         + AsPrimitive<f64>
@@ -463,36 +490,20 @@ fn fast_gaussian_next_impl<
     f32: AsPrimitive<T> + ToStorage<T>,
     f64: AsPrimitive<T> + ToStorage<T>,
 {
-    let mut _dispatcher_vertical: fn(
-        bytes: &UnsafeSlice<T>,
-        stride: u32,
-        width: u32,
-        height: u32,
-        radius: u32,
-        start: u32,
-        end: u32,
-    ) = if BASE_RADIUS_I64_CUTOFF > radius {
+    let mut _dispatcher_vertical: FastGaussianPass<T> = if BASE_RADIUS_I64_CUTOFF > radius {
         fast_gaussian_next_vertical_pass::<T, i32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
     } else {
         fast_gaussian_next_vertical_pass::<T, i64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
     };
-    let mut _dispatcher_horizontal: fn(
-        bytes: &UnsafeSlice<T>,
-        stride: u32,
-        width: u32,
-        height: u32,
-        radius: u32,
-        start: u32,
-        end: u32,
-    ) = if BASE_RADIUS_I64_CUTOFF > radius {
+    let mut _dispatcher_horizontal: FastGaussianPass<T> = if BASE_RADIUS_I64_CUTOFF > radius {
         fast_gaussian_next_horizontal_pass::<T, i32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
     } else {
         fast_gaussian_next_horizontal_pass::<T, i64, f64, CHANNEL_CONFIGURATION, EDGE_MODE>
     };
 
-    if std::any::type_name::<T>() == "f32"
-        || std::any::type_name::<T>() == "f16"
-        || std::any::type_name::<T>() == "half::f16"
+    if core::any::type_name::<T>() == "f32"
+        || core::any::type_name::<T>() == "f16"
+        || core::any::type_name::<T>() == "half::f16"
     {
         _dispatcher_vertical = if BASE_RADIUS_I64_CUTOFF > radius {
             fast_gaussian_next_vertical_pass::<T, f32, f32, CHANNEL_CONFIGURATION, EDGE_MODE>
@@ -509,7 +520,7 @@ fn fast_gaussian_next_impl<
     #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
     {
         if BASE_RADIUS_I64_CUTOFF > radius {
-            if std::any::type_name::<T>() == "u8" {
+            if core::any::type_name::<T>() == "u8" {
                 _dispatcher_vertical =
                     fast_gaussian_next_vertical_pass_neon_u8::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
                 _dispatcher_horizontal = fast_gaussian_next_horizontal_pass_neon_u8::<
@@ -517,7 +528,7 @@ fn fast_gaussian_next_impl<
                     CHANNEL_CONFIGURATION,
                     EDGE_MODE,
                 >;
-            } else if std::any::type_name::<T>() == "f32" {
+            } else if core::any::type_name::<T>() == "f32" {
                 _dispatcher_horizontal = fast_gaussian_next_horizontal_pass_neon_f32::<
                     T,
                     CHANNEL_CONFIGURATION,
@@ -537,7 +548,7 @@ fn fast_gaussian_next_impl<
     ))]
     {
         if BASE_RADIUS_I64_CUTOFF > radius {
-            if std::any::type_name::<T>() == "u8" {
+            if core::any::type_name::<T>() == "u8" {
                 _dispatcher_vertical =
                     fast_gaussian_next_vertical_pass_sse_u8::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
                 _dispatcher_horizontal = fast_gaussian_next_horizontal_pass_sse_u8::<
@@ -548,50 +559,89 @@ fn fast_gaussian_next_impl<
             }
         }
     }
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-
-    let unsafe_image = UnsafeSlice::new(bytes);
-    pool.scope(|scope| {
-        let segment_size = width / thread_count;
-
-        for i in 0..thread_count {
-            let start_x = i * segment_size;
-            let mut end_x = (i + 1) * segment_size;
-            if i == thread_count - 1 {
-                end_x = width;
+
+    // Wrap and reflect read back pixels of the far edge which in place pass has already blurred,
+    // so for them pixels entering the kernel are read from a copy made before each pass,
+    // the copy is kept in `edge_source` when caller provides it
+    let edge_copy = EDGE_MODE != EdgeMode::Clamp as usize;
+    let mut owned_source: Vec<T> = Vec::new();
+    let source: &mut [T] = match edge_source {
+        Some(edge_source) if edge_copy => &mut edge_source[..bytes.len()],
+        _ => {
+            if edge_copy {
+                owned_source.resize(bytes.len(), T::default());
             }
-            scope.spawn(move |_| {
-                _dispatcher_vertical(&unsafe_image, stride, width, height, radius, start_x, end_x);
-            });
+            &mut owned_source
         }
-    });
+    };
+    if edge_copy {
+        source.copy_from_slice(bytes);
+    }
+    {
+        let unsafe_image = UnsafeSlice::new(bytes);
+        let unsafe_source = if edge_copy {
+            UnsafeSlice::new(source)
+        } else {
+            unsafe_image
+        };
+        pool.scope(|scope| {
+            let segment_size = width / thread_count;
 
-    pool.scope(|scope| {
-        let segment_size = height / thread_count;
+            for i in 0..thread_count {
+                let start_x = i * segment_size;
+                let mut end_x = (i + 1) * segment_size;
+                if i == thread_count - 1 {
+                    end_x = width;
+                }
+                scope.spawn(move |_| {
+                    _dispatcher_vertical(
+                        &unsafe_image,
+                        &unsafe_source,
+                        stride,
+                        width,
+                        height,
+                        radius,
+                        start_x,
+                        end_x,
+                    );
+                });
+            }
+        });
+    }
+    if edge_copy {
+        source.copy_from_slice(bytes);
+    }
+    {
+        let unsafe_image = UnsafeSlice::new(bytes);
+        let unsafe_source = if edge_copy {
+            UnsafeSlice::new(source)
+        } else {
+            unsafe_image
+        };
+        pool.scope(|scope| {
+            let segment_size = height / thread_count;
 
-        for i in 0..thread_count {
-            let start_y = i * segment_size;
-            let mut end_y = (i + 1) * segment_size;
-            if i == thread_count - 1 {
-                end_y = height;
+            for i in 0..thread_count {
+                let start_y = i * segment_size;
+                let mut end_y = (i + 1) * segment_size;
+                if i == thread_count - 1 {
+                    end_y = height;
+                }
+                scope.spawn(move |_| {
+                    _dispatcher_horizontal(
+                        &unsafe_image,
+                        &unsafe_source,
+                        stride,
+                        width,
+                        height,
+                        radius,
+                        start_y,
+                        end_y,
+                    );
+                });
             }
-            scope.spawn(move |_| {
-                _dispatcher_horizontal(
-                    &unsafe_image,
-                    stride,
-                    width,
-                    height,
-                    radius,
-                    start_y,
-                    end_y,
-                );
-            });
-        }
-    });
+        });
+    }
 }
 
 /// Performs gaussian approximation on the image.
@@ -622,7 +672,41 @@ pub fn fast_gaussian_next(
     threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
 ) {
-    let radius = std::cmp::min(radius, 280);
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::FastGaussianNext.work_per_pixel(radius) * type_work_per_pixel::<u8>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    fast_gaussian_next_in_pool(
+        bytes,
+        stride,
+        width,
+        height,
+        radius,
+        channels,
+        edge_mode,
+        &pool,
+        thread_count,
+        None,
+    );
+}
+
+/// Performs *fast_gaussian_next* on u8 image in the thread pool,
+/// copy of the image required by edge modes other than clamp is kept in `edge_source` when provided
+pub(crate) fn fast_gaussian_next_in_pool(
+    bytes: &mut [u8],
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    pool: &BlurPool,
+    thread_count: u32,
+    edge_source: Option<&mut [u8]>,
+) {
+    let radius = core::cmp::min(radius, 280);
     impl_margin_call!(
         u8,
         channels,
@@ -632,7 +716,9 @@ pub fn fast_gaussian_next(
         width,
         height,
         radius,
-        threading_policy
+        pool,
+        thread_count,
+        edge_source
     );
 }
 
@@ -664,7 +750,13 @@ pub fn fast_gaussian_next_u16(
     threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
 ) {
-    let acq_radius = std::cmp::min(radius, 152);
+    let acq_radius = core::cmp::min(radius, 152);
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::FastGaussianNext.work_per_pixel(radius) * type_work_per_pixel::<u16>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
     impl_margin_call!(
         u16,
         channels,
@@ -674,7 +766,9 @@ pub fn fast_gaussian_next_u16(
         width,
         height,
         acq_radius,
-        threading_policy
+        &pool,
+        thread_count,
+        None
     );
 }
 
@@ -703,6 +797,38 @@ pub fn fast_gaussian_next_f32(
     channels: FastBlurChannels,
     threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
+) {
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::FastGaussianNext.work_per_pixel(radius) * type_work_per_pixel::<f32>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    fast_gaussian_next_f32_in_pool(
+        bytes,
+        width,
+        height,
+        radius,
+        channels,
+        edge_mode,
+        &pool,
+        thread_count,
+        None,
+    );
+}
+
+/// Performs *fast_gaussian_next_f32* in the thread pool,
+/// copy of the image required by edge modes other than clamp is kept in `edge_source` when provided
+pub(crate) fn fast_gaussian_next_f32_in_pool(
+    bytes: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    pool: &BlurPool,
+    thread_count: u32,
+    edge_source: Option<&mut [f32]>,
 ) {
     impl_margin_call!(
         f32,
@@ -713,7 +839,9 @@ pub fn fast_gaussian_next_f32(
         width,
         height,
         radius,
-        threading_policy
+        pool,
+        thread_count,
+        edge_source
     );
 }
 
@@ -743,16 +871,24 @@ pub fn fast_gaussian_next_f16(
     threading_policy: ThreadingPolicy,
     edge_mode: EdgeMode,
 ) {
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::FastGaussianNext.work_per_pixel(radius) * type_work_per_pixel::<half::f16>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
     impl_margin_call!(
         half::f16,
         channels,
         edge_mode,
-        unsafe { std::mem::transmute(bytes) },
+        unsafe { core::mem::transmute(bytes) },
         width * channels.get_channels() as u32,
         width,
         height,
         radius,
-        threading_policy
+        &pool,
+        thread_count,
+        None
     );
 }
 
@@ -774,6 +910,7 @@ pub fn fast_gaussian_next_f16(
 ///
 /// # Panics
 /// Panic is stride/width/height/channel configuration do not match provided
+#[cfg(feature = "colorutils")]
 pub fn fast_gaussian_next_in_linear(
     in_place: &mut [u8],
     stride: u32,
@@ -828,3 +965,81 @@ pub fn fast_gaussian_next_in_linear(
         transfer_function,
     );
 }
+
+/// Performs gaussian approximation on the image with radius derived from gaussian sigma.
+///
+/// Same as *fast_gaussian_next* however blur strength is set in terms of gaussian sigma,
+/// so switching between approximations keeps the same visual strength.
+/// Radius derived from sigma is limited to 280.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Gaussian sigma, radius is computed with *fast_gaussian_next_radius_for_sigma*
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn fast_gaussian_next_sigma(
+    bytes: &mut [u8],
+    stride: u32,
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    fast_gaussian_next(
+        bytes,
+        stride,
+        width,
+        height,
+        fast_gaussian_next_radius_for_sigma(sigma),
+        channels,
+        threading_policy,
+        edge_mode,
+    );
+}
+
+/// Performs gaussian approximation on the f32 image with radius derived from gaussian sigma.
+///
+/// Same as *fast_gaussian_next_f32* however blur strength is set in terms of gaussian sigma,
+/// so switching between approximations keeps the same visual strength.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Gaussian sigma, radius is computed with *fast_gaussian_next_radius_for_sigma*
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn fast_gaussian_next_sigma_f32(
+    bytes: &mut [f32],
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    fast_gaussian_next_f32(
+        bytes,
+        width,
+        height,
+        fast_gaussian_next_radius_for_sigma(sigma),
+        channels,
+        threading_policy,
+        edge_mode,
+    );
+}
@@ -25,13 +25,15 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use crate::BlurAlgorithm;
 use crate::{FastBlurChannels, ThreadingPolicy};
 
 mod fast_gaussian_superior {
     use num_traits::{FromPrimitive, ToPrimitive};
 
+    use crate::threading_policy::BlurPool;
     use crate::unsafe_slice::UnsafeSlice;
-    use crate::ThreadingPolicy;
 
     fn fast_gaussian_vertical_pass<
         T: FromPrimitive + ToPrimitive + Default + Into<i64> + Send + Sync,
@@ -45,7 +47,7 @@ mod fast_gaussian_superior {
         start: u32,
         end: u32,
     ) where
-        T: std::ops::AddAssign + std::ops::SubAssign + Copy,
+        T: core::ops::AddAssign + core::ops::SubAssign + Copy,
     {
         let mut buffer_r: [i64; 2048] = [0; 2048];
         let mut buffer_g: [i64; 2048] = [0; 2048];
@@ -55,7 +57,7 @@ mod fast_gaussian_superior {
         let height_wide = height as i64;
         let radius_2d = (radius as f64) * (radius as f64);
         let weight = 1.0f64 / (radius_2d * radius_2d);
-        for x in start..std::cmp::min(width, end) {
+        for x in start..core::cmp::min(width, end) {
             let mut dif_r: i64 = 0;
             let mut der_1_r: i64 = 0;
             let mut der_2_r: i64 = 0;
@@ -156,7 +158,7 @@ mod fast_gaussian_superior {
                 }
 
                 let next_row_y =
-                    (std::cmp::min(std::cmp::max(y + 2 * radius_64 - 1, 0), height_wide - 1)
+                    (core::cmp::min(core::cmp::max(y + 2 * radius_64 - 1, 0), height_wide - 1)
                         as usize)
                         * (stride as usize);
                 let next_row_x = x as usize * CHANNELS_COUNT;
@@ -219,7 +221,7 @@ mod fast_gaussian_superior {
         start: u32,
         end: u32,
     ) where
-        T: std::ops::AddAssign + std::ops::SubAssign + Copy,
+        T: core::ops::AddAssign + core::ops::SubAssign + Copy,
     {
         let mut buffer_r: [i64; 2048] = [0; 2048];
         let mut buffer_g: [i64; 2048] = [0; 2048];
@@ -229,7 +231,7 @@ mod fast_gaussian_superior {
         let width_wide = width as i64;
         let radius_2d = (radius as f64) * (radius as f64);
         let weight = 1.0f64 / (radius_2d * radius_2d);
-        for y in start..std::cmp::min(height, end) {
+        for y in start..core::cmp::min(height, end) {
             let mut dif_r: i64 = 0;
             let mut der_1_r: i64 = 0;
             let mut der_2_r: i64 = 0;
@@ -251,7 +253,7 @@ mod fast_gaussian_superior {
 
             for x in (0i64 - 4i64 * radius_64)..(width as i64) {
                 if x >= 0 {
-                    let current_px = (std::cmp::max(x, 0) as u32) as usize * CHANNELS_COUNT;
+                    let current_px = (core::cmp::max(x, 0) as u32) as usize * CHANNELS_COUNT;
                     let new_r = T::from_u32(((sum_r as f64) * weight) as u32).unwrap_or_default();
                     let new_g = T::from_u32(((sum_g as f64) * weight) as u32).unwrap_or_default();
                     let new_b = T::from_u32(((sum_b as f64) * weight) as u32).unwrap_or_default();
@@ -330,8 +332,8 @@ mod fast_gaussian_superior {
 
                 let next_row_y = (y as usize) * (stride as usize);
                 let next_row_x =
-                    (std::cmp::min(std::cmp::max(x + 2 * radius_64 - 1, 0), width_wide - 1) as u32)
-                        as usize
+                    (core::cmp::min(core::cmp::max(x + 2 * radius_64 - 1, 0), width_wide - 1)
+                        as u32) as usize
                         * CHANNELS_COUNT;
 
                 let bytes_offset = next_row_y + next_row_x;
@@ -389,16 +391,12 @@ mod fast_gaussian_superior {
         width: u32,
         height: u32,
         radius: u32,
-        threading_policy: ThreadingPolicy,
+        pool: &BlurPool,
+        thread_count: u32,
     ) where
-        T: std::ops::AddAssign + std::ops::SubAssign + Copy,
+        T: core::ops::AddAssign + core::ops::SubAssign + Copy,
     {
         let unsafe_image = UnsafeSlice::new(bytes);
-        let thread_count = threading_policy.get_threads_count(width, height) as u32;
-        let pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(thread_count as usize)
-            .build()
-            .unwrap();
         pool.scope(|scope| {
             let segment_size = width / thread_count;
 
@@ -461,7 +459,36 @@ pub fn fast_gaussian_superior(
     channels: FastBlurChannels,
     threading_policy: ThreadingPolicy,
 ) {
-    let acq_radius = std::cmp::min(radius, 256);
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::FastGaussianSuperior.work_per_pixel(radius) * type_work_per_pixel::<u8>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    fast_gaussian_superior_in_pool(
+        bytes,
+        stride,
+        width,
+        height,
+        radius,
+        channels,
+        &pool,
+        thread_count,
+    );
+}
+
+/// Performs *fast_gaussian_superior* in the thread pool
+pub(crate) fn fast_gaussian_superior_in_pool(
+    bytes: &mut [u8],
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    pool: &BlurPool,
+    thread_count: u32,
+) {
+    let acq_radius = core::cmp::min(radius, 256);
     match channels {
         FastBlurChannels::Channels3 => {
             fast_gaussian_superior::fast_gaussian_impl::<u8, 3>(
@@ -470,7 +497,8 @@ pub fn fast_gaussian_superior(
                 width,
                 height,
                 acq_radius,
-                threading_policy,
+                pool,
+                thread_count,
             );
         }
         FastBlurChannels::Channels4 => {
@@ -480,7 +508,8 @@ pub fn fast_gaussian_superior(
                 width,
                 height,
                 acq_radius,
-                threading_policy,
+                pool,
+                thread_count,
             );
         }
     }
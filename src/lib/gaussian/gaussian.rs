@@ -25,16 +25,21 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem::size_of;
 use num_traits::cast::FromPrimitive;
 use num_traits::AsPrimitive;
-use rayon::ThreadPool;
 
+use crate::blur_scratch::aligned_scratch;
 use crate::channels_configuration::FastBlurChannels;
 use crate::edge_mode::EdgeMode;
 use crate::gaussian::gaussian_f16::gaussian_f16::gaussian_blur_impl_f16;
-use crate::gaussian::gaussian_filter::create_filter;
+use crate::gaussian::gaussian_fft::{gaussian_blur_fft_f32, GAUSSIAN_FFT_KERNEL_CUTOFF};
+use crate::gaussian::gaussian_filter::{create_filter, GaussianFilter};
 use crate::gaussian::gaussian_horizontal::gaussian_blur_horizontal_pass_impl;
-use crate::gaussian::gaussian_kernel::get_gaussian_kernel_1d;
+use crate::gaussian::gaussian_kernel::{fill_gaussian_kernel_1d, get_gaussian_kernel_1d};
 use crate::gaussian::gaussian_kernel_filter_dispatch::{
     gaussian_blur_horizontal_pass_edge_clip_dispatch,
     gaussian_blur_vertical_pass_edge_clip_dispatch,
@@ -51,6 +56,7 @@ use crate::gaussian::gaussian_sse::sse_support::{
 use crate::gaussian::gaussian_vertical::gaussian_blur_vertical_pass_c_impl;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
+use crate::BlurAlgorithm;
 use crate::ThreadingPolicy;
 
 fn gaussian_blur_horizontal_pass<
@@ -65,11 +71,11 @@ fn gaussian_blur_horizontal_pass<
     width: u32,
     height: u32,
     kernel_size: usize,
-    kernel: &Vec<f32>,
-    thread_pool: &ThreadPool,
+    kernel: &[f32],
+    thread_pool: &BlurPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     let mut _dispatcher: fn(
@@ -84,7 +90,7 @@ fn gaussian_blur_horizontal_pass<
         end_y: u32,
     ) = gaussian_blur_horizontal_pass_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    if std::any::type_name::<T>() == "u8" && edge_mode == EdgeMode::Clamp {
+    if core::any::type_name::<T>() == "u8" && edge_mode == EdgeMode::Clamp {
         #[cfg(all(
             any(target_arch = "x86_64", target_arch = "x86"),
             target_feature = "sse4.1"
@@ -141,7 +147,7 @@ fn gaussian_blur_vertical_pass_impl<
     start_y: u32,
     end_y: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     gaussian_blur_vertical_pass_c_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
@@ -170,11 +176,11 @@ fn gaussian_blur_vertical_pass<
     width: u32,
     height: u32,
     kernel_size: usize,
-    kernel: &Vec<f32>,
-    thread_pool: &ThreadPool,
+    kernel: &[f32],
+    thread_pool: &BlurPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     let mut _dispatcher: fn(
@@ -190,7 +196,7 @@ fn gaussian_blur_vertical_pass<
         end_y: u32,
     ) = gaussian_blur_vertical_pass_impl::<T, CHANNEL_CONFIGURATION, EDGE_MODE>;
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    if std::any::type_name::<T>() == "u8" && edge_mode == EdgeMode::Clamp {
+    if core::any::type_name::<T>() == "u8" && edge_mode == EdgeMode::Clamp {
         #[cfg(all(
             any(target_arch = "x86_64", target_arch = "x86"),
             target_feature = "sse4.1"
@@ -233,7 +239,36 @@ fn gaussian_blur_vertical_pass<
     });
 }
 
-fn gaussian_blur_impl<
+/// Gaussian kernel prepared for the image size and the edge mode
+pub(crate) enum GaussianKernel {
+    /// Same kernel for each pixel
+    Kernel(Vec<f32>),
+    /// Kernels clipped by the image edges for each column and each row
+    Filters(Vec<GaussianFilter>, Vec<GaussianFilter>),
+}
+
+pub(crate) fn create_gaussian_kernel(
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+) -> GaussianKernel {
+    if kernel_size % 2 == 0 {
+        panic!("kernel size must be odd");
+    }
+    match edge_mode {
+        EdgeMode::KernelClip => GaussianKernel::Filters(
+            create_filter(width as usize, kernel_size, sigma),
+            create_filter(height as usize, kernel_size, sigma),
+        ),
+        _ => GaussianKernel::Kernel(get_gaussian_kernel_1d(kernel_size, sigma)),
+    }
+}
+
+/// Performs gaussian blur with prepared kernel, transient buffer and thread pool,
+/// transient must have `dst_stride * height` elements
+pub(crate) fn gaussian_blur_in_pool<
     T: FromPrimitive + Default + Into<f32> + Send + Sync,
     const CHANNEL_CONFIGURATION: usize,
 >(
@@ -243,124 +278,176 @@ fn gaussian_blur_impl<
     dst_stride: u32,
     width: u32,
     height: u32,
-    kernel_size: u32,
-    sigma: f32,
-    threading_policy: ThreadingPolicy,
+    kernel: &GaussianKernel,
+    transient: &mut [T],
     edge_mode: EdgeMode,
+    pool: &BlurPool,
+    thread_count: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
-    if kernel_size % 2 == 0 {
-        panic!("kernel size must be odd");
+    match kernel {
+        GaussianKernel::Kernel(kernel) => {
+            gaussian_blur_kernel_in_pool::<T, CHANNEL_CONFIGURATION>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                kernel,
+                transient,
+                edge_mode,
+                pool,
+                thread_count,
+            );
+        }
+        GaussianKernel::Filters(horizontal_filter, vertical_filter) => {
+            if edge_mode != EdgeMode::KernelClip {
+                panic!("Gaussian kernel does not match edge mode")
+            }
+            gaussian_blur_horizontal_pass_edge_clip_dispatch::<T, CHANNEL_CONFIGURATION>(
+                src,
+                src_stride,
+                transient,
+                dst_stride,
+                width,
+                height,
+                horizontal_filter,
+                pool,
+                thread_count,
+            );
+            gaussian_blur_vertical_pass_edge_clip_dispatch::<T, CHANNEL_CONFIGURATION>(
+                transient,
+                dst_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                vertical_filter,
+                pool,
+                thread_count,
+            );
+        }
     }
-    let mut transient: Vec<T> =
-        vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
-
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+}
 
+/// Performs gaussian blur with the same kernel for each pixel, prepared transient buffer and thread pool,
+/// transient must have `dst_stride * height` elements, kernel may be borrowed from the scratch of the caller
+pub(crate) fn gaussian_blur_kernel_in_pool<
+    T: FromPrimitive + Default + Into<f32> + Send + Sync,
+    const CHANNEL_CONFIGURATION: usize,
+>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    kernel: &[f32],
+    transient: &mut [T],
+    edge_mode: EdgeMode,
+    pool: &BlurPool,
+    thread_count: u32,
+) where
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
     match edge_mode {
         EdgeMode::Reflect => {
-            let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
             gaussian_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Reflect as usize }>(
-                &src,
+                src,
                 src_stride,
-                &mut transient,
+                transient,
                 dst_stride,
                 width,
                 height,
                 kernel.len(),
-                &kernel,
-                &pool,
+                kernel,
+                pool,
                 thread_count,
             );
             gaussian_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Reflect as usize }>(
-                &transient,
+                transient,
                 dst_stride,
                 dst,
                 dst_stride,
                 width,
                 height,
                 kernel.len(),
-                &kernel,
-                &pool,
+                kernel,
+                pool,
                 thread_count,
             );
         }
         EdgeMode::Wrap => {
-            let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
             gaussian_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Wrap as usize }>(
-                &src,
+                src,
                 src_stride,
-                &mut transient,
+                transient,
                 dst_stride,
                 width,
                 height,
                 kernel.len(),
-                &kernel,
-                &pool,
+                kernel,
+                pool,
                 thread_count,
             );
             gaussian_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Wrap as usize }>(
-                &transient,
+                transient,
                 dst_stride,
                 dst,
                 dst_stride,
                 width,
                 height,
                 kernel.len(),
-                &kernel,
-                &pool,
+                kernel,
+                pool,
                 thread_count,
             );
         }
         EdgeMode::Clamp => {
-            let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
             gaussian_blur_horizontal_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Clamp as usize }>(
-                &src,
+                src,
                 src_stride,
-                &mut transient,
+                transient,
                 dst_stride,
                 width,
                 height,
                 kernel.len(),
-                &kernel,
-                &pool,
+                kernel,
+                pool,
                 thread_count,
             );
             gaussian_blur_vertical_pass::<T, CHANNEL_CONFIGURATION, { EdgeMode::Clamp as usize }>(
-                &transient,
+                transient,
                 dst_stride,
                 dst,
                 dst_stride,
                 width,
                 height,
                 kernel.len(),
-                &kernel,
-                &pool,
+                kernel,
+                pool,
                 thread_count,
             );
         }
         EdgeMode::Reflect101 => {
-            let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
             gaussian_blur_horizontal_pass::<
                 T,
                 CHANNEL_CONFIGURATION,
                 { EdgeMode::Reflect101 as usize },
             >(
-                &src,
+                src,
                 src_stride,
-                &mut transient,
+                transient,
                 dst_stride,
                 width,
                 height,
                 kernel.len(),
-                &kernel,
-                &pool,
+                kernel,
+                pool,
                 thread_count,
             );
             gaussian_blur_vertical_pass::<
@@ -368,47 +455,68 @@ fn gaussian_blur_impl<
                 CHANNEL_CONFIGURATION,
                 { EdgeMode::Reflect101 as usize },
             >(
-                &transient,
+                transient,
                 dst_stride,
                 dst,
                 dst_stride,
                 width,
                 height,
                 kernel.len(),
-                &kernel,
-                &pool,
+                kernel,
+                pool,
                 thread_count,
             );
         }
         EdgeMode::KernelClip => {
-            let horizontal_filter = create_filter(width as usize, kernel_size, sigma);
-            let vertical_filter = create_filter(height as usize, kernel_size, sigma);
-            gaussian_blur_horizontal_pass_edge_clip_dispatch::<T, CHANNEL_CONFIGURATION>(
-                &src,
-                dst_stride,
-                &mut transient,
-                dst_stride,
-                width,
-                height,
-                &horizontal_filter,
-                &pool,
-                thread_count,
-            );
-            gaussian_blur_vertical_pass_edge_clip_dispatch::<T, CHANNEL_CONFIGURATION>(
-                &transient,
-                dst_stride,
-                dst,
-                dst_stride,
-                width,
-                height,
-                &vertical_filter,
-                &pool,
-                thread_count,
-            );
+            panic!("Gaussian kernel does not match edge mode")
         }
     }
 }
 
+fn gaussian_blur_impl<
+    T: FromPrimitive + Default + Into<f32> + Send + Sync,
+    const CHANNEL_CONFIGURATION: usize,
+>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) where
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    let kernel = create_gaussian_kernel(width, height, kernel_size, sigma, edge_mode);
+    let mut transient: Vec<T> =
+        vec![T::from_u32(0).unwrap_or_default(); dst_stride as usize * height as usize];
+
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Gaussian.work_per_pixel(kernel_size / 2) * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+
+    gaussian_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        &kernel,
+        &mut transient,
+        edge_mode,
+        &pool,
+        thread_count,
+    );
+}
+
 /// Performs gaussian blur on the image.
 ///
 /// This performs a gaussian kernel filter on the image producing beautiful looking result.
@@ -550,6 +658,9 @@ pub fn gaussian_blur_u16(
 /// * `edge_mode` - Rule to handle edge mode
 /// * `threading_policy` - Threading policy according to *ThreadingPolicy*
 ///
+/// Kernels larger than *GAUSSIAN_FFT_KERNEL_CUTOFF* ( 201 ) are convolved in frequency domain using FFT,
+/// in this case complexity is O(log(N + R)) per pixel.
+///
 /// # Panics
 /// Panic is stride/width/height/channel configuration do not match provided
 pub fn gaussian_blur_f32(
@@ -563,6 +674,35 @@ pub fn gaussian_blur_f32(
     edge_mode: EdgeMode,
     threading_policy: ThreadingPolicy,
 ) {
+    if kernel_size > GAUSSIAN_FFT_KERNEL_CUTOFF {
+        match channels {
+            FastBlurChannels::Channels3 => {
+                gaussian_blur_fft_f32::<3>(
+                    src,
+                    dst,
+                    width,
+                    height,
+                    kernel_size,
+                    sigma,
+                    edge_mode,
+                    threading_policy,
+                );
+            }
+            FastBlurChannels::Channels4 => {
+                gaussian_blur_fft_f32::<4>(
+                    src,
+                    dst,
+                    width,
+                    height,
+                    kernel_size,
+                    sigma,
+                    edge_mode,
+                    threading_policy,
+                );
+            }
+        }
+        return;
+    }
     match channels {
         FastBlurChannels::Channels3 => {
             gaussian_blur_impl::<f32, 3>(
@@ -595,6 +735,214 @@ pub fn gaussian_blur_f32(
     }
 }
 
+/// Returns count of elements of the image type in the scratch of gaussian blur,
+/// transient image followed by the kernel with one more f32 to reach its alignment
+fn gaussian_scratch_size<T>(width: u32, height: u32, kernel_size: u32, channels: usize) -> usize {
+    let kernel_bytes = (kernel_size as usize + 1) * size_of::<f32>();
+    width as usize * height as usize * channels + kernel_bytes.div_ceil(size_of::<T>())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn gaussian_blur_scratch_impl<T, const CHANNEL_CONFIGURATION: usize>(
+    src: &[T],
+    dst: &mut [T],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [T],
+) where
+    T: FromPrimitive
+        + Default
+        + Into<f32>
+        + Send
+        + Sync
+        + core::ops::AddAssign
+        + core::ops::SubAssign
+        + Copy
+        + 'static,
+    f32: AsPrimitive<T> + ToStorage<T>,
+{
+    if kernel_size & 1 == 0 {
+        panic!("kernel size must be odd");
+    }
+    let required = gaussian_scratch_size::<T>(width, height, kernel_size, CHANNEL_CONFIGURATION);
+    if scratch.len() < required {
+        panic!(
+            "Scratch has {} elements but {} is required",
+            scratch.len(),
+            required
+        );
+    }
+    let stride = width * CHANNEL_CONFIGURATION as u32;
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Gaussian.work_per_pixel(kernel_size / 2) * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    let (transient, kernel_scratch) = scratch.split_at_mut(stride as usize * height as usize);
+    if edge_mode == EdgeMode::KernelClip {
+        let kernel = create_gaussian_kernel(width, height, kernel_size, sigma, edge_mode);
+        gaussian_blur_in_pool::<T, CHANNEL_CONFIGURATION>(
+            src,
+            stride,
+            dst,
+            stride,
+            width,
+            height,
+            &kernel,
+            transient,
+            edge_mode,
+            &pool,
+            thread_count,
+        );
+        return;
+    }
+    // Weights are placed after the transient image at the first f32 aligned element
+    let kernel: &mut [f32] = unsafe { aligned_scratch(kernel_scratch, kernel_size as usize) };
+    fill_gaussian_kernel_1d(kernel, sigma);
+    gaussian_blur_kernel_in_pool::<T, CHANNEL_CONFIGURATION>(
+        src,
+        stride,
+        dst,
+        stride,
+        width,
+        height,
+        kernel,
+        transient,
+        edge_mode,
+        &pool,
+        thread_count,
+    );
+}
+
+/// Returns count of u16 in the scratch required by *gaussian_blur_u16_with_scratch*
+pub fn gaussian_blur_u16_scratch_size(
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    channels: FastBlurChannels,
+) -> usize {
+    gaussian_scratch_size::<u16>(width, height, kernel_size, channels.get_channels())
+}
+
+/// Performs gaussian blur on the image with transient image and kernel taken from `scratch`.
+///
+/// Same as *gaussian_blur_u16* but nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single*. Multithreaded policies still allocate to schedule jobs on the threads
+/// and *EdgeMode::KernelClip* its clipped kernels.
+///
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
+/// * `channels` - Count of channels in the image
+/// * `edge_mode` - Rule to handle edge mode
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `scratch` - Buffer of at least *gaussian_blur_u16_scratch_size* elements, its content is overwritten
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided or scratch is smaller than required
+#[allow(clippy::too_many_arguments)]
+pub fn gaussian_blur_u16_with_scratch(
+    src: &[u16],
+    dst: &mut [u16],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [u16],
+) {
+    let executor = match channels {
+        FastBlurChannels::Channels3 => gaussian_blur_scratch_impl::<u16, 3>,
+        FastBlurChannels::Channels4 => gaussian_blur_scratch_impl::<u16, 4>,
+    };
+    executor(
+        src,
+        dst,
+        width,
+        height,
+        kernel_size,
+        sigma,
+        edge_mode,
+        threading_policy,
+        scratch,
+    );
+}
+
+/// Returns count of f32 in the scratch required by *gaussian_blur_f32_with_scratch*
+pub fn gaussian_blur_f32_scratch_size(
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    channels: FastBlurChannels,
+) -> usize {
+    gaussian_scratch_size::<f32>(width, height, kernel_size, channels.get_channels())
+}
+
+/// Performs gaussian blur on the image with transient image and kernel taken from `scratch`.
+///
+/// Same as *gaussian_blur_f32* but nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single*. Multithreaded policies still allocate to schedule jobs on the threads
+/// and *EdgeMode::KernelClip* its clipped kernels.
+/// Kernels larger than *GAUSSIAN_FFT_KERNEL_CUTOFF* are convolved directly as well,
+/// since FFT would allocate its buffers.
+///
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
+/// * `channels` - Count of channels in the image
+/// * `edge_mode` - Rule to handle edge mode
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `scratch` - Buffer of at least *gaussian_blur_f32_scratch_size* elements, its content is overwritten
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided or scratch is smaller than required
+#[allow(clippy::too_many_arguments)]
+pub fn gaussian_blur_f32_with_scratch(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [f32],
+) {
+    let executor = match channels {
+        FastBlurChannels::Channels3 => gaussian_blur_scratch_impl::<f32, 3>,
+        FastBlurChannels::Channels4 => gaussian_blur_scratch_impl::<f32, 4>,
+    };
+    executor(
+        src,
+        dst,
+        width,
+        height,
+        kernel_size,
+        sigma,
+        edge_mode,
+        threading_policy,
+        scratch,
+    );
+}
+
 /// Performs gaussian blur on the image.
 ///
 /// This performs a gaussian kernel filter on the image producing beautiful looking result.
@@ -635,3 +983,65 @@ pub fn gaussian_blur_f16(
         threading_policy,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaussian_kernel_clip_supports_different_strides() {
+        let width = 21usize;
+        let height = 13usize;
+        let row = width * 3;
+        let src: Vec<u8> = (0..row * height).map(|i| (i * 37 % 251) as u8).collect();
+        for threading_policy in [ThreadingPolicy::Single, ThreadingPolicy::Fixed(3)] {
+            let mut expected = vec![0u8; row * height];
+            gaussian_blur(
+                &src,
+                row as u32,
+                &mut expected,
+                row as u32,
+                width as u32,
+                height as u32,
+                7,
+                0.,
+                FastBlurChannels::Channels3,
+                EdgeMode::KernelClip,
+                threading_policy,
+            );
+            for (src_stride, dst_stride) in [(row + 7, row + 2), (row + 2, row + 9)] {
+                let mut padded = vec![0u8; src_stride * height];
+                for (dst, src) in padded
+                    .chunks_exact_mut(src_stride)
+                    .zip(src.chunks_exact(row))
+                {
+                    dst[..row].copy_from_slice(src);
+                }
+                let mut dst = vec![0u8; dst_stride * height];
+                gaussian_blur(
+                    &padded,
+                    src_stride as u32,
+                    &mut dst,
+                    dst_stride as u32,
+                    width as u32,
+                    height as u32,
+                    7,
+                    0.,
+                    FastBlurChannels::Channels3,
+                    EdgeMode::KernelClip,
+                    threading_policy,
+                );
+                for y in 0..height {
+                    assert_eq!(
+                        &dst[y * dst_stride..y * dst_stride + row],
+                        &expected[y * row..(y + 1) * row],
+                        "Row {} differs for src stride {} and dst stride {}",
+                        y,
+                        src_stride,
+                        dst_stride
+                    );
+                }
+            }
+        }
+    }
+}
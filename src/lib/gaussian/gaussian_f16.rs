@@ -37,7 +37,7 @@ mod gaussian_f16_impl {
         width: u32,
         kernel_size: usize,
         gaussian_channels: FastBlurChannels,
-        kernel: &Vec<f32>,
+        kernel: &[f32],
         start_y: u32,
         end_y: u32,
     ) {
@@ -53,7 +53,7 @@ mod gaussian_f16_impl {
                 let mut weights: [f32; 4] = [0f32; 4];
                 for r in -half_kernel..=half_kernel {
                     let px =
-                        std::cmp::min(std::cmp::max(x as i64 + r as i64, 0), (width - 1) as i64)
+                        core::cmp::min(core::cmp::max(x as i64 + r as i64, 0), (width - 1) as i64)
                             as usize
                             * channels_count;
                     let weight = kernel[(r + half_kernel) as usize];
@@ -104,7 +104,7 @@ mod gaussian_f16_impl {
         height: u32,
         kernel_size: usize,
         gaussian_channels: FastBlurChannels,
-        kernel: &Vec<f32>,
+        kernel: &[f32],
         start_y: u32,
         end_y: u32,
     ) {
@@ -120,7 +120,7 @@ mod gaussian_f16_impl {
                 let mut weights: [f32; 4] = [0f32; 4];
                 for r in -half_kernel..=half_kernel {
                     let py =
-                        std::cmp::min(std::cmp::max(y as i64 + r as i64, 0), (height - 1) as i64);
+                        core::cmp::min(core::cmp::max(y as i64 + r as i64, 0), (height - 1) as i64);
                     let y_src_shift = py as usize * src_stride as usize;
                     let weight = kernel[(r + half_kernel) as usize];
                     weights[0] += half::f16::from_bits(src[y_src_shift + px]).to_f32() * weight;
@@ -161,12 +161,14 @@ mod gaussian_f16_impl {
 }
 
 pub(crate) mod gaussian_f16 {
-    use rayon::ThreadPool;
+    use crate::threading_policy::{type_work_per_pixel, BlurPool};
+    use alloc::vec;
+    use alloc::vec::Vec;
 
     use crate::gaussian::gaussian_f16::gaussian_f16_impl;
     use crate::gaussian::gaussian_kernel::get_gaussian_kernel_1d;
     use crate::unsafe_slice::UnsafeSlice;
-    use crate::{FastBlurChannels, ThreadingPolicy};
+    use crate::{BlurAlgorithm, FastBlurChannels, ThreadingPolicy};
 
     fn gaussian_blur_horizontal_pass_f16(
         src: &[u16],
@@ -177,8 +179,8 @@ pub(crate) mod gaussian_f16 {
         height: u32,
         kernel_size: usize,
         gaussian_channels: FastBlurChannels,
-        kernel: &Vec<f32>,
-        thread_pool: &ThreadPool,
+        kernel: &[f32],
+        thread_pool: &BlurPool,
         thread_count: u32,
     ) {
         let unsafe_dst = UnsafeSlice::new(dst);
@@ -218,8 +220,8 @@ pub(crate) mod gaussian_f16 {
         height: u32,
         kernel_size: usize,
         gaussian_channels: FastBlurChannels,
-        kernel: &Vec<f32>,
-        thread_pool: &ThreadPool,
+        kernel: &[f32],
+        thread_pool: &BlurPool,
         thread_count: u32,
     ) {
         let unsafe_dst = UnsafeSlice::new(dst);
@@ -270,11 +272,13 @@ pub(crate) mod gaussian_f16 {
         }
         let mut transient: Vec<u16> = vec![0u16; dst_stride as usize * height as usize];
 
-        let thread_count = threading_policy.get_threads_count(width, height);
-        let pool = rayon::ThreadPoolBuilder::new()
-            .num_threads(thread_count)
-            .build()
-            .unwrap();
+        let thread_count = threading_policy.get_threads_count_for_work(
+            width,
+            height,
+            BlurAlgorithm::Gaussian.work_per_pixel(kernel_size / 2)
+                * type_work_per_pixel::<half::f16>(),
+        );
+        let pool = threading_policy.create_pool(thread_count);
 
         gaussian_blur_horizontal_pass_f16(
             &src,
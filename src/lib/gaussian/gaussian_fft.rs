@@ -0,0 +1,367 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::fft::{Complex, Fft};
+use crate::gaussian::gaussian_kernel::get_gaussian_kernel_1d;
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{clamp_edge, EdgeMode, ThreadingPolicy};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Cost of the pixel relative to stack blur for *ThreadingPolicy::get_threads_count_for_work*,
+/// rows and columns are transformed forward and back
+const GAUSSIAN_FFT_WORK_PER_PIXEL: f32 = 20f32;
+
+/// Kernel size since gaussian blur on f32 switches to FFT convolution
+pub(crate) const GAUSSIAN_FFT_KERNEL_CUTOFF: u32 = 201;
+
+/// Shared state of one pass: transform, kernel spectrum and line addressing
+struct GaussianFftPass<'a> {
+    fft: &'a Fft,
+    /// Spectrum of symmetric kernel is real
+    kernel_spectrum: &'a [f64],
+    /// Normalization weights for *KernelClip* mode
+    clip_weights: Option<&'a [f64]>,
+    edge_mode: EdgeMode,
+    half_kernel: usize,
+    /// Count of samples in a line
+    length: usize,
+    /// Distance between lines
+    line_step: usize,
+    /// Distance between samples in a line
+    sample_step: usize,
+}
+
+impl<'a> GaussianFftPass<'a> {
+    #[inline]
+    fn fill(&self, src: &[f32], line: usize, channel: usize, lane: &mut [f64]) {
+        let base = line * self.line_step + channel;
+        let max = self.length as i64 - 1;
+        for (i, dst) in lane.iter_mut().enumerate() {
+            let position = i as i64 - self.half_kernel as i64;
+            *dst = if self.edge_mode == EdgeMode::KernelClip {
+                if position < 0 || position > max {
+                    0f64
+                } else {
+                    unsafe {
+                        *src.get_unchecked(base + position as usize * self.sample_step) as f64
+                    }
+                }
+            } else {
+                let index = clamp_edge!(self.edge_mode, position, 0, max);
+                unsafe { *src.get_unchecked(base + index * self.sample_step) as f64 }
+            };
+        }
+    }
+
+    /// Convolves two lines at once packing them into real and imaginary parts
+    fn execute(
+        &self,
+        src: &[f32],
+        unsafe_dst: &UnsafeSlice<f32>,
+        first: (usize, usize),
+        second: Option<(usize, usize)>,
+        buffer: &mut [Complex],
+        lane: &mut [f64],
+    ) {
+        let extended = self.length + 2 * self.half_kernel;
+        buffer.fill(Complex::default());
+        self.fill(src, first.0, first.1, &mut lane[..extended]);
+        for (dst, &v) in buffer.iter_mut().zip(lane[..extended].iter()) {
+            dst.re = v;
+        }
+        if let Some(second) = second {
+            self.fill(src, second.0, second.1, &mut lane[..extended]);
+            for (dst, &v) in buffer.iter_mut().zip(lane[..extended].iter()) {
+                dst.im = v;
+            }
+        }
+        self.fft.forward(buffer);
+        for (dst, &k) in buffer.iter_mut().zip(self.kernel_spectrum.iter()) {
+            *dst = *dst * k;
+        }
+        self.fft.inverse(buffer);
+
+        let convolved = &buffer[self.half_kernel..self.half_kernel + self.length];
+        let first_base = first.0 * self.line_step + first.1;
+        for (i, v) in convolved.iter().enumerate() {
+            let scale = self.clip_weights.map(|w| w[i]).unwrap_or(1f64);
+            unsafe {
+                unsafe_dst.write(first_base + i * self.sample_step, (v.re * scale) as f32);
+            }
+        }
+        if let Some(second) = second {
+            let second_base = second.0 * self.line_step + second.1;
+            for (i, v) in convolved.iter().enumerate() {
+                let scale = self.clip_weights.map(|w| w[i]).unwrap_or(1f64);
+                unsafe {
+                    unsafe_dst.write(second_base + i * self.sample_step, (v.im * scale) as f32);
+                }
+            }
+        }
+    }
+
+    fn run<const CHANNEL_CONFIGURATION: usize>(
+        &self,
+        src: &[f32],
+        unsafe_dst: &UnsafeSlice<f32>,
+        start: usize,
+        end: usize,
+    ) {
+        let mut buffer = vec![Complex::default(); self.fft.size()];
+        let mut lane = vec![0f64; self.length + 2 * self.half_kernel];
+        let mut pending: Option<(usize, usize)> = None;
+        for line in start..end {
+            for channel in 0..CHANNEL_CONFIGURATION {
+                match pending.take() {
+                    Some(first) => self.execute(
+                        src,
+                        unsafe_dst,
+                        first,
+                        Some((line, channel)),
+                        &mut buffer,
+                        &mut lane,
+                    ),
+                    None => pending = Some((line, channel)),
+                }
+            }
+        }
+        if let Some(first) = pending {
+            self.execute(src, unsafe_dst, first, None, &mut buffer, &mut lane);
+        }
+    }
+}
+
+fn gaussian_fft_kernel_spectrum(fft: &Fft, kernel: &[f32]) -> Vec<f64> {
+    let size = fft.size();
+    let half_kernel = kernel.len() / 2;
+    let mut spectrum = vec![Complex::default(); size];
+    for (j, &weight) in kernel.iter().enumerate() {
+        let index = (j as i64 - half_kernel as i64).rem_euclid(size as i64) as usize;
+        spectrum[index].re += weight as f64;
+    }
+    fft.forward(&mut spectrum);
+    spectrum.iter().map(|v| v.re).collect()
+}
+
+/// Sum of kernel weights which are inside the line, used to renormalize *KernelClip* results
+fn gaussian_fft_clip_weights(length: usize, kernel: &[f32]) -> Vec<f64> {
+    let half_kernel = kernel.len() as i64 / 2;
+    let mut prefix = vec![0f64; kernel.len() + 1];
+    for (i, &weight) in kernel.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + weight as f64;
+    }
+    (0..length as i64)
+        .map(|i| {
+            // Kernel tap `j` reads sample `i + j - half_kernel`
+            let first = (half_kernel - i).max(0) as usize;
+            let last = (length as i64 - 1 - i + half_kernel).min(kernel.len() as i64 - 1) as usize;
+            let sum = prefix[last + 1] - prefix[first];
+            if sum != 0f64 {
+                1f64 / sum
+            } else {
+                1f64
+            }
+        })
+        .collect()
+}
+
+fn gaussian_fft_pass<const CHANNEL_CONFIGURATION: usize>(
+    src: &[f32],
+    dst: &mut [f32],
+    lines: usize,
+    length: usize,
+    line_step: usize,
+    sample_step: usize,
+    kernel: &[f32],
+    edge_mode: EdgeMode,
+    pool: &BlurPool,
+    thread_count: usize,
+) {
+    let half_kernel = kernel.len() / 2;
+    let fft = Fft::new(length + 2 * half_kernel);
+    let kernel_spectrum = gaussian_fft_kernel_spectrum(&fft, kernel);
+    let clip_weights = if edge_mode == EdgeMode::KernelClip {
+        Some(gaussian_fft_clip_weights(length, kernel))
+    } else {
+        None
+    };
+    let pass = GaussianFftPass {
+        fft: &fft,
+        kernel_spectrum: &kernel_spectrum,
+        clip_weights: clip_weights.as_deref(),
+        edge_mode,
+        half_kernel,
+        length,
+        line_step,
+        sample_step,
+    };
+    let unsafe_dst = UnsafeSlice::new(dst);
+    if thread_count == 1 {
+        pass.run::<CHANNEL_CONFIGURATION>(src, &unsafe_dst, 0, lines);
+        return;
+    }
+    let pass = &pass;
+    pool.scope(|scope| {
+        let segment_size = lines / thread_count;
+        for i in 0..thread_count {
+            let start = i * segment_size;
+            let mut end = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end = lines;
+            }
+            let unsafe_dst = &unsafe_dst;
+            scope.spawn(move |_| {
+                pass.run::<CHANNEL_CONFIGURATION>(src, unsafe_dst, start, end);
+            });
+        }
+    });
+}
+
+/// Separable gaussian blur for f32 where each pass is performed as a convolution in frequency domain.
+/// Cost of each pass is O(log(N + R)) per pixel and does not depend on kernel size directly,
+/// so this one is used instead of direct convolution for a huge kernels
+pub(crate) fn gaussian_blur_fft_f32<const CHANNEL_CONFIGURATION: usize>(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) {
+    if kernel_size & 1 == 0 {
+        panic!("kernel size must be odd");
+    }
+    let kernel = get_gaussian_kernel_1d(kernel_size, sigma);
+    let stride = width as usize * CHANNEL_CONFIGURATION;
+    let mut transient: Vec<f32> = vec![0f32; stride * height as usize];
+
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        GAUSSIAN_FFT_WORK_PER_PIXEL * type_work_per_pixel::<f32>(),
+    );
+    let pool = threading_policy.create_pool(thread_count);
+
+    gaussian_fft_pass::<CHANNEL_CONFIGURATION>(
+        src,
+        &mut transient,
+        height as usize,
+        width as usize,
+        stride,
+        CHANNEL_CONFIGURATION,
+        &kernel,
+        edge_mode,
+        &pool,
+        thread_count,
+    );
+    gaussian_fft_pass::<CHANNEL_CONFIGURATION>(
+        &transient,
+        dst,
+        width as usize,
+        height as usize,
+        CHANNEL_CONFIGURATION,
+        stride,
+        &kernel,
+        edge_mode,
+        &pool,
+        thread_count,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gaussian_blur_f32, FastBlurChannels};
+
+    #[test]
+    fn fft_matches_direct_convolution() {
+        let width = 41u32;
+        let height = 29u32;
+        let src: Vec<f32> = (0..width as usize * height as usize * 3)
+            .map(|i| ((i * 7919) % 256) as f32)
+            .collect();
+        for edge_mode in [
+            EdgeMode::Clamp,
+            EdgeMode::Wrap,
+            EdgeMode::Reflect,
+            EdgeMode::Reflect101,
+        ] {
+            let mut fft = vec![0f32; src.len()];
+            gaussian_blur_fft_f32::<3>(
+                &src,
+                &mut fft,
+                width,
+                height,
+                31,
+                5f32,
+                edge_mode,
+                ThreadingPolicy::Single,
+            );
+            let mut direct = vec![0f32; src.len()];
+            gaussian_blur_f32(
+                &src,
+                &mut direct,
+                width,
+                height,
+                31,
+                5f32,
+                FastBlurChannels::Channels3,
+                edge_mode,
+                ThreadingPolicy::Single,
+            );
+            for (a, b) in fft.iter().zip(direct.iter()) {
+                assert!((a - b).abs() < 0.01f32, "{:?}: {} != {}", edge_mode, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn constant_image_stays_constant() {
+        let width = 64u32;
+        let height = 48u32;
+        let src = vec![0.75f32; width as usize * height as usize * 4];
+        for edge_mode in [EdgeMode::Clamp, EdgeMode::KernelClip] {
+            let mut dst = vec![0f32; src.len()];
+            gaussian_blur_f32(
+                &src,
+                &mut dst,
+                width,
+                height,
+                GAUSSIAN_FFT_KERNEL_CUTOFF + 50,
+                40f32,
+                FastBlurChannels::Channels4,
+                edge_mode,
+                ThreadingPolicy::Single,
+            );
+            assert!(dst.iter().all(|&v| (v - 0.75f32).abs() < 1e-4));
+        }
+    }
+}
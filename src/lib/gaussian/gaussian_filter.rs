@@ -25,6 +25,11 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
 #[derive(Clone)]
 pub(crate) struct GaussianFilter {
     pub start: usize,
@@ -46,7 +51,7 @@ pub(crate) fn create_filter(length: usize, kernel_size: u32, sigma: f32) -> Vec<
     let mut filter: Vec<GaussianFilter> = vec![GaussianFilter::new(0, 0, vec![]); length];
     let filter_radius = (kernel_size / 2) as usize;
 
-    let filter_scale = 1f32 / (f32::sqrt(2f32 * std::f32::consts::PI) * sigma);
+    let filter_scale = 1f32 / (f32::sqrt(2f32 * core::f32::consts::PI) * sigma);
     for x in 0..length {
         let start = (x as i64 - filter_radius as i64).max(0) as usize;
         let end = (x + filter_radius).min(length - 1);
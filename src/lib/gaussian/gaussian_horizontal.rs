@@ -28,7 +28,8 @@
 use crate::gaussian::gaussian_filter::GaussianFilter;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{clamp_edge, reflect_101, reflect_index, EdgeMode};
+use crate::{clamp_edge, EdgeMode};
+use alloc::vec::Vec;
 use num_traits::{AsPrimitive, FromPrimitive};
 
 pub(crate) fn gaussian_blur_horizontal_pass_impl<
@@ -46,7 +47,7 @@ pub(crate) fn gaussian_blur_horizontal_pass_impl<
     start_y: u32,
     end_y: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     gaussian_blur_horizontal_pass_impl_c::<T, CHANNEL_CONFIGURATION, EDGE_MODE>(
@@ -77,7 +78,7 @@ pub(crate) fn gaussian_blur_horizontal_pass_impl_c<
     start_y: u32,
     end_y: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     let edge_mode: EdgeMode = EDGE_MODE.into();
@@ -128,7 +129,7 @@ pub(crate) fn gaussian_blur_horizontal_pass_impl_clip_edge<
     start_y: u32,
     end_y: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     for y in start_y..end_y {
@@ -25,26 +25,33 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
 pub(crate) fn get_gaussian_kernel_1d(width: u32, sigma: f32) -> Vec<f32> {
+    let mut kernel = vec![0f32; width as usize];
+    fill_gaussian_kernel_1d(&mut kernel, sigma);
+    kernel
+}
+
+/// Writes normalized gaussian weights into the whole `kernel`, its length is the kernel size
+pub(crate) fn fill_gaussian_kernel_1d(kernel: &mut [f32], sigma: f32) {
     let mut sum_norm: f32 = 0f32;
-    let mut kernel: Vec<f32> = Vec::with_capacity(width as usize);
-    let scale = 1f32 / (f32::sqrt(2f32 * std::f32::consts::PI) * sigma);
-    let mean = (width / 2) as f32;
+    let scale = 1f32 / (f32::sqrt(2f32 * core::f32::consts::PI) * sigma);
+    let mean = (kernel.len() / 2) as f32;
 
-    for x in 0..width {
+    for (x, weight) in kernel.iter_mut().enumerate() {
         let new_weight = f32::exp(-0.5f32 * f32::powf((x as f32 - mean) / sigma, 2.0f32)) * scale;
-        kernel.push(new_weight);
+        *weight = new_weight;
         sum_norm += new_weight;
     }
 
     if sum_norm != 0f32 {
         let sum_scale = 1f32 / sum_norm;
-        for x in 0..width as usize {
-            unsafe {
-                *kernel.get_unchecked_mut(x) = (*kernel.get_unchecked(x)) * sum_scale;
-            }
+        for weight in kernel.iter_mut() {
+            *weight *= sum_scale;
         }
     }
-
-    return kernel;
 }
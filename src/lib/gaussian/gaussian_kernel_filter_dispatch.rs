@@ -39,10 +39,11 @@ use crate::gaussian::gaussian_sse_filter::sse_filter::{
     gaussian_blur_horizontal_pass_filter_sse, gaussian_blur_vertical_pass_filter_sse,
 };
 use crate::gaussian::gaussian_vertical::gaussian_blur_vertical_pass_clip_edge_impl;
+use crate::threading_policy::BlurPool;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
+use alloc::vec::Vec;
 use num_traits::{AsPrimitive, FromPrimitive};
-use rayon::ThreadPool;
 
 pub(crate) fn gaussian_blur_vertical_pass_edge_clip_dispatch<
     T: FromPrimitive + Default + Into<f32> + Send + Sync,
@@ -55,10 +56,10 @@ pub(crate) fn gaussian_blur_vertical_pass_edge_clip_dispatch<
     width: u32,
     height: u32,
     filter: &Vec<GaussianFilter>,
-    thread_pool: &ThreadPool,
+    thread_pool: &BlurPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
     f32: ToStorage<T>,
 {
     let mut _dispatcher: fn(
@@ -72,7 +73,7 @@ pub(crate) fn gaussian_blur_vertical_pass_edge_clip_dispatch<
         start_y: u32,
         end_y: u32,
     ) = gaussian_blur_vertical_pass_clip_edge_impl::<T, CHANNEL_CONFIGURATION>;
-    if std::any::type_name::<T>() == "u8" {
+    if core::any::type_name::<T>() == "u8" {
         #[cfg(all(
             any(target_arch = "x86_64", target_arch = "x86"),
             target_feature = "sse4.1"
@@ -124,10 +125,10 @@ pub(crate) fn gaussian_blur_horizontal_pass_edge_clip_dispatch<
     width: u32,
     height: u32,
     filter: &Vec<GaussianFilter>,
-    thread_pool: &ThreadPool,
+    thread_pool: &BlurPool,
     thread_count: u32,
 ) where
-    T: std::ops::AddAssign + std::ops::SubAssign + Copy + 'static,
+    T: core::ops::AddAssign + core::ops::SubAssign + Copy + 'static,
     f32: AsPrimitive<T> + ToStorage<T>,
 {
     let mut _dispatcher: fn(
@@ -140,7 +141,7 @@ pub(crate) fn gaussian_blur_horizontal_pass_edge_clip_dispatch<
         start_y: u32,
         end_y: u32,
     ) = gaussian_blur_horizontal_pass_impl_clip_edge::<T, CHANNEL_CONFIGURATION>;
-    if std::any::type_name::<T>() == "u8" {
+    if core::any::type_name::<T>() == "u8" {
         #[cfg(all(
             any(target_arch = "x86_64", target_arch = "x86"),
             target_feature = "sse4.1"
@@ -26,10 +26,12 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{gaussian_blur_f32, EdgeMode, FastBlurChannels, ThreadingPolicy};
+use alloc::vec;
+use alloc::vec::Vec;
 use colorutils_rs::{
     linear_to_rgb, linear_to_rgba, rgb_to_linear, rgba_to_linear, TransferFunction,
 };
-use std::mem::size_of;
+use core::mem::size_of;
 
 /// Performs gaussian blur on the image in linear colorspace
 ///
@@ -30,7 +30,8 @@ pub mod neon_support {
     use crate::neon::{
         load_u8_u16_x2_fast, load_u8_u32_fast, load_u8_u32_one, prefer_vfma_f32, prefer_vfmaq_f32,
     };
-    use std::arch::aarch64::*;
+    use alloc::vec::Vec;
+    use core::arch::aarch64::*;
 
     use crate::unsafe_slice::UnsafeSlice;
 
@@ -45,8 +46,8 @@ pub mod neon_support {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undef_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undef_unsafe_dst) };
         let half_kernel = (kernel_size / 2) as i32;
 
         let shuf_table_1: [u8; 8] = [0, 1, 2, 255, 3, 4, 5, 255];
@@ -71,8 +72,8 @@ pub mod neon_support {
                         && x as i64 + r as i64 + (if CHANNEL_CONFIGURATION == 4 { 4 } else { 6 })
                             < width as i64
                     {
-                        let px = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let px = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize
                             * CHANNEL_CONFIGURATION;
@@ -142,8 +143,8 @@ pub mod neon_support {
                         && x as i64 + r as i64 + (if CHANNEL_CONFIGURATION == 4 { 2 } else { 3 })
                             < width as i64
                     {
-                        let px = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let px = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize
                             * CHANNEL_CONFIGURATION;
@@ -184,8 +185,8 @@ pub mod neon_support {
 
                 unsafe {
                     while r <= half_kernel {
-                        let current_x = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let current_x = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize;
                         let px = current_x * CHANNEL_CONFIGURATION;
@@ -259,8 +260,8 @@ pub mod neon_support {
 
                 unsafe {
                     while r + 4 <= half_kernel && x as i64 + r as i64 + 6 < width as i64 {
-                        let px = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let px = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize
                             * CHANNEL_CONFIGURATION;
@@ -304,8 +305,8 @@ pub mod neon_support {
 
                 unsafe {
                     while r + 2 <= half_kernel && x as i64 + r as i64 + 2 < width as i64 {
-                        let current_x = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let current_x = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize;
                         let px = current_x * CHANNEL_CONFIGURATION;
@@ -328,8 +329,8 @@ pub mod neon_support {
 
                 unsafe {
                     while r <= half_kernel {
-                        let current_x = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let current_x = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize;
                         let px = current_x * CHANNEL_CONFIGURATION;
@@ -380,8 +381,8 @@ pub mod neon_support {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undef_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undef_unsafe_dst) };
         let half_kernel = (kernel_size / 2) as i32;
 
         let zeros = unsafe { vdupq_n_f32(0f32) };
@@ -409,8 +410,8 @@ pub mod neon_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight: float32x4_t = vdupq_n_f32(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
@@ -477,8 +478,8 @@ pub mod neon_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight: float32x4_t = vdupq_n_f32(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
@@ -523,8 +524,8 @@ pub mod neon_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight: float32x4_t = vdupq_n_f32(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
@@ -560,8 +561,8 @@ pub mod neon_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight: float32x4_t = vdupq_n_f32(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
@@ -595,8 +596,8 @@ pub mod neon_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight = vdup_n_f32(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
@@ -640,7 +641,7 @@ pub mod neon_support {
         _width: u32,
         _height: u32,
         _kernel_size: usize,
-        _kernel: &Vec<f32>,
+        _kernel: &[f32],
         _start_y: u32,
         _end_y: u32,
     ) {
@@ -654,7 +655,7 @@ pub mod neon_support {
         _dst_stride: u32,
         _width: u32,
         _kernel_size: usize,
-        _kernel: &Vec<f32>,
+        _kernel: &[f32],
         _start_y: u32,
         _end_y: u32,
     ) {
@@ -31,7 +31,8 @@ pub(crate) mod neon_gaussian_filter {
     use crate::neon::{
         load_u8_u16_x2_fast, load_u8_u32_fast, load_u8_u32_one, prefer_vfma_f32, prefer_vfmaq_f32,
     };
-    use std::arch::aarch64::*;
+    use alloc::vec::Vec;
+    use core::arch::aarch64::*;
 
     use crate::unsafe_slice::UnsafeSlice;
 
@@ -45,8 +46,8 @@ pub(crate) mod neon_gaussian_filter {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undef_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undef_unsafe_dst) };
         let shuf_table_1: [u8; 8] = [0, 1, 2, 255, 3, 4, 5, 255];
         let shuffle_1 = unsafe { vld1_u8(shuf_table_1.as_ptr()) };
         let shuf_table_2: [u8; 8] = [6, 7, 8, 255, 9, 10, 11, 255];
@@ -371,8 +372,8 @@ pub(crate) mod neon_gaussian_filter {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undef_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undef_unsafe_dst) };
         let zeros = unsafe { vdupq_n_f32(0f32) };
 
         let total_size = CHANNEL_CONFIGURATION * width as usize;
@@ -618,7 +619,7 @@ pub mod neon_support {
         _width: u32,
         _height: u32,
         _kernel_size: usize,
-        _kernel: &Vec<f32>,
+        _kernel: &[f32],
         _start_y: u32,
         _end_y: u32,
     ) {
@@ -632,7 +633,7 @@ pub mod neon_support {
         _dst_stride: u32,
         _width: u32,
         _kernel_size: usize,
-        _kernel: &Vec<f32>,
+        _kernel: &[f32],
         _start_y: u32,
         _end_y: u32,
     ) {
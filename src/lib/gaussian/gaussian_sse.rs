@@ -32,9 +32,9 @@
 pub mod sse_support {
     use crate::sse::{_mm_prefer_fma_ps, load_u8_f32_fast, load_u8_u32_one};
     #[cfg(target_arch = "x86")]
-    use std::arch::x86::*;
+    use core::arch::x86::*;
     #[cfg(target_arch = "x86_64")]
-    use std::arch::x86_64::*;
+    use core::arch::x86_64::*;
 
     use crate::unsafe_slice::UnsafeSlice;
 
@@ -49,8 +49,8 @@ pub mod sse_support {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undef_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undef_unsafe_dst) };
 
         let half_kernel = (kernel_size / 2) as i32;
 
@@ -75,8 +75,8 @@ pub mod sse_support {
                         && x as i64 + r as i64 + (if CHANNEL_CONFIGURATION == 4 { 4 } else { 6 })
                             < width as i64
                     {
-                        let px = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let px = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize
                             * CHANNEL_CONFIGURATION;
@@ -148,8 +148,8 @@ pub mod sse_support {
                         && x as i64 + r as i64 + (if CHANNEL_CONFIGURATION == 4 { 4 } else { 6 })
                             < width as i64
                     {
-                        let px = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let px = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize
                             * CHANNEL_CONFIGURATION;
@@ -192,8 +192,8 @@ pub mod sse_support {
 
                 unsafe {
                     while r <= half_kernel {
-                        let current_x = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let current_x = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize;
                         let px = current_x * CHANNEL_CONFIGURATION;
@@ -205,7 +205,7 @@ pub mod sse_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight = _mm_set1_ps(weight);
                         store_0 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_0, f_weight);
-                        store_1 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_1, f_weight);
+                        store_1 = _mm_prefer_fma_ps(store_1, pixel_colors_f32_1, f_weight);
 
                         r += 1;
                     }
@@ -241,7 +241,7 @@ pub mod sse_support {
                 let pixel = unsafe { _mm_extract_epi32::<0>(px_8) };
                 if CHANNEL_CONFIGURATION == 4 {
                     unsafe {
-                        let unsafe_offset = y_dst_shift + src_stride as usize + px;
+                        let unsafe_offset = y_dst_shift + dst_stride as usize + px;
                         let dst_ptr = unsafe_dst.slice.as_ptr().add(unsafe_offset) as *mut i32;
                         dst_ptr.write_unaligned(pixel);
                     }
@@ -249,7 +249,7 @@ pub mod sse_support {
                     let pixel_bytes = pixel.to_le_bytes();
 
                     unsafe {
-                        let unsafe_offset = y_dst_shift + src_stride as usize + px;
+                        let unsafe_offset = y_dst_shift + dst_stride as usize + px;
                         unsafe_dst.write(unsafe_offset, pixel_bytes[0]);
                         unsafe_dst.write(unsafe_offset + 1, pixel_bytes[1]);
                         unsafe_dst.write(unsafe_offset + 2, pixel_bytes[2]);
@@ -270,8 +270,8 @@ pub mod sse_support {
 
                 unsafe {
                     while r + 4 <= half_kernel && x as i64 + r as i64 + 6 < width as i64 {
-                        let px = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let px = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize
                             * CHANNEL_CONFIGURATION;
@@ -314,8 +314,8 @@ pub mod sse_support {
 
                 unsafe {
                     while r <= half_kernel {
-                        let current_x = std::cmp::min(
-                            std::cmp::max(x as i64 + r as i64, 0),
+                        let current_x = core::cmp::min(
+                            core::cmp::max(x as i64 + r as i64, 0),
                             (width - 1) as i64,
                         ) as usize;
                         let px = current_x * CHANNEL_CONFIGURATION;
@@ -366,8 +366,8 @@ pub mod sse_support {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undef_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undef_unsafe_dst) };
         let half_kernel = (kernel_size / 2) as i32;
         const ROUNDING_FLAGS: i32 = _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC;
 
@@ -395,8 +395,8 @@ pub mod sse_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight = _mm_set1_ps(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
@@ -464,8 +464,8 @@ pub mod sse_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight = _mm_set1_ps(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
@@ -510,8 +510,8 @@ pub mod sse_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight = _mm_set1_ps(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
@@ -534,7 +534,7 @@ pub mod sse_support {
 
                     let dst_ptr = unsafe_dst.slice.as_ptr().add(y_dst_shift + cx) as *mut u8;
 
-                    std::ptr::copy_nonoverlapping(&store as *const _ as *const u8, dst_ptr, 8);
+                    core::ptr::copy_nonoverlapping(&store as *const _ as *const u8, dst_ptr, 8);
 
                     cx += 8;
                 }
@@ -547,8 +547,8 @@ pub mod sse_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight = _mm_set1_ps(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
@@ -580,8 +580,8 @@ pub mod sse_support {
                         let weight = *kernel.get_unchecked((r + half_kernel) as usize);
                         let f_weight = _mm_set1_ps(weight);
 
-                        let py = std::cmp::min(
-                            std::cmp::max(y as i64 + r as i64, 0),
+                        let py = core::cmp::min(
+                            core::cmp::max(y as i64 + r as i64, 0),
                             (height - 1) as i64,
                         );
                         let y_src_shift = py as usize * src_stride as usize;
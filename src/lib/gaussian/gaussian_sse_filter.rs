@@ -32,10 +32,11 @@
 pub mod sse_filter {
     use crate::gaussian::gaussian_filter::GaussianFilter;
     use crate::sse::{_mm_prefer_fma_ps, load_u8_f32_fast, load_u8_u32_one};
+    use alloc::vec::Vec;
     #[cfg(target_arch = "x86")]
-    use std::arch::x86::*;
+    use core::arch::x86::*;
     #[cfg(target_arch = "x86_64")]
-    use std::arch::x86_64::*;
+    use core::arch::x86_64::*;
 
     use crate::unsafe_slice::UnsafeSlice;
 
@@ -49,8 +50,8 @@ pub mod sse_filter {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undef_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undef_unsafe_dst) };
         #[rustfmt::skip]
         let shuffle_rgb =
             unsafe { _mm_setr_epi8(0, 1, 2, -1, 3, 4,
@@ -203,7 +204,7 @@ pub mod sse_filter {
                         let weight = *filter_weights.get_unchecked(j);
                         let f_weight = _mm_set1_ps(weight);
                         store_0 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_0, f_weight);
-                        store_1 = _mm_prefer_fma_ps(store_0, pixel_colors_f32_1, f_weight);
+                        store_1 = _mm_prefer_fma_ps(store_1, pixel_colors_f32_1, f_weight);
 
                         j += 1;
                     }
@@ -240,7 +241,7 @@ pub mod sse_filter {
 
                 if CHANNEL_CONFIGURATION == 4 {
                     unsafe {
-                        let unsafe_offset = y_dst_shift + src_stride as usize + px;
+                        let unsafe_offset = y_dst_shift + dst_stride as usize + px;
                         let dst_ptr = unsafe_dst.slice.as_ptr().add(unsafe_offset) as *mut i32;
                         dst_ptr.write_unaligned(pixel);
                     }
@@ -248,7 +249,7 @@ pub mod sse_filter {
                     let pixel_bytes = pixel.to_le_bytes();
 
                     unsafe {
-                        let unsafe_offset = y_dst_shift + src_stride as usize + px;
+                        let unsafe_offset = y_dst_shift + dst_stride as usize + px;
                         unsafe_dst.write(unsafe_offset, pixel_bytes[0]);
                         unsafe_dst.write(unsafe_offset + 1, pixel_bytes[1]);
                         unsafe_dst.write(unsafe_offset + 2, pixel_bytes[2]);
@@ -397,8 +398,8 @@ pub mod sse_filter {
         start_y: u32,
         end_y: u32,
     ) {
-        let src: &[u8] = unsafe { std::mem::transmute(undef_src) };
-        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undef_unsafe_dst) };
+        let src: &[u8] = unsafe { core::mem::transmute(undef_src) };
+        let unsafe_dst: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undef_unsafe_dst) };
         const ROUNDING_FLAGS: i32 = _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC;
 
         let zeros = unsafe { _mm_setzero_ps() };
@@ -559,7 +560,7 @@ pub mod sse_filter {
 
                     let dst_ptr = unsafe_dst.slice.as_ptr().add(y_dst_shift + cx) as *mut u8;
 
-                    std::ptr::copy_nonoverlapping(&store as *const _ as *const u8, dst_ptr, 8);
+                    core::ptr::copy_nonoverlapping(&store as *const _ as *const u8, dst_ptr, 8);
 
                     cx += 8;
                 }
@@ -28,7 +28,8 @@
 use crate::gaussian::gaussian_filter::GaussianFilter;
 use crate::to_storage::ToStorage;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{clamp_edge, reflect_101, reflect_index, EdgeMode};
+use crate::{clamp_edge, EdgeMode};
+use alloc::vec::Vec;
 use num_traits::{AsPrimitive, FromPrimitive};
 
 pub fn gaussian_blur_vertical_pass_c_impl<
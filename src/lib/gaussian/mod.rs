@@ -27,10 +27,12 @@
 
 mod gaussian;
 mod gaussian_f16;
+mod gaussian_fft;
 mod gaussian_filter;
 mod gaussian_horizontal;
 mod gaussian_kernel;
 mod gaussian_kernel_filter_dispatch;
+#[cfg(feature = "colorutils")]
 mod gaussian_linear;
 mod gaussian_neon;
 mod gaussian_neon_filter;
@@ -39,4 +41,6 @@ mod gaussian_sse_filter;
 mod gaussian_vertical;
 
 pub use gaussian::*;
+pub(crate) use gaussian_kernel::fill_gaussian_kernel_1d;
+#[cfg(feature = "colorutils")]
 pub use gaussian_linear::gaussian_blur_in_linear;
@@ -0,0 +1,700 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::edge_mode::border_position;
+use crate::threading_policy::type_work_per_pixel;
+use crate::to_storage::ToStorage;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{EdgeMode, ThreadingPolicy};
+use alloc::vec;
+use alloc::vec::Vec;
+use num_traits::AsPrimitive;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// Cost of the pixel relative to stack blur for *ThreadingPolicy::get_threads_count_for_work*,
+/// recursion runs forward and backward over each row and column
+const IIR_GAUSSIAN_WORK_PER_PIXEL: f32 = 1.5f32;
+
+/// Third order recursive gaussian coefficients, feedback coefficients are normalized so filter is
+/// `w[n] = gain * x[n] + b1 * w[n - 1] + b2 * w[n - 2] + b3 * w[n - 3]`
+#[derive(Copy, Clone)]
+struct IirGaussianCoefficients {
+    gain: f64,
+    b1: f64,
+    b2: f64,
+    b3: f64,
+}
+
+/// Poles of the third order filter designed for sigma 2, L-infinity optimized
+const IIR_GAUSSIAN_POLE_RE: f64 = 1.41650f64;
+const IIR_GAUSSIAN_POLE_IM: f64 = 1.00829f64;
+const IIR_GAUSSIAN_POLE_REAL: f64 = 1.86543f64;
+
+/// Scales base poles with `d^(1/q)`, returns complex pole as (re, im) and a real pole
+#[inline]
+fn iir_gaussian_poles(q: f64) -> (f64, f64, f64) {
+    let magnitude = IIR_GAUSSIAN_POLE_RE
+        .hypot(IIR_GAUSSIAN_POLE_IM)
+        .powf(1f64 / q);
+    let phase = IIR_GAUSSIAN_POLE_IM.atan2(IIR_GAUSSIAN_POLE_RE) / q;
+    (
+        magnitude * phase.cos(),
+        magnitude * phase.sin(),
+        IIR_GAUSSIAN_POLE_REAL.powf(1f64 / q),
+    )
+}
+
+/// Variance of forward-backward filter is a sum of `2d / (d - 1)^2` over all poles
+#[inline]
+fn iir_gaussian_variance(q: f64) -> f64 {
+    let (re, im, real) = iir_gaussian_poles(q);
+    let u_re = (re - 1f64) * (re - 1f64) - im * im;
+    let u_im = 2f64 * (re - 1f64) * im;
+    let u_norm = u_re * u_re + u_im * u_im;
+    // Real part of 2d / u for the pole and its conjugate
+    let complex_part = 2f64 * 2f64 * (re * u_re + im * u_im) / u_norm;
+    complex_part + 2f64 * real / ((real - 1f64) * (real - 1f64))
+}
+
+impl IirGaussianCoefficients {
+    /// L.J. van Vliet, I.T. Young, P.W. Verbeek, Recursive Gaussian derivative filters, 1998.
+    /// Poles scale is found numerically so filter variance exactly matches requested sigma
+    fn new(sigma: f32) -> IirGaussianCoefficients {
+        let sigma = (sigma as f64).max(0.5f64);
+        let target = sigma * sigma;
+        let mut low = 0.01f64;
+        let mut high = (sigma * 2f64).max(2f64);
+        for _ in 0..64 {
+            let mid = (low + high) * 0.5f64;
+            if iir_gaussian_variance(mid) < target {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let (re, im, real) = iir_gaussian_poles((low + high) * 0.5f64);
+        let norm = re * re + im * im;
+        let a1 = -(2f64 * re / norm + 1f64 / real);
+        let a2 = 1f64 / norm + 2f64 * re / (norm * real);
+        let a3 = -1f64 / (norm * real);
+        IirGaussianCoefficients {
+            gain: 1f64 + a1 + a2 + a3,
+            b1: -a1,
+            b2: -a2,
+            b3: -a3,
+        }
+    }
+}
+
+type IirMatrix = [[f64; 3]; 3];
+
+fn iir_matrix_mul(a: &IirMatrix, b: &IirMatrix) -> IirMatrix {
+    let mut result = [[0f64; 3]; 3];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            *v = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    result
+}
+
+fn iir_matrix_inverse(m: &IirMatrix) -> IirMatrix {
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let adjugate = [
+        [
+            cofactor(1, 2, 1, 2),
+            -cofactor(0, 2, 1, 2),
+            cofactor(0, 1, 1, 2),
+        ],
+        [
+            -cofactor(1, 2, 0, 2),
+            cofactor(0, 2, 0, 2),
+            -cofactor(0, 1, 0, 2),
+        ],
+        [
+            cofactor(1, 2, 0, 1),
+            -cofactor(0, 2, 0, 1),
+            cofactor(0, 1, 0, 1),
+        ],
+    ];
+    let determinant =
+        m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] + m[0][2] * adjugate[2][0];
+    adjugate.map(|row| row.map(|v| v / determinant))
+}
+
+/// Initial state of the filter at line boundaries.
+///
+/// Clamp extends the line with its edge samples, so the causal filter starts from a steady state
+/// and the anti-causal one is initialized with the Triggs - Sdika matrix.
+/// Wrap and reflections make the extended line periodic, and the state at the line start is a steady state
+/// of the period `S = A^P * S + c`, where `c` is the response of the period to a zero state.
+#[derive(Copy, Clone)]
+enum IirBoundary {
+    Clamp(IirMatrix),
+    Periodic(usize, IirMatrix),
+}
+
+impl IirBoundary {
+    fn new(
+        coefficients: &IirGaussianCoefficients,
+        length: usize,
+        edge_mode: EdgeMode,
+    ) -> IirBoundary {
+        let a1 = coefficients.b1;
+        let a2 = coefficients.b2;
+        let a3 = coefficients.b3;
+        let period = match edge_mode {
+            EdgeMode::Wrap => length,
+            EdgeMode::Reflect => 2 * length,
+            EdgeMode::Reflect101 => core::cmp::max(2 * length, 3) - 2,
+            _ => {
+                // B. Triggs, M. Sdika, Boundary conditions for Young - van Vliet recursive filtering, 2006.
+                let scale = coefficients.gain
+                    / ((1f64 + a1 - a2 + a3)
+                        * (1f64 - a1 - a2 - a3)
+                        * (1f64 + a2 + (a1 - a3) * a3));
+                return IirBoundary::Clamp([
+                    [
+                        scale * (-a3 * a1 + 1f64 - a3 * a3 - a2),
+                        scale * (a3 + a1) * (a2 + a3 * a1),
+                        scale * a3 * (a1 + a3 * a2),
+                    ],
+                    [
+                        scale * (a1 + a3 * a2),
+                        -scale * (a2 - 1f64) * (a2 + a3 * a1),
+                        -scale * a3 * (a3 * a1 + a3 * a3 + a2 - 1f64),
+                    ],
+                    [
+                        scale * (a3 * a1 + a2 + a1 * a1 - a2 * a2),
+                        scale
+                            * (a1 * a2 + a3 * a2 * a2 - a1 * a3 * a3 - a3 * a3 * a3 - a3 * a2 + a3),
+                        scale * a3 * (a1 + a3 * a2),
+                    ],
+                ]);
+            }
+        };
+        let mut power = [[1f64, 0f64, 0f64], [0f64, 1f64, 0f64], [0f64, 0f64, 1f64]];
+        let mut base = [[a1, a2, a3], [1f64, 0f64, 0f64], [0f64, 1f64, 0f64]];
+        let mut exponent = period;
+        while exponent > 0 {
+            if exponent & 1 != 0 {
+                power = iir_matrix_mul(&power, &base);
+            }
+            base = iir_matrix_mul(&base, &base);
+            exponent >>= 1;
+        }
+        let mut steady = [[0f64; 3]; 3];
+        for (i, row) in steady.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = if i == j { 1f64 } else { 0f64 } - power[i][j];
+            }
+        }
+        IirBoundary::Periodic(period, iir_matrix_inverse(&steady))
+    }
+
+    /// Count of samples that line buffer must hold, line is extended to a full period for periodic boundaries
+    fn line_length(&self, length: usize) -> usize {
+        match self {
+            IirBoundary::Clamp(_) => length,
+            IirBoundary::Periodic(period, _) => *period,
+        }
+    }
+}
+
+/// Last three outputs of the filter, most recent first
+type IirState = [[f64; 4]; 3];
+
+#[inline(always)]
+fn iir_gaussian_step<const CHANNELS_CONFIGURATION: usize>(
+    state: &mut IirState,
+    item: &[f64; 4],
+    coefficients: &IirGaussianCoefficients,
+) -> [f64; 4] {
+    let mut w0 = [0f64; 4];
+    for c in 0..CHANNELS_CONFIGURATION {
+        w0[c] = coefficients.gain * item[c]
+            + coefficients.b1 * state[0][c]
+            + coefficients.b2 * state[1][c]
+            + coefficients.b3 * state[2][c];
+    }
+    state[2] = state[1];
+    state[1] = state[0];
+    state[0] = w0;
+    w0
+}
+
+#[inline(always)]
+fn iir_apply_matrix<const CHANNELS_CONFIGURATION: usize>(
+    matrix: &IirMatrix,
+    state: &IirState,
+) -> IirState {
+    let mut result = [[0f64; 4]; 3];
+    for (row, dst) in matrix.iter().zip(result.iter_mut()) {
+        for c in 0..CHANNELS_CONFIGURATION {
+            dst[c] = row[0] * state[0][c] + row[1] * state[1][c] + row[2] * state[2][c];
+        }
+    }
+    result
+}
+
+/// Runs causal and anti-causal passes over the line in place.
+/// For periodic boundaries line is expected to be extended to a full period.
+fn iir_gaussian_filter_line<const CHANNELS_CONFIGURATION: usize>(
+    line: &mut [[f64; 4]],
+    coefficients: &IirGaussianCoefficients,
+    boundary: &IirBoundary,
+) {
+    let (Some(&first), Some(&last)) = (line.first(), line.last()) else {
+        return;
+    };
+    match boundary {
+        IirBoundary::Clamp(matrix) => {
+            let mut state = [first; 3];
+            for item in line.iter_mut() {
+                *item = iir_gaussian_step::<CHANNELS_CONFIGURATION>(&mut state, item, coefficients);
+            }
+            for c in 0..CHANNELS_CONFIGURATION {
+                for v in state.iter_mut() {
+                    v[c] -= last[c];
+                }
+            }
+            let mut state = iir_apply_matrix::<CHANNELS_CONFIGURATION>(matrix, &state);
+            for v in state.iter_mut() {
+                for c in 0..CHANNELS_CONFIGURATION {
+                    v[c] += last[c];
+                }
+            }
+            // Matrix yields outputs at `n - 1`, `n` and `n + 1`, so the last sample is already done
+            let length = line.len();
+            line[length - 1] = state[0];
+            for item in line[..length - 1].iter_mut().rev() {
+                *item = iir_gaussian_step::<CHANNELS_CONFIGURATION>(&mut state, item, coefficients);
+            }
+        }
+        IirBoundary::Periodic(_, steady) => {
+            let mut state = [[0f64; 4]; 3];
+            for item in line.iter() {
+                iir_gaussian_step::<CHANNELS_CONFIGURATION>(&mut state, item, coefficients);
+            }
+            let mut state = iir_apply_matrix::<CHANNELS_CONFIGURATION>(steady, &state);
+            for item in line.iter_mut() {
+                *item = iir_gaussian_step::<CHANNELS_CONFIGURATION>(&mut state, item, coefficients);
+            }
+            let mut state = [[0f64; 4]; 3];
+            for item in line.iter().rev() {
+                iir_gaussian_step::<CHANNELS_CONFIGURATION>(&mut state, item, coefficients);
+            }
+            let mut state = iir_apply_matrix::<CHANNELS_CONFIGURATION>(steady, &state);
+            for item in line.iter_mut().rev() {
+                *item = iir_gaussian_step::<CHANNELS_CONFIGURATION>(&mut state, item, coefficients);
+            }
+        }
+    }
+}
+
+fn iir_gaussian_vertical_pass<T, const CHANNELS_CONFIGURATION: usize>(
+    bytes: &UnsafeSlice<T>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    start: u32,
+    end: u32,
+) where
+    T: Copy + AsPrimitive<f64>,
+    f64: ToStorage<T>,
+{
+    let coefficients = IirGaussianCoefficients::new(sigma);
+    let boundary = IirBoundary::new(&coefficients, height as usize, edge_mode);
+    let mut line: Vec<[f64; 4]> = vec![[0f64; 4]; boundary.line_length(height as usize)];
+    for x in start..core::cmp::min(width, end) {
+        let current_px = x as usize * CHANNELS_CONFIGURATION;
+        for (i, item) in line.iter_mut().enumerate() {
+            let y = border_position(i as i64, height as usize, edge_mode).unwrap_or(0);
+            let bytes_offset = y * stride as usize + current_px;
+            for (c, v) in item.iter_mut().take(CHANNELS_CONFIGURATION).enumerate() {
+                *v = (*bytes.get(bytes_offset + c)).as_();
+            }
+        }
+        iir_gaussian_filter_line::<CHANNELS_CONFIGURATION>(&mut line, &coefficients, &boundary);
+        for (y, item) in line.iter().take(height as usize).enumerate() {
+            let bytes_offset = y * stride as usize + current_px;
+            for (c, &v) in item.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+                unsafe {
+                    bytes.write(bytes_offset + c, v.to_());
+                }
+            }
+        }
+    }
+}
+
+fn iir_gaussian_horizontal_pass<T, const CHANNELS_CONFIGURATION: usize>(
+    bytes: &UnsafeSlice<T>,
+    stride: u32,
+    width: u32,
+    height: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    start: u32,
+    end: u32,
+) where
+    T: Copy + AsPrimitive<f64>,
+    f64: ToStorage<T>,
+{
+    let coefficients = IirGaussianCoefficients::new(sigma);
+    let boundary = IirBoundary::new(&coefficients, width as usize, edge_mode);
+    let mut line: Vec<[f64; 4]> = vec![[0f64; 4]; boundary.line_length(width as usize)];
+    for y in start..core::cmp::min(height, end) {
+        let current_y = y as usize * stride as usize;
+        for (i, item) in line.iter_mut().enumerate() {
+            let x = border_position(i as i64, width as usize, edge_mode).unwrap_or(0);
+            let bytes_offset = current_y + x * CHANNELS_CONFIGURATION;
+            for (c, v) in item.iter_mut().take(CHANNELS_CONFIGURATION).enumerate() {
+                *v = (*bytes.get(bytes_offset + c)).as_();
+            }
+        }
+        iir_gaussian_filter_line::<CHANNELS_CONFIGURATION>(&mut line, &coefficients, &boundary);
+        for (x, item) in line.iter().take(width as usize).enumerate() {
+            let bytes_offset = current_y + x * CHANNELS_CONFIGURATION;
+            for (c, &v) in item.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+                unsafe {
+                    bytes.write(bytes_offset + c, v.to_());
+                }
+            }
+        }
+    }
+}
+
+fn iir_gaussian_impl<T, const CHANNELS_CONFIGURATION: usize>(
+    bytes: &mut [T],
+    stride: u32,
+    width: u32,
+    height: u32,
+    sigma: f32,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) where
+    T: Copy + Send + Sync + AsPrimitive<f64>,
+    f64: ToStorage<T>,
+{
+    if edge_mode == EdgeMode::KernelClip {
+        panic!("Kernel clip is supported only in gaussian");
+    }
+    let unsafe_image = UnsafeSlice::new(bytes);
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        IIR_GAUSSIAN_WORK_PER_PIXEL * type_work_per_pixel::<T>(),
+    ) as u32;
+    if thread_count == 1 {
+        iir_gaussian_vertical_pass::<T, CHANNELS_CONFIGURATION>(
+            &unsafe_image,
+            stride,
+            width,
+            height,
+            sigma,
+            edge_mode,
+            0,
+            width,
+        );
+        iir_gaussian_horizontal_pass::<T, CHANNELS_CONFIGURATION>(
+            &unsafe_image,
+            stride,
+            width,
+            height,
+            sigma,
+            edge_mode,
+            0,
+            height,
+        );
+        return;
+    }
+    let pool = threading_policy.create_pool(thread_count as usize);
+    pool.scope(|scope| {
+        let segment_size = width / thread_count;
+
+        for i in 0..thread_count {
+            let start_x = i * segment_size;
+            let mut end_x = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_x = width;
+            }
+            scope.spawn(move |_| {
+                iir_gaussian_vertical_pass::<T, CHANNELS_CONFIGURATION>(
+                    &unsafe_image,
+                    stride,
+                    width,
+                    height,
+                    sigma,
+                    edge_mode,
+                    start_x,
+                    end_x,
+                );
+            });
+        }
+    });
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| {
+                iir_gaussian_horizontal_pass::<T, CHANNELS_CONFIGURATION>(
+                    &unsafe_image,
+                    stride,
+                    width,
+                    height,
+                    sigma,
+                    edge_mode,
+                    start_y,
+                    end_y,
+                );
+            });
+        }
+    });
+}
+
+/// Performs recursive gaussian blur on the image.
+///
+/// Third order IIR gaussian ( van Vliet - Young - Verbeek ), cost does not depend on sigma and frequency response is much closer to gaussian than in any box or stack approximations.
+/// Fractional sigma is supported and there is no radius limit, preferred when large sigma gaussian is required.
+/// Internally computed in f64.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Sigma for a gaussian, values less than 0.5 are clamped to 0.5
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn iir_gaussian_blur_f32(
+    bytes: &mut [f32],
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    let stride = width * channels.get_channels() as u32;
+    match channels {
+        FastBlurChannels::Channels3 => {
+            iir_gaussian_impl::<f32, 3>(
+                bytes,
+                stride,
+                width,
+                height,
+                sigma,
+                threading_policy,
+                edge_mode,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            iir_gaussian_impl::<f32, 4>(
+                bytes,
+                stride,
+                width,
+                height,
+                sigma,
+                threading_policy,
+                edge_mode,
+            );
+        }
+    }
+}
+
+/// Performs recursive gaussian blur on the image.
+///
+/// Third order IIR gaussian ( van Vliet - Young - Verbeek ), cost does not depend on sigma and frequency response is much closer to gaussian than in any box or stack approximations.
+/// Fractional sigma is supported and there is no radius limit, preferred when large sigma gaussian is required.
+/// Internally computed in f64.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `sigma` - Sigma for a gaussian, values less than 0.5 are clamped to 0.5
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn iir_gaussian_blur_u16(
+    bytes: &mut [u16],
+    stride: u32,
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    match channels {
+        FastBlurChannels::Channels3 => {
+            iir_gaussian_impl::<u16, 3>(
+                bytes,
+                stride,
+                width,
+                height,
+                sigma,
+                threading_policy,
+                edge_mode,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            iir_gaussian_impl::<u16, 4>(
+                bytes,
+                stride,
+                width,
+                height,
+                sigma,
+                threading_policy,
+                edge_mode,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Filters line padded far beyond the filter support, so boundary state does not matter
+    fn padded_reference(line: &[f64], sigma: f32, edge_mode: EdgeMode) -> Vec<f64> {
+        let coefficients = IirGaussianCoefficients::new(sigma);
+        let pad = line.len() * 4 + (sigma * 40f32) as usize;
+        let mut padded: Vec<[f64; 4]> = (0..line.len() + 2 * pad)
+            .map(|i| {
+                let x = border_position(i as i64 - pad as i64, line.len(), edge_mode).unwrap();
+                [line[x], 0f64, 0f64, 0f64]
+            })
+            .collect();
+        let mut state = [padded[0]; 3];
+        for item in padded.iter_mut() {
+            *item = iir_gaussian_step::<1>(&mut state, item, &coefficients);
+        }
+        let mut state = [*padded.last().unwrap(); 3];
+        for item in padded.iter_mut().rev() {
+            *item = iir_gaussian_step::<1>(&mut state, item, &coefficients);
+        }
+        padded[pad..pad + line.len()].iter().map(|v| v[0]).collect()
+    }
+
+    #[test]
+    fn boundary_matches_padded_filter() {
+        for edge_mode in [
+            EdgeMode::Clamp,
+            EdgeMode::Wrap,
+            EdgeMode::Reflect,
+            EdgeMode::Reflect101,
+        ] {
+            for length in [1usize, 2, 3, 7, 40] {
+                for sigma in [0.5f32, 2f32, 15f32] {
+                    let line: Vec<f64> = (0..length).map(|i| ((i * 37) % 11) as f64).collect();
+                    let coefficients = IirGaussianCoefficients::new(sigma);
+                    let boundary = IirBoundary::new(&coefficients, length, edge_mode);
+                    let mut buffer: Vec<[f64; 4]> = (0..boundary.line_length(length))
+                        .map(|i| {
+                            let x = border_position(i as i64, length, edge_mode).unwrap();
+                            [line[x], 0f64, 0f64, 0f64]
+                        })
+                        .collect();
+                    iir_gaussian_filter_line::<1>(&mut buffer, &coefficients, &boundary);
+                    let reference = padded_reference(&line, sigma, edge_mode);
+                    for (v, r) in buffer.iter().zip(reference.iter()) {
+                        assert!(
+                            (v[0] - r).abs() < 1e-6,
+                            "{:?} length {} sigma {}: {} != {}",
+                            edge_mode,
+                            length,
+                            sigma,
+                            v[0],
+                            r
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn constant_image_stays_constant() {
+        let width = 19u32;
+        let height = 11u32;
+        for edge_mode in [EdgeMode::Clamp, EdgeMode::Wrap, EdgeMode::Reflect101] {
+            let mut image = vec![1234u16; width as usize * height as usize * 3];
+            iir_gaussian_blur_u16(
+                &mut image,
+                width * 3,
+                width,
+                height,
+                25f32,
+                FastBlurChannels::Channels3,
+                ThreadingPolicy::Single,
+                edge_mode,
+            );
+            assert!(image.iter().all(|&v| v == 1234));
+        }
+    }
+
+    #[test]
+    fn impulse_spreads_with_unit_mass() {
+        let width = 64u32;
+        let height = 64u32;
+        let mut image = vec![0f32; width as usize * height as usize * 4];
+        image[(32 * width as usize + 32) * 4] = 1f32;
+        iir_gaussian_blur_f32(
+            &mut image,
+            width,
+            height,
+            3f32,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Single,
+            EdgeMode::Wrap,
+        );
+        let total: f32 = image.iter().step_by(4).sum();
+        assert!((total - 1f32).abs() < 1e-4);
+        assert!(image[(32 * width as usize + 32) * 4] < 0.1f32);
+        assert!(image[(32 * width as usize + 35) * 4] > 0f32);
+    }
+}
@@ -25,17 +25,36 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod adaptive_median;
+mod anisotropic_diffusion;
+mod blur_filter;
+mod blur_plan;
+mod blur_scratch;
 mod r#box;
 mod channels_configuration;
+mod domain_transform;
+mod dual_kawase;
 mod edge_mode;
 mod fast_gaussian;
 mod fast_gaussian_next;
 mod fast_gaussian_superior;
+mod fft;
 mod gaussian;
+mod iir_gaussian;
 mod median_blur;
+mod morphology;
 mod mul_table;
+mod nan_blur;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 mod neon;
+mod non_local_means;
+mod normalized_convolution;
+mod rank_filter;
+mod sigma;
 #[cfg(all(
     any(target_arch = "x86_64", target_arch = "x86"),
     target_feature = "sse4.1"
@@ -43,44 +62,140 @@ mod neon;
 mod sse;
 mod stack_blur;
 mod stack_blur_f32;
+#[cfg(feature = "colorutils")]
 mod stack_blur_linear;
+mod surface_blur;
 mod threading_policy;
+mod tiled_blur;
 mod to_storage;
 mod unsafe_slice;
+mod vector_median;
+mod weighted_median;
 
+pub use adaptive_median::adaptive_median_blur;
+pub use adaptive_median::adaptive_median_blur_plane;
+pub use anisotropic_diffusion::anisotropic_diffusion_f32;
+pub use anisotropic_diffusion::anisotropic_diffusion_plane_f32;
+pub use anisotropic_diffusion::anisotropic_diffusion_plane_u16;
+pub use anisotropic_diffusion::anisotropic_diffusion_u16;
+pub use anisotropic_diffusion::DiffusionConductance;
+pub use blur_filter::blur;
+pub use blur_filter::BlurAlgorithm;
+pub use blur_filter::BlurFilter;
+pub use blur_filter::BlurParams;
+pub use blur_plan::BlurPlan;
+#[cfg(feature = "colorutils")]
+pub use blur_plan::LinearBlurPlan;
+#[cfg(feature = "colorutils")]
+pub use blur_scratch::blur_in_linear_scratch_size;
+#[cfg(feature = "colorutils")]
+pub use blur_scratch::blur_in_linear_with_scratch;
+pub use blur_scratch::blur_scratch_size;
+pub use blur_scratch::blur_with_scratch;
 pub use channels_configuration::FastBlurChannels;
+#[cfg(feature = "colorutils")]
 pub use colorutils_rs::TransferFunction;
+pub use domain_transform::domain_transform;
+pub use domain_transform::domain_transform_f32;
+pub use domain_transform::DomainTransformMode;
+pub use dual_kawase::dual_kawase_blur;
+pub use dual_kawase::dual_kawase_blur_f32;
 pub use edge_mode::*;
 pub use fast_gaussian::fast_gaussian;
 pub use fast_gaussian::fast_gaussian_f16;
 pub use fast_gaussian::fast_gaussian_f32;
+#[cfg(feature = "colorutils")]
 pub use fast_gaussian::fast_gaussian_in_linear;
+pub use fast_gaussian::fast_gaussian_sigma;
+pub use fast_gaussian::fast_gaussian_sigma_f32;
 pub use fast_gaussian::fast_gaussian_u16;
 pub use fast_gaussian_next::fast_gaussian_next;
 pub use fast_gaussian_next::fast_gaussian_next_f16;
 pub use fast_gaussian_next::fast_gaussian_next_f32;
+#[cfg(feature = "colorutils")]
 pub use fast_gaussian_next::fast_gaussian_next_in_linear;
+pub use fast_gaussian_next::fast_gaussian_next_sigma;
+pub use fast_gaussian_next::fast_gaussian_next_sigma_f32;
 pub use fast_gaussian_next::fast_gaussian_next_u16;
 pub use fast_gaussian_superior::fast_gaussian_superior;
 pub use gaussian::gaussian_blur;
 pub use gaussian::gaussian_blur_f16;
 pub use gaussian::gaussian_blur_f32;
+pub use gaussian::gaussian_blur_f32_scratch_size;
+pub use gaussian::gaussian_blur_f32_with_scratch;
+#[cfg(feature = "colorutils")]
 pub use gaussian::gaussian_blur_in_linear;
 pub use gaussian::gaussian_blur_u16;
+pub use gaussian::gaussian_blur_u16_scratch_size;
+pub use gaussian::gaussian_blur_u16_with_scratch;
+pub use iir_gaussian::iir_gaussian_blur_f32;
+pub use iir_gaussian::iir_gaussian_blur_u16;
 pub use median_blur::median_blur;
+pub use median_blur::median_blur_u16;
+pub use morphology::morphology;
+pub use morphology::morphology_f32;
+pub use morphology::morphology_plane;
+pub use morphology::morphology_plane_f32;
+pub use morphology::morphology_u16;
+pub use morphology::MorphologyOp;
+pub use nan_blur::fast_gaussian_nan_f32;
+pub use nan_blur::fast_gaussian_nan_plane_f32;
+pub use nan_blur::gaussian_blur_nan_f32;
+pub use nan_blur::gaussian_blur_nan_plane_f32;
+pub use nan_blur::stack_blur_nan_f32;
+pub use nan_blur::stack_blur_nan_plane_f32;
+pub use non_local_means::non_local_means;
+pub use non_local_means::non_local_means_f32;
+pub use non_local_means::non_local_means_plane;
+pub use non_local_means::non_local_means_plane_f32;
+pub use normalized_convolution::normalized_fast_gaussian_f32;
+pub use normalized_convolution::normalized_fast_gaussian_plane_f32;
+pub use normalized_convolution::normalized_gaussian_blur_f32;
+pub use normalized_convolution::normalized_gaussian_blur_plane_f32;
 pub use r#box::box_blur;
 pub use r#box::box_blur_f32;
+#[cfg(feature = "colorutils")]
 pub use r#box::box_blur_in_linear;
 pub use r#box::box_blur_u16;
 pub use r#box::gaussian_box_blur;
 pub use r#box::gaussian_box_blur_f32;
+pub use r#box::gaussian_box_blur_f32_with_scratch;
+#[cfg(feature = "colorutils")]
 pub use r#box::gaussian_box_blur_in_linear;
+pub use r#box::gaussian_box_blur_scratch_size;
+pub use r#box::gaussian_box_blur_sigma;
+pub use r#box::gaussian_box_blur_sigma_f32;
 pub use r#box::gaussian_box_blur_u16;
+pub use r#box::gaussian_box_blur_u16_with_scratch;
 pub use r#box::tent_blur;
 pub use r#box::tent_blur_f32;
+pub use r#box::tent_blur_f32_with_scratch;
+#[cfg(feature = "colorutils")]
 pub use r#box::tent_blur_in_linear;
+pub use r#box::tent_blur_scratch_size;
+pub use r#box::tent_blur_sigma;
+pub use r#box::tent_blur_sigma_f32;
 pub use r#box::tent_blur_u16;
+pub use r#box::tent_blur_u16_with_scratch;
+pub use rank_filter::rank_filter;
+pub use rank_filter::rank_filter_u16;
+pub use rank_filter::RankWindow;
+pub use sigma::*;
 pub use stack_blur::stack_blur;
+pub use stack_blur::stack_blur_scratch_size;
+pub use stack_blur::stack_blur_sigma;
+pub use stack_blur::stack_blur_with_scratch;
 pub use stack_blur_f32::stack_blur_f32;
+pub use stack_blur_f32::stack_blur_f32_scratch_size;
+pub use stack_blur_f32::stack_blur_f32_with_scratch;
+pub use stack_blur_f32::stack_blur_sigma_f32;
+#[cfg(feature = "colorutils")]
 pub use stack_blur_linear::stack_blur_in_linear;
+pub use surface_blur::surface_blur;
+pub use surface_blur::surface_blur_f32;
 pub use threading_policy::*;
+pub use tiled_blur::blur_tiled;
+pub use tiled_blur::TileRegion;
+pub use vector_median::vector_median_blur;
+pub use weighted_median::center_weighted_median_blur;
+pub use weighted_median::weighted_median_blur;
@@ -26,156 +26,218 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::channels_configuration::FastBlurChannels;
+use crate::edge_mode::border_position;
+use crate::rank_filter::{
+    histogram_rank, kernel_rank, rank_filter, rank_filter_u16, segment_rank, RankWindow,
+};
+use crate::threading_policy::BlurPool;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::ThreadingPolicy;
+use crate::{RankEdgeMode, ThreadingPolicy};
+use alloc::vec;
 
-struct MedianHistogram {
-    r: [i32; 256],
-    g: [i32; 256],
-    b: [i32; 256],
-    a: [i32; 256],
-    n: i32,
+/// Count of coarse bins of u8 histogram, each coarse bin covers 16 fine bins
+const COARSE_BINS_U8: usize = 16;
+const FINE_BINS_U8: usize = 256;
+
+/// Per column histograms of a `2 * radius + 1` tall column for every channel, coarse level has 16 bins and fine 256
+struct MedianColumns<'a, const CHANNELS_CONFIGURATION: usize> {
+    coarse: &'a mut [u16],
+    fine: &'a mut [u16],
 }
 
-fn add_rgb_pixels<const CHANNEL_CONFIGURATION: usize>(
-    src: &[u8],
-    src_stride: u32,
-    y: i64,
-    x: i64,
-    width: u32,
-    height: u32,
-    size: u32,
-    histogram: &mut MedianHistogram,
-) {
-    if x < 0 || x >= width as i64 {
-        return;
+impl<'a, const CHANNELS_CONFIGURATION: usize> MedianColumns<'a, CHANNELS_CONFIGURATION> {
+    /// Count of u16 in the histograms of all columns
+    fn size(width: u32) -> usize {
+        width as usize * CHANNELS_CONFIGURATION * (COARSE_BINS_U8 + FINE_BINS_U8)
     }
-    let px = x as usize * CHANNEL_CONFIGURATION;
-    let cap = std::cmp::min(y + size as i64, height as i64 - 1);
-    let start = y - size as i64;
-    for i in start..=cap {
-        if i < 0 {
-            continue;
-        }
-        let y_shift = i as usize * src_stride as usize;
-        let bytes_offset = y_shift + px;
-        let v0 = unsafe { *src.get_unchecked(bytes_offset) };
-        unsafe {
-            let k = histogram.r.get_unchecked_mut(usize::from(v0));
-            let x = *k + 1;
-            *k = x;
-        }
-        let v1 = unsafe { *src.get_unchecked(bytes_offset + 1) };
-        unsafe {
-            let k = histogram.g.get_unchecked_mut(usize::from(v1));
-            let x = *k + 1;
-            *k = x;
-        }
-        let v2 = unsafe { *src.get_unchecked(bytes_offset + 2) };
-        unsafe {
-            let k = histogram.b.get_unchecked_mut(usize::from(v2));
-            let x = *k + 1;
-            *k = x;
+
+    /// Places empty histograms into the start of `storage`
+    fn new(storage: &'a mut [u16], width: u32) -> Self {
+        let columns = width as usize * CHANNELS_CONFIGURATION;
+        let (coarse, fine) = storage[..Self::size(width)].split_at_mut(columns * COARSE_BINS_U8);
+        coarse.fill(0);
+        fine.fill(0);
+        MedianColumns { coarse, fine }
+    }
+
+    #[inline(always)]
+    fn add_row(&mut self, src: &[u8], src_stride: u32, y: usize, width: u32) {
+        let row = &src[y * src_stride as usize..];
+        for (column, &v) in row[..width as usize * CHANNELS_CONFIGURATION]
+            .iter()
+            .enumerate()
+        {
+            let v = v as usize;
+            unsafe {
+                *self
+                    .coarse
+                    .get_unchecked_mut(column * COARSE_BINS_U8 + (v >> 4)) += 1;
+                *self.fine.get_unchecked_mut(column * FINE_BINS_U8 + v) += 1;
+            }
         }
-        if CHANNEL_CONFIGURATION == 4 {
+    }
+
+    #[inline(always)]
+    fn remove_row(&mut self, src: &[u8], src_stride: u32, y: usize, width: u32) {
+        let row = &src[y * src_stride as usize..];
+        for (column, &v) in row[..width as usize * CHANNELS_CONFIGURATION]
+            .iter()
+            .enumerate()
+        {
+            let v = v as usize;
             unsafe {
-                let v3 = *src.get_unchecked(bytes_offset + 3);
-                let k = histogram.a.get_unchecked_mut(usize::from(v3));
-                let x = *k + 1;
-                *k = x;
+                *self
+                    .coarse
+                    .get_unchecked_mut(column * COARSE_BINS_U8 + (v >> 4)) -= 1;
+                *self.fine.get_unchecked_mut(column * FINE_BINS_U8 + v) -= 1;
             }
         }
-        histogram.n += 1;
     }
 }
 
-fn remove_rgb_pixels<const CHANNELS_CONFIGURATION: usize>(
-    src: &[u8],
-    src_stride: u32,
-    y: i64,
-    x: i64,
-    width: u32,
-    height: u32,
-    size: u32,
-    histogram: &mut MedianHistogram,
-) {
-    if x < 0 || x >= width as i64 {
-        return;
-    }
-    let px = x as usize * CHANNELS_CONFIGURATION;
-    let cap = std::cmp::min(y + size as i64, height as i64 - 1);
-    let start = y - size as i64;
-    for i in start..=cap {
-        if i < 0 {
-            continue;
-        }
-        let y_shift = i as usize * src_stride as usize;
-        let bytes_offset = y_shift + px;
-        let v0 = unsafe { *src.get_unchecked(bytes_offset) };
-        unsafe {
-            let k = histogram.r.get_unchecked_mut(usize::from(v0));
-            let x = *k - 1;
-            *k = x;
+/// Kernel histogram, coarse level is kept up to date on each step,
+/// fine segments are synchronized lazily only when median falls into it
+struct MedianKernel {
+    coarse: [[u32; COARSE_BINS_U8]; 4],
+    fine: [[u32; FINE_BINS_U8]; 4],
+    /// Kernel center position for which fine segment is valid
+    fine_position: [[i64; COARSE_BINS_U8]; 4],
+}
+
+impl MedianKernel {
+    fn new() -> MedianKernel {
+        MedianKernel {
+            coarse: [[0u32; COARSE_BINS_U8]; 4],
+            fine: [[0u32; FINE_BINS_U8]; 4],
+            fine_position: [[i64::MIN; COARSE_BINS_U8]; 4],
         }
-        let v1 = unsafe { *src.get_unchecked(bytes_offset + 1) };
-        unsafe {
-            let k = histogram.g.get_unchecked_mut(usize::from(v1));
-            let x = *k - 1;
-            *k = x;
+    }
+
+    fn reset(&mut self) {
+        self.coarse = [[0u32; COARSE_BINS_U8]; 4];
+        self.fine_position = [[i64::MIN; COARSE_BINS_U8]; 4];
+    }
+
+    #[inline(always)]
+    fn add_column_coarse<const CHANNELS_CONFIGURATION: usize>(
+        &mut self,
+        columns: &MedianColumns<CHANNELS_CONFIGURATION>,
+        x: usize,
+    ) {
+        for c in 0..CHANNELS_CONFIGURATION {
+            let offset = (x * CHANNELS_CONFIGURATION + c) * COARSE_BINS_U8;
+            let column = &columns.coarse[offset..offset + COARSE_BINS_U8];
+            for (dst, &v) in self.coarse[c].iter_mut().zip(column.iter()) {
+                *dst += v as u32;
+            }
         }
-        let v2 = unsafe { *src.get_unchecked(bytes_offset + 2) };
-        unsafe {
-            let k = histogram.b.get_unchecked_mut(usize::from(v2));
-            let x = *k - 1;
-            *k = x;
+    }
+
+    #[inline(always)]
+    fn remove_column_coarse<const CHANNELS_CONFIGURATION: usize>(
+        &mut self,
+        columns: &MedianColumns<CHANNELS_CONFIGURATION>,
+        x: usize,
+    ) {
+        for c in 0..CHANNELS_CONFIGURATION {
+            let offset = (x * CHANNELS_CONFIGURATION + c) * COARSE_BINS_U8;
+            let column = &columns.coarse[offset..offset + COARSE_BINS_U8];
+            for (dst, &v) in self.coarse[c].iter_mut().zip(column.iter()) {
+                *dst -= v as u32;
+            }
         }
-        if CHANNELS_CONFIGURATION == 4 {
-            let v3 = unsafe { *src.get_unchecked(bytes_offset + 3) };
-            let k = unsafe { histogram.a.get_unchecked_mut(usize::from(v3)) };
-            let x = *k - 1;
-            *k = x;
+    }
+
+    /// Brings fine segment up to date for kernel centered at `x`
+    fn sync_fine<const CHANNELS_CONFIGURATION: usize>(
+        &mut self,
+        columns: &MedianColumns<CHANNELS_CONFIGURATION>,
+        channel: usize,
+        segment: usize,
+        x: i64,
+        radius: i64,
+        width: u32,
+        edge_mode: RankEdgeMode,
+    ) {
+        let fine_start = segment * COARSE_BINS_U8;
+        let last = self.fine_position[channel][segment];
+        let fine = &mut self.fine[channel][fine_start..fine_start + COARSE_BINS_U8];
+        let column_segment = |column: usize| {
+            let offset = (column * CHANNELS_CONFIGURATION + channel) * FINE_BINS_U8 + fine_start;
+            &columns.fine[offset..offset + COARSE_BINS_U8]
+        };
+        if last == i64::MIN || x - last > 2 * radius + 1 {
+            fine.fill(0);
+            for dx in -radius..=radius {
+                if let Some(column) = border_position(x + dx, width as usize, edge_mode.border()) {
+                    for (dst, &v) in fine.iter_mut().zip(column_segment(column).iter()) {
+                        *dst += v as u32;
+                    }
+                }
+            }
+        } else {
+            for position in last + 1..=x {
+                if let Some(column) =
+                    border_position(position - radius - 1, width as usize, edge_mode.border())
+                {
+                    for (dst, &v) in fine.iter_mut().zip(column_segment(column).iter()) {
+                        *dst -= v as u32;
+                    }
+                }
+                if let Some(column) =
+                    border_position(position + radius, width as usize, edge_mode.border())
+                {
+                    for (dst, &v) in fine.iter_mut().zip(column_segment(column).iter()) {
+                        *dst += v as u32;
+                    }
+                }
+            }
         }
-        histogram.n -= 1;
+        self.fine_position[channel][segment] = x;
     }
 }
 
-fn init_histogram<const CHANNELS_CONFIGURATION: usize>(
+/// Constant time rank filter with square window
+///
+/// S. Perreault, P. Hébert, Median Filtering in Constant Time, 2007.
+/// Column histograms are updated by one row on each step down,
+/// kernel histogram is updated by one column on each step right
+pub(crate) fn rank_filter_columns_impl<const CHANNELS_CONFIGURATION: usize>(
     src: &[u8],
     src_stride: u32,
-    y: u32,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: u32,
     width: u32,
     height: u32,
     radius: u32,
-    histogram: &mut MedianHistogram,
+    percentile: f64,
+    edge_mode: RankEdgeMode,
+    start_y: u32,
+    end_y: u32,
 ) {
-    histogram.r = [0; 256];
-    histogram.g = [0; 256];
-    histogram.b = [0; 256];
-    histogram.a = [0; 256];
-    histogram.n = 0;
-
-    for j in 0..std::cmp::min(radius, width) {
-        add_rgb_pixels::<CHANNELS_CONFIGURATION>(
-            src, src_stride, y as i64, j as i64, width, height, radius, histogram,
-        );
-    }
-}
-
-fn median_filter(x: [i32; 256], n: i32) -> i32 {
-    let mut n = n / 2;
-    let mut i = 0i64;
-    while i < 256 && i >= 0 {
-        n -= x[i as usize];
-        if n > 0 {
-            i += 1;
-        } else {
-            break;
-        }
+    if start_y >= end_y {
+        return;
     }
-    i as i32
+    let mut histograms = vec![0u16; MedianColumns::<CHANNELS_CONFIGURATION>::size(width)];
+    rank_filter_columns_in::<CHANNELS_CONFIGURATION>(
+        &mut histograms,
+        src,
+        src_stride,
+        unsafe_dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        percentile,
+        edge_mode,
+        start_y,
+        end_y,
+    );
 }
 
-fn median_blur_impl<const CHANNELS_CONFIGURATION: usize>(
+/// Same as *rank_filter_columns_impl* with column histograms placed into `histograms`
+fn rank_filter_columns_in<const CHANNELS_CONFIGURATION: usize>(
+    histograms: &mut [u16],
     src: &[u8],
     src_stride: u32,
     unsafe_dst: &UnsafeSlice<u8>,
@@ -183,94 +245,158 @@ fn median_blur_impl<const CHANNELS_CONFIGURATION: usize>(
     width: u32,
     height: u32,
     radius: u32,
+    percentile: f64,
+    edge_mode: RankEdgeMode,
     start_y: u32,
     end_y: u32,
 ) {
+    if start_y >= end_y {
+        return;
+    }
+    let radius = radius as i64;
+    let window = ((2 * radius + 1) * (2 * radius + 1)) as u32;
+    let mut columns = MedianColumns::<CHANNELS_CONFIGURATION>::new(histograms, width);
+    let mut kernel = MedianKernel::new();
+
+    for dy in -radius..=radius {
+        if let Some(row) = border_position(start_y as i64 + dy, height as usize, edge_mode.border())
+        {
+            columns.add_row(src, src_stride, row, width);
+        }
+    }
+
     for y in start_y..end_y {
-        let y_src_offset = y as usize * src_stride as usize;
+        let y_wide = y as i64;
+        if y > start_y {
+            if let Some(row) =
+                border_position(y_wide - radius - 1, height as usize, edge_mode.border())
+            {
+                columns.remove_row(src, src_stride, row, width);
+            }
+            if let Some(row) = border_position(y_wide + radius, height as usize, edge_mode.border())
+            {
+                columns.add_row(src, src_stride, row, width);
+            }
+        }
+
+        kernel.reset();
+        for dx in -radius..=radius {
+            if let Some(column) = border_position(dx, width as usize, edge_mode.border()) {
+                kernel.add_column_coarse(&columns, column);
+            }
+        }
+
         let y_dst_offset = y as usize * dst_stride as usize;
-        let mut histogram = MedianHistogram {
-            r: [0; 256],
-            g: [0; 256],
-            b: [0; 256],
-            a: [0; 256],
-            n: 0,
-        };
+
         for x in 0..width {
-            let px = x as usize * CHANNELS_CONFIGURATION;
-            if x == 0 {
-                init_histogram::<CHANNELS_CONFIGURATION>(
-                    src,
-                    src_stride,
-                    y,
-                    width,
-                    height,
-                    radius,
-                    &mut histogram,
-                );
-            } else {
-                remove_rgb_pixels::<CHANNELS_CONFIGURATION>(
-                    src,
-                    src_stride,
-                    y as i64,
-                    x as i64 - radius as i64,
-                    width,
-                    height,
-                    radius,
-                    &mut histogram,
-                );
-                add_rgb_pixels::<CHANNELS_CONFIGURATION>(
-                    src,
-                    src_stride,
-                    y as i64,
-                    x as i64 + radius as i64,
-                    width,
-                    height,
-                    radius,
-                    &mut histogram,
-                );
+            let x_wide = x as i64;
+            if x > 0 {
+                if let Some(column) =
+                    border_position(x_wide - radius - 1, width as usize, edge_mode.border())
+                {
+                    kernel.remove_column_coarse(&columns, column);
+                }
+                if let Some(column) =
+                    border_position(x_wide + radius, width as usize, edge_mode.border())
+                {
+                    kernel.add_column_coarse(&columns, column);
+                }
             }
 
-            if histogram.n > 0 {
-                unsafe {
-                    let bytes_offset = y_dst_offset + px;
-                    unsafe_dst.write(bytes_offset, median_filter(histogram.r, histogram.n) as u8);
-                    unsafe_dst.write(
-                        bytes_offset + 1,
-                        median_filter(histogram.g, histogram.n) as u8,
-                    );
-                    unsafe_dst.write(
-                        bytes_offset + 2,
-                        median_filter(histogram.b, histogram.n) as u8,
+            let px = x as usize * CHANNELS_CONFIGURATION;
+            for c in 0..CHANNELS_CONFIGURATION {
+                let count: u32 = kernel.coarse[c].iter().sum();
+                let value = if let Some(rank) = kernel_rank(count, window, percentile, edge_mode) {
+                    let (segment, rank) = histogram_rank(&kernel.coarse[c], rank);
+                    kernel.sync_fine(&columns, c, segment, x_wide, radius, width, edge_mode);
+                    let fine_start = segment * COARSE_BINS_U8;
+                    let bin = segment_rank(
+                        &kernel.fine[c][fine_start..fine_start + COARSE_BINS_U8],
+                        rank,
                     );
-                    if CHANNELS_CONFIGURATION == 4 {
-                        unsafe_dst.write(
-                            bytes_offset + 3,
-                            median_filter(histogram.a, histogram.n) as u8,
-                        );
-                    }
-                }
-            } else {
+                    (fine_start + bin) as u8
+                } else {
+                    0u8
+                };
                 unsafe {
-                    let bytes_offset = y_dst_offset + px;
-                    let src_offset = y_src_offset + px;
-                    unsafe_dst.write(bytes_offset, *src.get_unchecked(src_offset));
-                    unsafe_dst.write(bytes_offset + 1, *src.get_unchecked(src_offset + 1));
-                    unsafe_dst.write(bytes_offset + 2, *src.get_unchecked(src_offset + 2));
-                    if CHANNELS_CONFIGURATION == 4 {
-                        unsafe_dst.write(bytes_offset + 3, *src.get_unchecked(src_offset + 3));
-                    }
+                    unsafe_dst.write(y_dst_offset + px + c, value);
                 }
             }
         }
     }
 }
 
+/// Count of u16 in the column histograms of one thread for *median_blur_in_pool*
+pub(crate) fn median_histograms_size(width: u32, channels: FastBlurChannels) -> usize {
+    match channels {
+        FastBlurChannels::Channels3 => MedianColumns::<3>::size(width),
+        FastBlurChannels::Channels4 => MedianColumns::<4>::size(width),
+    }
+}
+
+/// Performs *median_blur* with square window in the thread pool,
+/// `histograms` holds *median_histograms_size* elements for each of `thread_count` threads
+pub(crate) fn median_blur_in_pool(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    edge_mode: RankEdgeMode,
+    histograms: &mut [u16],
+    pool: &BlurPool,
+    thread_count: u32,
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let size = median_histograms_size(width, channels);
+    if histograms.len() < size * thread_count as usize {
+        panic!(
+            "Median histograms have {} elements but {} is required",
+            histograms.len(),
+            size * thread_count as usize
+        );
+    }
+    let unsafe_dst = UnsafeSlice::new(dst);
+    let unsafe_dst = &unsafe_dst;
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for (i, histograms) in histograms
+            .chunks_exact_mut(size)
+            .take(thread_count as usize)
+            .enumerate()
+        {
+            let i = i as u32;
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| {
+                let executor = match channels {
+                    FastBlurChannels::Channels3 => rank_filter_columns_in::<3>,
+                    FastBlurChannels::Channels4 => rank_filter_columns_in::<4>,
+                };
+                executor(
+                    histograms, src, src_stride, unsafe_dst, dst_stride, width, height, radius,
+                    0.5f64, edge_mode, start_y, end_y,
+                );
+            });
+        }
+    });
+}
+
 /// Performs median blur on the image.
 ///
 /// This performs a median kernel filter on the image producing edge preserving blur result.
 /// Preferred if you need to save edges.
-/// O(R) complexity.
+/// Square window uses Perreault - Hébert algorithm with column histograms and coarse/fine bins with O(1) complexity,
+/// this is available only for u8, see *median_blur_u16* for 16 bit images,
+/// circular window exchanges row spans on each step with O(R) complexity and avoids blocky look on a large radius.
 ///
 /// # Arguments
 ///
@@ -278,7 +404,10 @@ fn median_blur_impl<const CHANNELS_CONFIGURATION: usize>(
 /// * `width` - Width of the image
 /// * `height` - Height of the image
 /// * `radius` - Radius of kernel
+/// * `window` - Window shape, see *RankWindow*
 /// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, see *RankEdgeMode*, *KernelClip* ignores them and *Constant* treats them as zero
 ///
 /// # Panics
 /// Panic is stride/width/height/channel configuration do not match provided
@@ -290,51 +419,208 @@ pub fn median_blur(
     width: u32,
     height: u32,
     radius: u32,
+    window: RankWindow,
     channels: FastBlurChannels,
     threading_policy: ThreadingPolicy,
+    edge_mode: RankEdgeMode,
 ) {
-    let unsafe_dst = UnsafeSlice::new(dst);
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
-    pool.scope(|scope| {
-        let segment_size = height / thread_count;
-        for i in 0..thread_count {
-            let start_y = i * segment_size;
-            let mut end_y = (i + 1) * segment_size;
-            if i == thread_count - 1 {
-                end_y = height;
-            }
-            scope.spawn(move |_| match channels {
-                FastBlurChannels::Channels3 => {
-                    median_blur_impl::<3>(
-                        src,
-                        src_stride,
-                        &unsafe_dst,
-                        dst_stride,
-                        width,
-                        height,
-                        radius,
-                        start_y,
-                        end_y,
-                    );
-                }
-                FastBlurChannels::Channels4 => {
-                    median_blur_impl::<4>(
-                        src,
-                        src_stride,
-                        &unsafe_dst,
-                        dst_stride,
-                        width,
-                        height,
-                        radius,
-                        start_y,
-                        end_y,
-                    );
+    rank_filter(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        50f32,
+        window,
+        channels,
+        threading_policy,
+        edge_mode,
+    );
+}
+
+/// Performs median blur on the u16 image.
+///
+/// This performs a median kernel filter on the image producing edge preserving blur result.
+/// Preferred if you need to save edges.
+/// Full 16 bit column histograms are too large, so there is no constant time path for u16,
+/// two-level kernel histogram exchanges a whole row or column of the window on each step.
+/// O(R) complexity for any window shape.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length in elements, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length in elements, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - Radius of kernel
+/// * `window` - Window shape, see *RankWindow*
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, see *RankEdgeMode*, *KernelClip* ignores them and *Constant* treats them as zero
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn median_blur_u16(
+    src: &[u16],
+    src_stride: u32,
+    dst: &mut [u16],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    window: RankWindow,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: RankEdgeMode,
+) {
+    rank_filter_u16(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        50f32,
+        window,
+        channels,
+        threading_policy,
+        edge_mode,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force_median(
+        src: &[u8],
+        width: usize,
+        height: usize,
+        radius: i64,
+        channels: usize,
+        edge_mode: RankEdgeMode,
+    ) -> Vec<u8> {
+        let mut dst = vec![0u8; src.len()];
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                for c in 0..channels {
+                    let mut window = Vec::new();
+                    for ky in y - radius..=y + radius {
+                        for kx in x - radius..=x + radius {
+                            let (Some(sy), Some(sx)) = (
+                                border_position(ky, height, edge_mode.border()),
+                                border_position(kx, width, edge_mode.border()),
+                            ) else {
+                                continue;
+                            };
+                            window.push(src[(sy * width + sx) * channels + c]);
+                        }
+                    }
+                    window.sort_unstable();
+                    let rank = ((window.len() - 1) as f64 * 0.5f64).round() as usize;
+                    dst[(y as usize * width + x as usize) * channels + c] = window[rank];
                 }
-            });
+            }
         }
-    });
+        dst
+    }
+
+    #[test]
+    fn square_median_matches_brute_force() {
+        let width = 23usize;
+        let height = 17usize;
+        let src: Vec<u8> = (0..width * height * 3)
+            .map(|i| ((i * 7919) % 251) as u8)
+            .collect();
+        for edge_mode in [RankEdgeMode::Clamp, RankEdgeMode::KernelClip] {
+            for radius in [1u32, 4] {
+                let mut dst = vec![0u8; src.len()];
+                median_blur(
+                    &src,
+                    width as u32 * 3,
+                    &mut dst,
+                    width as u32 * 3,
+                    width as u32,
+                    height as u32,
+                    radius,
+                    RankWindow::Square,
+                    FastBlurChannels::Channels3,
+                    ThreadingPolicy::Single,
+                    edge_mode,
+                );
+                let reference =
+                    brute_force_median(&src, width, height, radius as i64, 3, edge_mode);
+                assert_eq!(dst, reference, "{:?} radius {}", edge_mode, radius);
+            }
+        }
+    }
+
+    #[test]
+    fn u16_matches_u8() {
+        let width = 31usize;
+        let height = 12usize;
+        let src: Vec<u8> = (0..width * height * 4)
+            .map(|i| ((i * 104729) % 256) as u8)
+            .collect();
+        let src16: Vec<u16> = src.iter().map(|&v| v as u16 * 257).collect();
+        for window in [RankWindow::Square, RankWindow::Circle] {
+            let mut dst = vec![0u8; src.len()];
+            median_blur(
+                &src,
+                width as u32 * 4,
+                &mut dst,
+                width as u32 * 4,
+                width as u32,
+                height as u32,
+                3,
+                window,
+                FastBlurChannels::Channels4,
+                ThreadingPolicy::Single,
+                RankEdgeMode::Clamp,
+            );
+            let mut dst16 = vec![0u16; src.len()];
+            median_blur_u16(
+                &src16,
+                width as u32 * 4,
+                &mut dst16,
+                width as u32 * 4,
+                width as u32,
+                height as u32,
+                3,
+                window,
+                FastBlurChannels::Channels4,
+                ThreadingPolicy::Single,
+                RankEdgeMode::Clamp,
+            );
+            let expected: Vec<u16> = dst.iter().map(|&v| v as u16 * 257).collect();
+            assert_eq!(dst16, expected, "{:?}", window);
+        }
+    }
+
+    #[test]
+    fn impulse_is_removed_and_constant_kept() {
+        let width = 16usize;
+        let height = 16usize;
+        let mut src = vec![90u8; width * height * 3];
+        src[(8 * width + 8) * 3] = 255;
+        src[(3 * width + 11) * 3 + 1] = 0;
+        let mut dst = vec![0u8; src.len()];
+        median_blur(
+            &src,
+            width as u32 * 3,
+            &mut dst,
+            width as u32 * 3,
+            width as u32,
+            height as u32,
+            1,
+            RankWindow::Square,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+            RankEdgeMode::Clamp,
+        );
+        assert!(dst.iter().all(|&v| v == 90));
+    }
 }
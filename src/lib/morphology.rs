@@ -0,0 +1,922 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::ThreadingPolicy;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+/// Declares morphological operation
+pub enum MorphologyOp {
+    /// Minimum over rectangular structuring element, shrinks bright regions
+    Erode = 0,
+    /// Maximum over rectangular structuring element, grows bright regions
+    Dilate = 1,
+    /// Erosion followed by dilation, removes small bright details
+    Open = 2,
+    /// Dilation followed by erosion, removes small dark details
+    Close = 3,
+    /// Difference between image and its opening, keeps small bright details
+    TopHat = 4,
+    /// Difference between closing and the image, keeps small dark details
+    BlackHat = 5,
+    /// Difference between dilation and erosion, outlines edges
+    Gradient = 6,
+}
+
+pub(crate) trait MorphologyValue: Copy + Send + Sync + PartialOrd + 'static {
+    const MIN: Self;
+    const MAX: Self;
+    fn saturating_difference(self, other: Self) -> Self;
+}
+
+impl MorphologyValue for u8 {
+    const MIN: Self = u8::MIN;
+    const MAX: Self = u8::MAX;
+
+    #[inline(always)]
+    fn saturating_difference(self, other: Self) -> Self {
+        self.saturating_sub(other)
+    }
+}
+
+impl MorphologyValue for u16 {
+    const MIN: Self = u16::MIN;
+    const MAX: Self = u16::MAX;
+
+    #[inline(always)]
+    fn saturating_difference(self, other: Self) -> Self {
+        self.saturating_sub(other)
+    }
+}
+
+impl MorphologyValue for f32 {
+    const MIN: Self = f32::NEG_INFINITY;
+    const MAX: Self = f32::INFINITY;
+
+    #[inline(always)]
+    fn saturating_difference(self, other: Self) -> Self {
+        (self - other).max(0f32)
+    }
+}
+
+#[inline(always)]
+fn morphology_pick<T: MorphologyValue, const DILATE: bool>(a: T, b: T) -> T {
+    if DILATE {
+        if b > a {
+            b
+        } else {
+            a
+        }
+    } else if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+/// Maximum count of elements processed at once in vertical pass
+const MORPHOLOGY_VERTICAL_LANES: usize = 256;
+
+/// Min or max filter in a window `2 * radius + 1` over `count` steps of `lanes` elements each.
+///
+/// M. van Herk, A fast algorithm for local minimum and maximum filters on rectangular and octagonal kernels, 1992.
+/// J. Gil, M. Werman, Computing 2-D min, median, and max filters, 1993.
+/// Padded line is split into blocks of window size, prefix and suffix extrema inside blocks
+/// gives extremum in any window with one comparison. Out of bounds values do not affect result.
+fn van_herk_pass<T: MorphologyValue, const DILATE: bool>(
+    src: &[T],
+    src_offset: usize,
+    src_step: usize,
+    unsafe_dst: &UnsafeSlice<T>,
+    dst_offset: usize,
+    dst_step: usize,
+    lanes: usize,
+    count: usize,
+    radius: usize,
+    prefix: &mut Vec<T>,
+    suffix: &mut Vec<T>,
+) {
+    let neutral = if DILATE { T::MIN } else { T::MAX };
+    let window = 2 * radius + 1;
+    let padded = count + 2 * radius;
+    prefix.clear();
+    prefix.resize(padded * lanes, neutral);
+    suffix.clear();
+    suffix.resize(padded * lanes, neutral);
+
+    for i in 0..count {
+        let src_row = &src[src_offset + i * src_step..src_offset + i * src_step + lanes];
+        let p = (i + radius) * lanes;
+        prefix[p..p + lanes].copy_from_slice(src_row);
+        suffix[p..p + lanes].copy_from_slice(src_row);
+    }
+
+    for p in 1..padded {
+        if p % window == 0 {
+            continue;
+        }
+        let (previous, current) = prefix.split_at_mut(p * lanes);
+        let previous = &previous[(p - 1) * lanes..];
+        for (dst, &v) in current[..lanes].iter_mut().zip(previous.iter()) {
+            *dst = morphology_pick::<T, DILATE>(*dst, v);
+        }
+    }
+
+    for p in (0..padded - 1).rev() {
+        if p % window == window - 1 {
+            continue;
+        }
+        let (current, next) = suffix.split_at_mut((p + 1) * lanes);
+        let current = &mut current[p * lanes..];
+        for (dst, &v) in current.iter_mut().zip(next[..lanes].iter()) {
+            *dst = morphology_pick::<T, DILATE>(*dst, v);
+        }
+    }
+
+    for i in 0..count {
+        let backward = &suffix[i * lanes..(i + 1) * lanes];
+        let forward = &prefix[(i + window - 1) * lanes..(i + window) * lanes];
+        let dst = dst_offset + i * dst_step;
+        for (lane, (&a, &b)) in backward.iter().zip(forward.iter()).enumerate() {
+            unsafe {
+                unsafe_dst.write(dst + lane, morphology_pick::<T, DILATE>(a, b));
+            }
+        }
+    }
+}
+
+fn morphology_horizontal_pass<
+    T: MorphologyValue,
+    const CHANNELS_CONFIGURATION: usize,
+    const DILATE: bool,
+>(
+    src: &[T],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    radius: u32,
+    start_y: u32,
+    end_y: u32,
+) {
+    let mut prefix: Vec<T> = Vec::new();
+    let mut suffix: Vec<T> = Vec::new();
+    for y in start_y..end_y {
+        van_herk_pass::<T, DILATE>(
+            src,
+            y as usize * src_stride as usize,
+            CHANNELS_CONFIGURATION,
+            unsafe_dst,
+            y as usize * dst_stride as usize,
+            CHANNELS_CONFIGURATION,
+            CHANNELS_CONFIGURATION,
+            width as usize,
+            radius as usize,
+            &mut prefix,
+            &mut suffix,
+        );
+    }
+}
+
+fn morphology_vertical_pass<T: MorphologyValue, const DILATE: bool>(
+    src: &[T],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    height: u32,
+    radius: u32,
+    start_x: usize,
+    end_x: usize,
+) {
+    let mut prefix: Vec<T> = Vec::new();
+    let mut suffix: Vec<T> = Vec::new();
+    let mut x = start_x;
+    while x < end_x {
+        let lanes = core::cmp::min(MORPHOLOGY_VERTICAL_LANES, end_x - x);
+        van_herk_pass::<T, DILATE>(
+            src,
+            x,
+            src_stride as usize,
+            unsafe_dst,
+            x,
+            dst_stride as usize,
+            lanes,
+            height as usize,
+            radius as usize,
+            &mut prefix,
+            &mut suffix,
+        );
+        x += lanes;
+    }
+}
+
+/// Separable erosion or dilation with rectangular structuring element
+fn morphology_pass<T: MorphologyValue, const CHANNELS_CONFIGURATION: usize, const DILATE: bool>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius_x: u32,
+    radius_y: u32,
+    pool: &BlurPool,
+    thread_count: u32,
+) {
+    let transient_stride = width * CHANNELS_CONFIGURATION as u32;
+    let mut transient: Vec<T> = vec![T::MIN; transient_stride as usize * height as usize];
+    let unsafe_transient = UnsafeSlice::new(&mut transient);
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| {
+                morphology_horizontal_pass::<T, CHANNELS_CONFIGURATION, DILATE>(
+                    src,
+                    src_stride,
+                    &unsafe_transient,
+                    transient_stride,
+                    width,
+                    radius_x,
+                    start_y,
+                    end_y,
+                );
+            });
+        }
+    });
+    let transient = &transient;
+    let unsafe_dst = UnsafeSlice::new(dst);
+    pool.scope(|scope| {
+        let row_length = transient_stride as usize;
+        let segment_size = row_length / thread_count as usize;
+        for i in 0..thread_count as usize {
+            let start_x = i * segment_size;
+            let mut end_x = (i + 1) * segment_size;
+            if i == thread_count as usize - 1 {
+                end_x = row_length;
+            }
+            scope.spawn(move |_| {
+                morphology_vertical_pass::<T, DILATE>(
+                    transient,
+                    transient_stride,
+                    &unsafe_dst,
+                    dst_stride,
+                    height,
+                    radius_y,
+                    start_x,
+                    end_x,
+                );
+            });
+        }
+    });
+}
+
+/// Writes `minuend - subtrahend` clamped at zero into `dst`
+fn morphology_difference<T: MorphologyValue, const CHANNELS_CONFIGURATION: usize>(
+    minuend: &[T],
+    minuend_stride: u32,
+    subtrahend: &[T],
+    subtrahend_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+) {
+    let row_length = width as usize * CHANNELS_CONFIGURATION;
+    for y in 0..height as usize {
+        let minuend =
+            &minuend[y * minuend_stride as usize..y * minuend_stride as usize + row_length];
+        let subtrahend = &subtrahend
+            [y * subtrahend_stride as usize..y * subtrahend_stride as usize + row_length];
+        let dst = &mut dst[y * dst_stride as usize..y * dst_stride as usize + row_length];
+        for ((dst, &a), &b) in dst.iter_mut().zip(minuend.iter()).zip(subtrahend.iter()) {
+            *dst = a.saturating_difference(b);
+        }
+    }
+}
+
+pub(crate) fn morphology_impl<T: MorphologyValue, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    op: MorphologyOp,
+    radius_x: u32,
+    radius_y: u32,
+    threading_policy: ThreadingPolicy,
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        (radius_x + radius_y + 1) as f32 / 2f32 * type_work_per_pixel::<T>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    let temp_stride = width * CHANNELS_CONFIGURATION as u32;
+    let temp_size = temp_stride as usize * height as usize;
+    match op {
+        MorphologyOp::Erode => {
+            morphology_pass::<T, CHANNELS_CONFIGURATION, false>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                radius_x,
+                radius_y,
+                &pool,
+                thread_count,
+            );
+        }
+        MorphologyOp::Dilate => {
+            morphology_pass::<T, CHANNELS_CONFIGURATION, true>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                radius_x,
+                radius_y,
+                &pool,
+                thread_count,
+            );
+        }
+        MorphologyOp::Open | MorphologyOp::TopHat => {
+            let mut eroded = vec![T::MIN; temp_size];
+            morphology_pass::<T, CHANNELS_CONFIGURATION, false>(
+                src,
+                src_stride,
+                &mut eroded,
+                temp_stride,
+                width,
+                height,
+                radius_x,
+                radius_y,
+                &pool,
+                thread_count,
+            );
+            if op == MorphologyOp::Open {
+                morphology_pass::<T, CHANNELS_CONFIGURATION, true>(
+                    &eroded,
+                    temp_stride,
+                    dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius_x,
+                    radius_y,
+                    &pool,
+                    thread_count,
+                );
+            } else {
+                let mut opened = vec![T::MIN; temp_size];
+                morphology_pass::<T, CHANNELS_CONFIGURATION, true>(
+                    &eroded,
+                    temp_stride,
+                    &mut opened,
+                    temp_stride,
+                    width,
+                    height,
+                    radius_x,
+                    radius_y,
+                    &pool,
+                    thread_count,
+                );
+                morphology_difference::<T, CHANNELS_CONFIGURATION>(
+                    src,
+                    src_stride,
+                    &opened,
+                    temp_stride,
+                    dst,
+                    dst_stride,
+                    width,
+                    height,
+                );
+            }
+        }
+        MorphologyOp::Close | MorphologyOp::BlackHat => {
+            let mut dilated = vec![T::MIN; temp_size];
+            morphology_pass::<T, CHANNELS_CONFIGURATION, true>(
+                src,
+                src_stride,
+                &mut dilated,
+                temp_stride,
+                width,
+                height,
+                radius_x,
+                radius_y,
+                &pool,
+                thread_count,
+            );
+            if op == MorphologyOp::Close {
+                morphology_pass::<T, CHANNELS_CONFIGURATION, false>(
+                    &dilated,
+                    temp_stride,
+                    dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius_x,
+                    radius_y,
+                    &pool,
+                    thread_count,
+                );
+            } else {
+                let mut closed = vec![T::MIN; temp_size];
+                morphology_pass::<T, CHANNELS_CONFIGURATION, false>(
+                    &dilated,
+                    temp_stride,
+                    &mut closed,
+                    temp_stride,
+                    width,
+                    height,
+                    radius_x,
+                    radius_y,
+                    &pool,
+                    thread_count,
+                );
+                morphology_difference::<T, CHANNELS_CONFIGURATION>(
+                    &closed,
+                    temp_stride,
+                    src,
+                    src_stride,
+                    dst,
+                    dst_stride,
+                    width,
+                    height,
+                );
+            }
+        }
+        MorphologyOp::Gradient => {
+            let mut dilated = vec![T::MIN; temp_size];
+            let mut eroded = vec![T::MIN; temp_size];
+            morphology_pass::<T, CHANNELS_CONFIGURATION, true>(
+                src,
+                src_stride,
+                &mut dilated,
+                temp_stride,
+                width,
+                height,
+                radius_x,
+                radius_y,
+                &pool,
+                thread_count,
+            );
+            morphology_pass::<T, CHANNELS_CONFIGURATION, false>(
+                src,
+                src_stride,
+                &mut eroded,
+                temp_stride,
+                width,
+                height,
+                radius_x,
+                radius_y,
+                &pool,
+                thread_count,
+            );
+            morphology_difference::<T, CHANNELS_CONFIGURATION>(
+                &dilated,
+                temp_stride,
+                &eroded,
+                temp_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+            );
+        }
+    }
+}
+
+/// Performs morphological operation on the image.
+///
+/// Erosion and dilation are min and max rank filters with rectangular structuring element
+/// `(2 * radius_x + 1) x (2 * radius_y + 1)`, computed with van Herk/Gil-Werman algorithm.
+/// Out of bounds pixels do not affect result.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `op` - Morphological operation, see *MorphologyOp*
+/// * `radius_x` - Horizontal radius of structuring element
+/// * `radius_y` - Vertical radius of structuring element
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn morphology(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    op: MorphologyOp,
+    radius_x: u32,
+    radius_y: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    match channels {
+        FastBlurChannels::Channels3 => {
+            morphology_impl::<u8, 3>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                op,
+                radius_x,
+                radius_y,
+                threading_policy,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            morphology_impl::<u8, 4>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                op,
+                radius_x,
+                radius_y,
+                threading_policy,
+            );
+        }
+    }
+}
+
+/// Performs morphological operation on the single plane image, alpha mask, gray etc.
+///
+/// Erosion and dilation are min and max rank filters with rectangular structuring element
+/// `(2 * radius_x + 1) x (2 * radius_y + 1)`, computed with van Herk/Gil-Werman algorithm.
+/// Out of bounds pixels do not affect result.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width if not aligned
+/// * `dst_stride` - Lane length, default is width if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `op` - Morphological operation, see *MorphologyOp*
+/// * `radius_x` - Horizontal radius of structuring element
+/// * `radius_y` - Vertical radius of structuring element
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is stride/width/height do not match provided
+pub fn morphology_plane(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    op: MorphologyOp,
+    radius_x: u32,
+    radius_y: u32,
+    threading_policy: ThreadingPolicy,
+) {
+    morphology_impl::<u8, 1>(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        op,
+        radius_x,
+        radius_y,
+        threading_policy,
+    );
+}
+
+/// Performs morphological operation on the u16 image.
+///
+/// Erosion and dilation are min and max rank filters with rectangular structuring element
+/// `(2 * radius_x + 1) x (2 * radius_y + 1)`, computed with van Herk/Gil-Werman algorithm.
+/// Out of bounds pixels do not affect result.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length in elements, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length in elements, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `op` - Morphological operation, see *MorphologyOp*
+/// * `radius_x` - Horizontal radius of structuring element
+/// * `radius_y` - Vertical radius of structuring element
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn morphology_u16(
+    src: &[u16],
+    src_stride: u32,
+    dst: &mut [u16],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    op: MorphologyOp,
+    radius_x: u32,
+    radius_y: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    match channels {
+        FastBlurChannels::Channels3 => {
+            morphology_impl::<u16, 3>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                op,
+                radius_x,
+                radius_y,
+                threading_policy,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            morphology_impl::<u16, 4>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                op,
+                radius_x,
+                radius_y,
+                threading_policy,
+            );
+        }
+    }
+}
+
+/// Performs morphological operation on the f32 image.
+///
+/// Erosion and dilation are min and max rank filters with rectangular structuring element
+/// `(2 * radius_x + 1) x (2 * radius_y + 1)`, computed with van Herk/Gil-Werman algorithm.
+/// Out of bounds pixels do not affect result.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `op` - Morphological operation, see *MorphologyOp*
+/// * `radius_x` - Horizontal radius of structuring element
+/// * `radius_y` - Vertical radius of structuring element
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is width/height/channel configuration do not match provided
+pub fn morphology_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    op: MorphologyOp,
+    radius_x: u32,
+    radius_y: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    let stride = width * channels.get_channels() as u32;
+    match channels {
+        FastBlurChannels::Channels3 => {
+            morphology_impl::<f32, 3>(
+                src,
+                stride,
+                dst,
+                stride,
+                width,
+                height,
+                op,
+                radius_x,
+                radius_y,
+                threading_policy,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            morphology_impl::<f32, 4>(
+                src,
+                stride,
+                dst,
+                stride,
+                width,
+                height,
+                op,
+                radius_x,
+                radius_y,
+                threading_policy,
+            );
+        }
+    }
+}
+
+/// Performs morphological operation on the f32 single plane image, alpha mask, gray etc.
+///
+/// Erosion and dilation are min and max rank filters with rectangular structuring element
+/// `(2 * radius_x + 1) x (2 * radius_y + 1)`, computed with van Herk/Gil-Werman algorithm.
+/// Out of bounds pixels do not affect result.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `op` - Morphological operation, see *MorphologyOp*
+/// * `radius_x` - Horizontal radius of structuring element
+/// * `radius_y` - Vertical radius of structuring element
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is width/height do not match provided
+pub fn morphology_plane_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    op: MorphologyOp,
+    radius_x: u32,
+    radius_y: u32,
+    threading_policy: ThreadingPolicy,
+) {
+    morphology_impl::<f32, 1>(
+        src,
+        width,
+        dst,
+        width,
+        width,
+        height,
+        op,
+        radius_x,
+        radius_y,
+        threading_policy,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(
+        src: &[u8],
+        width: usize,
+        height: usize,
+        radius_x: usize,
+        radius_y: usize,
+        dilate: bool,
+    ) -> Vec<u8> {
+        let mut dst = vec![0u8; src.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let mut value = if dilate { 0u8 } else { 255u8 };
+                for ky in y.saturating_sub(radius_y)..(y + radius_y + 1).min(height) {
+                    for kx in x.saturating_sub(radius_x)..(x + radius_x + 1).min(width) {
+                        let v = src[ky * width + kx];
+                        value = if dilate { value.max(v) } else { value.min(v) };
+                    }
+                }
+                dst[y * width + x] = value;
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn erode_and_dilate_match_brute_force() {
+        let width = 37usize;
+        let height = 21usize;
+        let src: Vec<u8> = (0..width * height)
+            .map(|i| ((i * 7919) % 253) as u8)
+            .collect();
+        for (radius_x, radius_y) in [(0usize, 0usize), (1, 1), (3, 1), (2, 7), (40, 2)] {
+            for (op, dilate) in [(MorphologyOp::Erode, false), (MorphologyOp::Dilate, true)] {
+                let mut dst = vec![0u8; src.len()];
+                morphology_plane(
+                    &src,
+                    width as u32,
+                    &mut dst,
+                    width as u32,
+                    width as u32,
+                    height as u32,
+                    op,
+                    radius_x as u32,
+                    radius_y as u32,
+                    ThreadingPolicy::Single,
+                );
+                let reference = brute_force(&src, width, height, radius_x, radius_y, dilate);
+                assert_eq!(dst, reference, "{:?} {} {}", op, radius_x, radius_y);
+            }
+        }
+    }
+
+    #[test]
+    fn opening_removes_bright_impulse() {
+        let width = 16usize;
+        let height = 16usize;
+        let mut src = vec![0.25f32; width * height * 3];
+        src[(5 * width + 9) * 3 + 2] = 1f32;
+        let mut opened = vec![0f32; src.len()];
+        morphology_f32(
+            &src,
+            &mut opened,
+            width as u32,
+            height as u32,
+            MorphologyOp::Open,
+            1,
+            1,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+        );
+        assert!(opened.iter().all(|&v| v == 0.25f32));
+        let mut top_hat = vec![0f32; src.len()];
+        morphology_f32(
+            &src,
+            &mut top_hat,
+            width as u32,
+            height as u32,
+            MorphologyOp::TopHat,
+            1,
+            1,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+        );
+        assert_eq!(top_hat[(5 * width + 9) * 3 + 2], 0.75f32);
+        assert_eq!(top_hat.iter().filter(|&&v| v != 0f32).count(), 1);
+    }
+
+    #[test]
+    fn gradient_of_constant_image_is_zero() {
+        let src = vec![4321u16; 9 * 7 * 4];
+        let mut dst = vec![1u16; src.len()];
+        morphology_u16(
+            &src,
+            9 * 4,
+            &mut dst,
+            9 * 4,
+            9,
+            7,
+            MorphologyOp::Gradient,
+            2,
+            3,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Single,
+        );
+        assert!(dst.iter().all(|&v| v == 0));
+    }
+}
@@ -0,0 +1,599 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::normalized_convolution::normalized_convolution_impl;
+use crate::{fast_gaussian_f32, gaussian_blur_f32, stack_blur_f32, EdgeMode, ThreadingPolicy};
+
+/// NaN samples are missing, they are excluded from the sum and kernel is renormalized
+/// on remaining samples of each channel separately
+fn nan_blur_impl<const CHANNELS_CONFIGURATION: usize>(
+    image: &mut [f32],
+    width: u32,
+    height: u32,
+    keep_nan: bool,
+    blur: impl FnMut(&[f32], &mut [f32], FastBlurChannels),
+) {
+    let empty = if keep_nan { f32::NAN } else { 0f32 };
+    normalized_convolution_impl::<CHANNELS_CONFIGURATION, CHANNELS_CONFIGURATION>(
+        image,
+        width,
+        height,
+        |_, _, value| if value.is_nan() { 0f32 } else { 1f32 },
+        empty,
+        blur,
+    );
+}
+
+fn gaussian_blur_nan_dispatch<const CHANNELS_CONFIGURATION: usize>(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    keep_nan: bool,
+) {
+    let image_size = width as usize * height as usize * CHANNELS_CONFIGURATION;
+    dst[..image_size].copy_from_slice(&src[..image_size]);
+    nan_blur_impl::<CHANNELS_CONFIGURATION>(
+        dst,
+        width,
+        height,
+        keep_nan,
+        |packed, blurred, channels| {
+            gaussian_blur_f32(
+                packed,
+                blurred,
+                width,
+                height,
+                kernel_size,
+                sigma,
+                channels,
+                edge_mode,
+                threading_policy,
+            );
+        },
+    );
+}
+
+fn fast_gaussian_nan_dispatch<const CHANNELS_CONFIGURATION: usize>(
+    bytes: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+    keep_nan: bool,
+) {
+    nan_blur_impl::<CHANNELS_CONFIGURATION>(
+        bytes,
+        width,
+        height,
+        keep_nan,
+        |packed, blurred, channels| {
+            blurred.copy_from_slice(packed);
+            fast_gaussian_f32(
+                blurred,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+                edge_mode,
+            );
+        },
+    );
+}
+
+fn stack_blur_nan_dispatch<const CHANNELS_CONFIGURATION: usize>(
+    in_place: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+    keep_nan: bool,
+) {
+    nan_blur_impl::<CHANNELS_CONFIGURATION>(
+        in_place,
+        width,
+        height,
+        keep_nan,
+        |packed, blurred, channels| {
+            blurred.copy_from_slice(packed);
+            stack_blur_f32(blurred, width, height, radius, channels, threading_policy);
+        },
+    );
+}
+
+/// Performs gaussian blur on the image treating NaN as missing values.
+///
+/// NaN samples are excluded from the weighted sum and the kernel is renormalized on remaining samples,
+/// each channel separately, so NaN do not spread across the kernel footprint.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
+/// * `channels` - Count of channels in the image
+/// * `edge_mode` - Rule to handle edge mode
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `keep_nan` - Write NaN where all samples in the kernel are NaN, otherwise zero is written
+///
+/// # Panics
+/// Panic is width/height/channel configuration do not match provided
+pub fn gaussian_blur_nan_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    keep_nan: bool,
+) {
+    let dispatcher = match channels {
+        FastBlurChannels::Channels3 => gaussian_blur_nan_dispatch::<3>,
+        FastBlurChannels::Channels4 => gaussian_blur_nan_dispatch::<4>,
+    };
+    dispatcher(
+        src,
+        dst,
+        width,
+        height,
+        kernel_size,
+        sigma,
+        edge_mode,
+        threading_policy,
+        keep_nan,
+    );
+}
+
+/// Performs gaussian blur on the single plane image treating NaN as missing values, depth maps etc.
+///
+/// NaN samples are excluded from the weighted sum and the kernel is renormalized on remaining samples,
+/// so NaN do not spread across the kernel footprint.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
+/// * `edge_mode` - Rule to handle edge mode
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `keep_nan` - Write NaN where all samples in the kernel are NaN, otherwise zero is written
+///
+/// # Panics
+/// Panic is width/height do not match provided
+pub fn gaussian_blur_nan_plane_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+    keep_nan: bool,
+) {
+    gaussian_blur_nan_dispatch::<1>(
+        src,
+        dst,
+        width,
+        height,
+        kernel_size,
+        sigma,
+        edge_mode,
+        threading_policy,
+        keep_nan,
+    );
+}
+
+/// Performs gaussian approximation on the image treating NaN as missing values.
+///
+/// NaN samples are excluded from the running sums and the result is renormalized on remaining samples,
+/// each channel separately, so NaN do not poison the rest of the row.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - almost any radius is supported
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `keep_nan` - Write NaN where all samples in the kernel are NaN, otherwise zero is written
+///
+/// # Panics
+/// Panic is width/height/channel configuration do not match provided
+pub fn fast_gaussian_nan_f32(
+    bytes: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+    keep_nan: bool,
+) {
+    let dispatcher = match channels {
+        FastBlurChannels::Channels3 => fast_gaussian_nan_dispatch::<3>,
+        FastBlurChannels::Channels4 => fast_gaussian_nan_dispatch::<4>,
+    };
+    dispatcher(
+        bytes,
+        width,
+        height,
+        radius,
+        threading_policy,
+        edge_mode,
+        keep_nan,
+    );
+}
+
+/// Performs gaussian approximation on the single plane image treating NaN as missing values, depth maps etc.
+///
+/// NaN samples are excluded from the running sums and the result is renormalized on remaining samples,
+/// so NaN do not poison the rest of the row.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - almost any radius is supported
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+/// * `keep_nan` - Write NaN where all samples in the kernel are NaN, otherwise zero is written
+///
+/// # Panics
+/// Panic is width/height do not match provided
+pub fn fast_gaussian_nan_plane_f32(
+    bytes: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+    keep_nan: bool,
+) {
+    fast_gaussian_nan_dispatch::<1>(
+        bytes,
+        width,
+        height,
+        radius,
+        threading_policy,
+        edge_mode,
+        keep_nan,
+    );
+}
+
+/// Performs stack blur on the image treating NaN as missing values.
+///
+/// NaN samples are excluded from the running sums and the result is renormalized on remaining samples,
+/// each channel separately, so NaN do not poison the rest of the row.
+///
+/// # Arguments
+/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
+/// * `width` - image width
+/// * `height` - image height
+/// * `radius` - radius almost is not limited for f32 implementation
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+/// * `keep_nan` - Write NaN where all samples in the kernel are NaN, otherwise zero is written
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_nan_f32(
+    in_place: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    keep_nan: bool,
+) {
+    let dispatcher = match channels {
+        FastBlurChannels::Channels3 => stack_blur_nan_dispatch::<3>,
+        FastBlurChannels::Channels4 => stack_blur_nan_dispatch::<4>,
+    };
+    dispatcher(in_place, width, height, radius, threading_policy, keep_nan);
+}
+
+/// Performs stack blur on the single plane image treating NaN as missing values, depth maps etc.
+///
+/// NaN samples are excluded from the running sums and the result is renormalized on remaining samples,
+/// so NaN do not poison the rest of the row.
+///
+/// # Arguments
+/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
+/// * `width` - image width
+/// * `height` - image height
+/// * `radius` - radius almost is not limited for f32 implementation
+/// * `threading_policy` - Threads usage policy
+/// * `keep_nan` - Write NaN where all samples in the kernel are NaN, otherwise zero is written
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_nan_plane_f32(
+    in_place: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+    keep_nan: bool,
+) {
+    stack_blur_nan_dispatch::<1>(in_place, width, height, radius, threading_policy, keep_nan);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn test_image(width: usize, height: usize, channels: usize) -> Vec<f32> {
+        (0..width * height * channels)
+            .map(|i| ((i * 7919 + 3) % 89) as f32 / 89f32)
+            .collect()
+    }
+
+    fn close(a: &[f32], b: &[f32], tolerance: f32) {
+        for (i, (&a, &b)) in a.iter().zip(b.iter()).enumerate() {
+            assert!((a - b).abs() < tolerance, "at {}: {} vs {}", i, a, b);
+        }
+    }
+
+    #[test]
+    fn without_nan_matches_regular_blurs() {
+        let (width, height) = (19u32, 14u32);
+        let src = test_image(width as usize, height as usize, 3);
+
+        let mut expected = vec![0f32; src.len()];
+        gaussian_blur_f32(
+            &src,
+            &mut expected,
+            width,
+            height,
+            5,
+            1.2f32,
+            FastBlurChannels::Channels3,
+            EdgeMode::Reflect101,
+            ThreadingPolicy::Single,
+        );
+        let mut dst = vec![0f32; src.len()];
+        gaussian_blur_nan_f32(
+            &src,
+            &mut dst,
+            width,
+            height,
+            5,
+            1.2f32,
+            FastBlurChannels::Channels3,
+            EdgeMode::Reflect101,
+            ThreadingPolicy::Single,
+            true,
+        );
+        close(&dst, &expected, 1e-4);
+
+        let mut expected = src.clone();
+        fast_gaussian_f32(
+            &mut expected,
+            width,
+            height,
+            4,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+            EdgeMode::Clamp,
+        );
+        let mut dst = src.clone();
+        fast_gaussian_nan_f32(
+            &mut dst,
+            width,
+            height,
+            4,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+            EdgeMode::Clamp,
+            true,
+        );
+        close(&dst, &expected, 1e-4);
+
+        // Stack blur f32 gain is not exactly one, NaN variant is normalized by blurred weights
+        let mut expected = src.clone();
+        stack_blur_f32(
+            &mut expected,
+            width,
+            height,
+            3,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+        );
+        let mut gain = vec![1f32; src.len()];
+        stack_blur_f32(
+            &mut gain,
+            width,
+            height,
+            3,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+        );
+        for (dst, &gain) in expected.iter_mut().zip(gain.iter()) {
+            *dst /= gain;
+        }
+        let mut dst = src.clone();
+        stack_blur_nan_f32(
+            &mut dst,
+            width,
+            height,
+            3,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+            true,
+        );
+        close(&dst, &expected, 1e-4);
+    }
+
+    #[test]
+    fn nan_do_not_spread() {
+        let (width, height) = (16u32, 12u32);
+        let pixels = (width * height) as usize;
+        // NaN only in the alpha of every 3rd pixel, other channels keep the constant
+        let src = (0..pixels * 4)
+            .map(|i| {
+                if i % 4 == 3 && (i / 4) % 3 == 0 {
+                    f32::NAN
+                } else {
+                    0.5f32 + (i % 4) as f32 * 0.1f32
+                }
+            })
+            .collect::<Vec<_>>();
+        let check = |image: &[f32]| {
+            for (i, &v) in image.iter().enumerate() {
+                assert!(
+                    (v - (0.5f32 + (i % 4) as f32 * 0.1f32)).abs() < 1e-4,
+                    "at {}: {}",
+                    i,
+                    v
+                );
+            }
+        };
+
+        let mut dst = vec![0f32; src.len()];
+        gaussian_blur_nan_f32(
+            &src,
+            &mut dst,
+            width,
+            height,
+            7,
+            1.5f32,
+            FastBlurChannels::Channels4,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Adaptive,
+            true,
+        );
+        check(&dst);
+        let mut dst = src.clone();
+        fast_gaussian_nan_f32(
+            &mut dst,
+            width,
+            height,
+            5,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Adaptive,
+            EdgeMode::Reflect101,
+            true,
+        );
+        check(&dst);
+        let mut dst = src.clone();
+        stack_blur_nan_f32(
+            &mut dst,
+            width,
+            height,
+            5,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Adaptive,
+            true,
+        );
+        check(&dst);
+    }
+
+    #[test]
+    fn missing_neighborhood_is_nan_or_zero() {
+        let (width, height) = (40u32, 40u32);
+        let src = (0..(width * height) as usize)
+            .map(|i| {
+                let (x, y) = (i % width as usize, i / width as usize);
+                if (10..30).contains(&x) && (10..30).contains(&y) {
+                    f32::NAN
+                } else {
+                    0.75f32
+                }
+            })
+            .collect::<Vec<_>>();
+        let center = (20 * width + 20) as usize;
+        for keep_nan in [true, false] {
+            let expected_center = |v: f32| {
+                if keep_nan {
+                    assert!(v.is_nan());
+                } else {
+                    assert_eq!(v, 0f32);
+                }
+            };
+            let mut dst = vec![0f32; src.len()];
+            gaussian_blur_nan_plane_f32(
+                &src,
+                &mut dst,
+                width,
+                height,
+                5,
+                1f32,
+                EdgeMode::Clamp,
+                ThreadingPolicy::Single,
+                keep_nan,
+            );
+            expected_center(dst[center]);
+            assert!((dst[0] - 0.75f32).abs() < 1e-5);
+            assert!((dst[(10 * width + 10) as usize] - 0.75f32).abs() < 1e-4);
+
+            let mut dst = src.clone();
+            fast_gaussian_nan_plane_f32(
+                &mut dst,
+                width,
+                height,
+                2,
+                ThreadingPolicy::Single,
+                EdgeMode::Clamp,
+                keep_nan,
+            );
+            expected_center(dst[center]);
+            assert!((dst[0] - 0.75f32).abs() < 1e-4);
+
+            let mut dst = src.clone();
+            stack_blur_nan_plane_f32(
+                &mut dst,
+                width,
+                height,
+                2,
+                ThreadingPolicy::Single,
+                keep_nan,
+            );
+            expected_center(dst[center]);
+            assert!((dst[0] - 0.75f32).abs() < 1e-4);
+        }
+    }
+}
@@ -26,8 +26,8 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::neon::{load_u8_s32_fast, vmulq_s32_f32};
-use crate::{clamp_edge, reflect_101, reflect_index, EdgeMode};
-use std::arch::aarch64::*;
+use crate::{clamp_edge, EdgeMode};
+use core::arch::aarch64::*;
 
 use crate::unsafe_slice::UnsafeSlice;
 
@@ -37,6 +37,7 @@ pub fn fast_gaussian_horizontal_pass_neon_u8<
     const EDGE_MODE: usize,
 >(
     undefined_slice: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -45,14 +46,15 @@ pub fn fast_gaussian_horizontal_pass_neon_u8<
     end: u32,
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    let bytes: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_slice) };
+    let bytes: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_slice) };
+    let src: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_src) };
     let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
     let initial_sum = ((radius * radius) >> 1) as i32;
 
     let radius_64 = radius as i64;
     let width_wide = width as i64;
     let v_weight = unsafe { vdupq_n_f32((1f64 / (radius as f64 * radius as f64)) as f32) };
-    for y in start..std::cmp::min(height, end) {
+    for y in start..core::cmp::min(height, end) {
         let mut diffs: int32x4_t = unsafe { vdupq_n_s32(0) };
         let mut summs: int32x4_t = unsafe { vdupq_n_s32(initial_sum) };
 
@@ -61,7 +63,7 @@ pub fn fast_gaussian_horizontal_pass_neon_u8<
         let start_x = 0 - 2 * radius_64;
         for x in start_x..(width as i64) {
             if x >= 0 {
-                let current_px = ((std::cmp::max(x, 0) as u32) * CHANNELS_COUNT as u32) as usize;
+                let current_px = ((core::cmp::max(x, 0) as u32) * CHANNELS_COUNT as u32) as usize;
 
                 let prepared_px_s32 =
                     unsafe { vreinterpretq_u32_s32(vmulq_s32_f32(summs, v_weight)) };
@@ -109,7 +111,7 @@ pub fn fast_gaussian_horizontal_pass_neon_u8<
             let next_row_x = clamp_edge!(edge_mode, x + radius_64, 0, width_wide - 1);
             let next_row_px = next_row_x * CHANNELS_COUNT;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_px) as *mut u8 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_px) as *mut u8 };
             let pixel_color = unsafe { load_u8_s32_fast::<CHANNELS_COUNT>(s_ptr) };
 
             let arr_index = ((x + radius_64) & 1023) as usize;
@@ -130,6 +132,7 @@ pub(crate) fn fast_gaussian_vertical_pass_neon_u8<
     const EDGE_MODE: usize,
 >(
     undefined_slice: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -138,7 +141,8 @@ pub(crate) fn fast_gaussian_vertical_pass_neon_u8<
     end: u32,
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    let bytes: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_slice) };
+    let bytes: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_slice) };
+    let src: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_src) };
     let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
     let initial_sum = ((radius * radius) >> 1) as i32;
 
@@ -146,7 +150,7 @@ pub(crate) fn fast_gaussian_vertical_pass_neon_u8<
 
     let radius_64 = radius as i64;
     let v_weight = unsafe { vdupq_n_f32((1f64 / (radius as f64 * radius as f64)) as f32) };
-    for x in start..std::cmp::min(width, end) {
+    for x in start..core::cmp::min(width, end) {
         let mut diffs: int32x4_t = unsafe { vdupq_n_s32(0) };
         let mut summs: int32x4_t = unsafe { vdupq_n_s32(initial_sum) };
 
@@ -155,7 +159,7 @@ pub(crate) fn fast_gaussian_vertical_pass_neon_u8<
             let current_y = (y * (stride as i64)) as usize;
 
             if y >= 0 {
-                let current_px = ((std::cmp::max(x, 0)) * CHANNELS_COUNT as u32) as usize;
+                let current_px = ((core::cmp::max(x, 0)) * CHANNELS_COUNT as u32) as usize;
 
                 let prepared_px_s32 =
                     unsafe { vreinterpretq_u32_s32(vmulq_s32_f32(summs, v_weight)) };
@@ -206,7 +210,7 @@ pub(crate) fn fast_gaussian_vertical_pass_neon_u8<
                 clamp_edge!(edge_mode, y + radius_64, 0, height_wide - 1) * (stride as usize);
             let next_row_x = (x * CHANNELS_COUNT as u32) as usize;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_x) as *mut u8 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_x) as *mut u8 };
             let pixel_color = unsafe { load_u8_s32_fast::<CHANNELS_COUNT>(s_ptr) };
 
             let arr_index = ((y + radius_64) & 1023) as usize;
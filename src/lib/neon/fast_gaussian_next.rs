@@ -26,9 +26,8 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::neon::load_u8_s32_fast;
-use crate::reflect_index;
-use crate::{clamp_edge, reflect_101, EdgeMode};
-use std::arch::aarch64::*;
+use crate::{clamp_edge, EdgeMode};
+use core::arch::aarch64::*;
 
 use crate::unsafe_slice::UnsafeSlice;
 
@@ -38,6 +37,7 @@ pub fn fast_gaussian_next_vertical_pass_neon_u8<
     const EDGE_MODE: usize,
 >(
     undefined_slice: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -46,7 +46,8 @@ pub fn fast_gaussian_next_vertical_pass_neon_u8<
     end: u32,
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    let bytes: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_slice) };
+    let bytes: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_slice) };
+    let src: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_src) };
     let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
 
     let height_wide = height as i64;
@@ -54,7 +55,7 @@ pub fn fast_gaussian_next_vertical_pass_neon_u8<
     let radius_64 = radius as i64;
     let weight = 1.0f32 / ((radius as f32) * (radius as f32) * (radius as f32));
     let f_weight = unsafe { vdupq_n_f32(weight) };
-    for x in start..std::cmp::min(width, end) {
+    for x in start..core::cmp::min(width, end) {
         let mut diffs: int32x4_t = unsafe { vdupq_n_s32(0) };
         let mut ders: int32x4_t = unsafe { vdupq_n_s32(0) };
         let mut summs: int32x4_t = unsafe { vdupq_n_s32(0) };
@@ -64,7 +65,7 @@ pub fn fast_gaussian_next_vertical_pass_neon_u8<
             let current_y = (y * (stride as i64)) as usize;
 
             if y >= 0 {
-                let current_px = ((std::cmp::max(x, 0)) * CHANNELS_COUNT as u32) as usize;
+                let current_px = ((core::cmp::max(x, 0)) * CHANNELS_COUNT as u32) as usize;
 
                 let prepared_px_s32 =
                     unsafe { vcvtaq_s32_f32(vmulq_f32(vcvtq_f32_s32(summs), f_weight)) };
@@ -131,7 +132,7 @@ pub fn fast_gaussian_next_vertical_pass_neon_u8<
                 * (stride as usize);
             let next_row_x = (x * CHANNELS_COUNT as u32) as usize;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_x) as *mut u8 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_x) as *mut u8 };
 
             let pixel_color = unsafe { load_u8_s32_fast::<CHANNELS_COUNT>(s_ptr) };
 
@@ -154,6 +155,7 @@ pub(crate) fn fast_gaussian_next_horizontal_pass_neon_u8<
     const EDGE_MODE: usize,
 >(
     undefined_slice: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -162,7 +164,8 @@ pub(crate) fn fast_gaussian_next_horizontal_pass_neon_u8<
     end: u32,
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    let bytes: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_slice) };
+    let bytes: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_slice) };
+    let src: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_src) };
     let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
 
     let width_wide = width as i64;
@@ -170,7 +173,7 @@ pub(crate) fn fast_gaussian_next_horizontal_pass_neon_u8<
     let radius_64 = radius as i64;
     let weight = 1.0f32 / ((radius as f32) * (radius as f32) * (radius as f32));
     let f_weight = unsafe { vdupq_n_f32(weight) };
-    for y in start..std::cmp::min(height, end) {
+    for y in start..core::cmp::min(height, end) {
         let mut diffs: int32x4_t = unsafe { vdupq_n_s32(0) };
         let mut ders: int32x4_t = unsafe { vdupq_n_s32(0) };
         let mut summs: int32x4_t = unsafe { vdupq_n_s32(0) };
@@ -245,7 +248,7 @@ pub(crate) fn fast_gaussian_next_horizontal_pass_neon_u8<
             let next_row_x = clamp_edge!(edge_mode, x + 3 * radius_64 / 2, 0, width_wide - 1);
             let next_row_px = next_row_x * CHANNELS_COUNT;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_px) as *mut u8 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_px) as *mut u8 };
 
             let pixel_color = unsafe { load_u8_s32_fast::<CHANNELS_COUNT>(s_ptr) };
 
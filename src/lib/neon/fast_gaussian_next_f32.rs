@@ -26,10 +26,9 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::neon::load_f32_fast;
-use crate::reflect_index;
 use crate::unsafe_slice::UnsafeSlice;
-use crate::{clamp_edge, reflect_101, EdgeMode, FastBlurChannels};
-use std::arch::aarch64::*;
+use crate::{clamp_edge, EdgeMode, FastBlurChannels};
+use core::arch::aarch64::*;
 
 pub fn fast_gaussian_next_vertical_pass_neon_f32<
     T,
@@ -37,6 +36,7 @@ pub fn fast_gaussian_next_vertical_pass_neon_f32<
     const EDGE_MODE: usize,
 >(
     undef_bytes: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -45,7 +45,8 @@ pub fn fast_gaussian_next_vertical_pass_neon_f32<
     end: u32,
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    let bytes: &UnsafeSlice<'_, f32> = unsafe { std::mem::transmute(undef_bytes) };
+    let bytes: &UnsafeSlice<'_, f32> = unsafe { core::mem::transmute(undef_bytes) };
+    let src: &UnsafeSlice<'_, f32> = unsafe { core::mem::transmute(undefined_src) };
     let channels: FastBlurChannels = CHANNELS_COUNT.into();
     let mut buffer: [[f32; 4]; 1024] = [[0f32; 4]; 1024];
 
@@ -58,7 +59,7 @@ pub fn fast_gaussian_next_vertical_pass_neon_f32<
         FastBlurChannels::Channels3 => 3,
         FastBlurChannels::Channels4 => 4,
     };
-    for x in start..std::cmp::min(width, end) {
+    for x in start..core::cmp::min(width, end) {
         let mut diffs: float32x4_t = unsafe { vdupq_n_f32(0f32) };
         let mut ders: float32x4_t = unsafe { vdupq_n_f32(0f32) };
         let mut summs: float32x4_t = unsafe { vdupq_n_f32(0f32) };
@@ -68,7 +69,7 @@ pub fn fast_gaussian_next_vertical_pass_neon_f32<
             let current_y = (y * (stride as i64)) as usize;
 
             if y >= 0 {
-                let current_px = ((std::cmp::max(x, 0)) * channels_count) as usize;
+                let current_px = ((core::cmp::max(x, 0)) * channels_count) as usize;
 
                 let prepared_px = unsafe { vmulq_f32(summs, f_weight) };
                 if CHANNELS_COUNT == 4 {
@@ -127,7 +128,7 @@ pub fn fast_gaussian_next_vertical_pass_neon_f32<
                 * (stride as usize);
             let next_row_x = (x * channels_count) as usize;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_x) as *mut f32 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_x) as *mut f32 };
 
             let pixel_color = unsafe { load_f32_fast::<CHANNELS_COUNT>(s_ptr) };
 
@@ -150,6 +151,7 @@ pub fn fast_gaussian_next_horizontal_pass_neon_f32<
     const EDGE_MODE: usize,
 >(
     undef_bytes: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -159,7 +161,8 @@ pub fn fast_gaussian_next_horizontal_pass_neon_f32<
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
     let mut buffer: [[f32; 4]; 1024] = [[0f32; 4]; 1024];
-    let bytes: &UnsafeSlice<'_, f32> = unsafe { std::mem::transmute(undef_bytes) };
+    let bytes: &UnsafeSlice<'_, f32> = unsafe { core::mem::transmute(undef_bytes) };
+    let src: &UnsafeSlice<'_, f32> = unsafe { core::mem::transmute(undefined_src) };
     let channels: FastBlurChannels = CHANNELS_COUNT.into();
 
     let width_wide = width as i64;
@@ -171,7 +174,7 @@ pub fn fast_gaussian_next_horizontal_pass_neon_f32<
         FastBlurChannels::Channels3 => 3,
         FastBlurChannels::Channels4 => 4,
     };
-    for y in start..std::cmp::min(height, end) {
+    for y in start..core::cmp::min(height, end) {
         let mut diffs: float32x4_t = unsafe { vdupq_n_f32(0f32) };
         let mut ders: float32x4_t = unsafe { vdupq_n_f32(0f32) };
         let mut summs: float32x4_t = unsafe { vdupq_n_f32(0f32) };
@@ -240,7 +243,7 @@ pub fn fast_gaussian_next_horizontal_pass_neon_f32<
             let next_row_x = clamp_edge!(edge_mode, x + 3 * radius_64 / 2, 0, width_wide - 1);
             let next_row_px = next_row_x * channels_count as usize;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_px) as *mut f32 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_px) as *mut f32 };
             let pixel_color = unsafe { load_f32_fast::<CHANNELS_COUNT>(s_ptr) };
 
             let arr_index = ((x + 2 * radius_64) & 1023) as usize;
@@ -25,7 +25,7 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::arch::aarch64::*;
+use core::arch::aarch64::*;
 
 use crate::neon::{load_f32_fast, store_f32};
 use crate::stack_blur::StackBlurPass;
@@ -40,6 +40,7 @@ pub fn stack_blur_pass_neon_f32<const COMPONENTS: usize>(
     pass: StackBlurPass,
     thread: usize,
     total_threads: usize,
+    stacks: &mut [f32],
 ) {
     unsafe {
         let div = ((radius * 2) + 1) as usize;
@@ -47,7 +48,7 @@ pub fn stack_blur_pass_neon_f32<const COMPONENTS: usize>(
         let (mut xp, mut yp);
         let mut sp;
         let mut stack_start;
-        let mut stacks = vec![0f32; 4 * div];
+        let stacks = &mut stacks[..4 * div];
 
         let mut sums: float32x4_t;
         let mut sum_in: float32x4_t;
@@ -25,11 +25,12 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::blur_scratch::aligned_scratch;
 use crate::mul_table::{MUL_TABLE_STACK_BLUR, SHR_TABLE_STACK_BLUR};
 use crate::neon::{load_u8_s32_fast, store_u8_s32};
 use crate::stack_blur::StackBlurPass;
 use crate::unsafe_slice::UnsafeSlice;
-use std::arch::aarch64::*;
+use core::arch::aarch64::*;
 
 pub fn stack_blur_pass_neon_i32<const COMPONENTS: usize>(
     pixels: &UnsafeSlice<u8>,
@@ -40,13 +41,12 @@ pub fn stack_blur_pass_neon_i32<const COMPONENTS: usize>(
     pass: StackBlurPass,
     thread: usize,
     total_threads: usize,
+    stacks: &mut [u8],
 ) {
     unsafe {
-        let div = ((radius * 2) + 1) as usize;
         let (mut xp, mut yp);
         let mut sp;
         let mut stack_start;
-        let mut stacks = vec![0i32; 4 * div];
 
         let mut sums: int32x4_t;
         let mut sum_in: int32x4_t;
@@ -55,6 +55,8 @@ pub fn stack_blur_pass_neon_i32<const COMPONENTS: usize>(
         let wm = width - 1;
         let hm = height - 1;
         let div = (radius * 2) + 1;
+        // Stacks are placed into the scratch at the first position aligned for the accumulator
+        let stacks: &mut [i32] = aligned_scratch(stacks, 4 * div as usize);
         let mul_sum = vdup_n_s32(MUL_TABLE_STACK_BLUR[radius as usize]);
         let shr_sum = vdupq_n_s64(-SHR_TABLE_STACK_BLUR[radius as usize] as i64);
 
@@ -0,0 +1,595 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::edge_mode::border_position;
+use crate::rank_filter::rank_filter_dispatch;
+use crate::threading_policy::type_work_per_pixel;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{EdgeMode, ThreadingPolicy};
+use alloc::vec;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+pub(crate) trait NonLocalMeansValue: Copy + Send + Sync + 'static {
+    fn to_f32(self) -> f32;
+    fn from_f32(value: f32) -> Self;
+}
+
+impl NonLocalMeansValue for u8 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value.round().clamp(0f32, 255f32) as u8
+    }
+}
+
+impl NonLocalMeansValue for f32 {
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+/// Non-local means denoising
+///
+/// A. Buades, B. Coll, J. M. Morel, A non-local algorithm for image denoising, 2005.
+/// J. Darbon et al., Fast nonlocal filtering applied to electron cryomicroscopy, 2008.
+/// For each offset in the search window squared differences between the image and the shifted image
+/// are accumulated into integral image, so patch distance for every pixel costs O(1).
+/// Rows `start_y..end_y` are processed, integral image also covers `patch_radius` rows around them.
+fn non_local_means_impl<T: NonLocalMeansValue, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    patch_radius: u32,
+    search_radius: u32,
+    strength: f32,
+    start_y: u32,
+    end_y: u32,
+) {
+    if start_y >= end_y || width == 0 {
+        return;
+    }
+    let patch_radius = patch_radius as i64;
+    let search_radius = search_radius as i64;
+    let rows = (end_y - start_y) as usize;
+    let columns = width as usize;
+    let integral_rows = rows + 2 * patch_radius as usize;
+    let integral_columns = columns + 2 * patch_radius as usize;
+    let integral_stride = integral_columns + 1;
+    let mut integral = vec![0f64; (integral_rows + 1) * integral_stride];
+    let mut numerator = vec![0f32; rows * columns * CHANNELS_CONFIGURATION];
+    let mut denominator = vec![0f32; rows * columns];
+    let patch_side = 2 * patch_radius as usize + 1;
+    let normalization = 1f64 / (patch_side * patch_side * CHANNELS_CONFIGURATION) as f64;
+    let inverse_strength = 1f32 / (strength * strength).max(f32::MIN_POSITIVE);
+
+    let pixel_offset = |x: i64, y: i64| -> usize {
+        let column = border_position(x, width as usize, EdgeMode::Clamp).unwrap_or_default();
+        let row = border_position(y, height as usize, EdgeMode::Clamp).unwrap_or_default();
+        row * src_stride as usize + column * CHANNELS_CONFIGURATION
+    };
+
+    for dy in -search_radius..=search_radius {
+        for dx in -search_radius..=search_radius {
+            for i in 0..integral_rows {
+                let y = start_y as i64 - patch_radius + i as i64;
+                let mut row_sum = 0f64;
+                let (previous, current) = integral.split_at_mut((i + 1) * integral_stride);
+                let previous = &previous[i * integral_stride..];
+                for j in 0..integral_columns {
+                    let x = j as i64 - patch_radius;
+                    let p = pixel_offset(x, y);
+                    let q = pixel_offset(x + dx, y + dy);
+                    let mut difference = 0f32;
+                    for c in 0..CHANNELS_CONFIGURATION {
+                        let d = src[p + c].to_f32() - src[q + c].to_f32();
+                        difference += d * d;
+                    }
+                    row_sum += difference as f64;
+                    current[j + 1] = previous[j + 1] + row_sum;
+                }
+            }
+
+            for i in 0..rows {
+                let y = start_y as i64 + i as i64;
+                let top = &integral[i * integral_stride..];
+                let bottom = &integral[(i + patch_side) * integral_stride..];
+                for x in 0..columns {
+                    let distance = (bottom[x + patch_side] - bottom[x] - top[x + patch_side]
+                        + top[x])
+                        * normalization;
+                    let weight = (-(distance as f32) * inverse_strength).exp();
+                    let q = pixel_offset(x as i64 + dx, y + dy);
+                    let index = i * columns + x;
+                    denominator[index] += weight;
+                    let accumulator = &mut numerator
+                        [index * CHANNELS_CONFIGURATION..(index + 1) * CHANNELS_CONFIGURATION];
+                    for (c, dst) in accumulator.iter_mut().enumerate() {
+                        *dst += weight * src[q + c].to_f32();
+                    }
+                }
+            }
+        }
+    }
+
+    for i in 0..rows {
+        let y_dst_offset = (start_y as usize + i) * dst_stride as usize;
+        for x in 0..columns {
+            let index = i * columns + x;
+            let weight = denominator[index];
+            let accumulator =
+                &numerator[index * CHANNELS_CONFIGURATION..(index + 1) * CHANNELS_CONFIGURATION];
+            for (c, &v) in accumulator.iter().enumerate() {
+                unsafe {
+                    unsafe_dst.write(
+                        y_dst_offset + x * CHANNELS_CONFIGURATION + c,
+                        T::from_f32(v / weight),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn non_local_means_dispatch<T: NonLocalMeansValue, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    patch_radius: u32,
+    search_radius: u32,
+    strength: f32,
+    threading_policy: ThreadingPolicy,
+) {
+    let unsafe_dst = UnsafeSlice::new(dst);
+    let search_side = (2 * search_radius + 1) as f32;
+    rank_filter_dispatch(
+        width,
+        height,
+        threading_policy,
+        search_side * search_side * type_work_per_pixel::<T>(),
+        |start_y, end_y| {
+            non_local_means_impl::<T, CHANNELS_CONFIGURATION>(
+                src,
+                src_stride,
+                &unsafe_dst,
+                dst_stride,
+                width,
+                height,
+                patch_radius,
+                search_radius,
+                strength,
+                start_y,
+                end_y,
+            );
+        },
+    );
+}
+
+/// Performs non-local means denoising on the image.
+///
+/// Each pixel is replaced with the average of pixels in the search window weighted by similarity
+/// of the patches around them, `exp(-distance / (strength * strength))` where distance is a mean squared difference of the patches.
+/// Patch distances are computed with integral images.
+/// O(S^2) complexity where S is a search radius, patch radius do not affect speed.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `patch_radius` - Radius of patches to compare, usually 1..3
+/// * `search_radius` - Radius of search window, usually 5..10
+/// * `strength` - Filter strength in levels 0..255, larger removes more noise and more details, usually 3..15
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn non_local_means(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    patch_radius: u32,
+    search_radius: u32,
+    strength: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    match channels {
+        FastBlurChannels::Channels3 => {
+            non_local_means_dispatch::<u8, 3>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                patch_radius,
+                search_radius,
+                strength,
+                threading_policy,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            non_local_means_dispatch::<u8, 4>(
+                src,
+                src_stride,
+                dst,
+                dst_stride,
+                width,
+                height,
+                patch_radius,
+                search_radius,
+                strength,
+                threading_policy,
+            );
+        }
+    }
+}
+
+/// Performs non-local means denoising on the single plane image, gray etc.
+///
+/// Each pixel is replaced with the average of pixels in the search window weighted by similarity
+/// of the patches around them, `exp(-distance / (strength * strength))` where distance is a mean squared difference of the patches.
+/// Patch distances are computed with integral images.
+/// O(S^2) complexity where S is a search radius, patch radius do not affect speed.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width if not aligned
+/// * `dst_stride` - Lane length, default is width if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `patch_radius` - Radius of patches to compare, usually 1..3
+/// * `search_radius` - Radius of search window, usually 5..10
+/// * `strength` - Filter strength in levels 0..255, larger removes more noise and more details, usually 3..15
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is stride/width/height do not match provided
+pub fn non_local_means_plane(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    patch_radius: u32,
+    search_radius: u32,
+    strength: f32,
+    threading_policy: ThreadingPolicy,
+) {
+    non_local_means_dispatch::<u8, 1>(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        patch_radius,
+        search_radius,
+        strength,
+        threading_policy,
+    );
+}
+
+/// Performs non-local means denoising on the f32 image.
+///
+/// Each pixel is replaced with the average of pixels in the search window weighted by similarity
+/// of the patches around them, `exp(-distance / (strength * strength))` where distance is a mean squared difference of the patches.
+/// Patch distances are computed with integral images.
+/// O(S^2) complexity where S is a search radius, patch radius do not affect speed.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `patch_radius` - Radius of patches to compare, usually 1..3
+/// * `search_radius` - Radius of search window, usually 5..10
+/// * `strength` - Filter strength in the units of the image, for image in range 0..1 usually 0.01..0.06
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is width/height/channel configuration do not match provided
+pub fn non_local_means_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    patch_radius: u32,
+    search_radius: u32,
+    strength: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    let stride = width * channels.get_channels() as u32;
+    match channels {
+        FastBlurChannels::Channels3 => {
+            non_local_means_dispatch::<f32, 3>(
+                src,
+                stride,
+                dst,
+                stride,
+                width,
+                height,
+                patch_radius,
+                search_radius,
+                strength,
+                threading_policy,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            non_local_means_dispatch::<f32, 4>(
+                src,
+                stride,
+                dst,
+                stride,
+                width,
+                height,
+                patch_radius,
+                search_radius,
+                strength,
+                threading_policy,
+            );
+        }
+    }
+}
+
+/// Performs non-local means denoising on the f32 single plane image, gray etc.
+///
+/// Each pixel is replaced with the average of pixels in the search window weighted by similarity
+/// of the patches around them, `exp(-distance / (strength * strength))` where distance is a mean squared difference of the patches.
+/// Patch distances are computed with integral images.
+/// O(S^2) complexity where S is a search radius, patch radius do not affect speed.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `patch_radius` - Radius of patches to compare, usually 1..3
+/// * `search_radius` - Radius of search window, usually 5..10
+/// * `strength` - Filter strength in the units of the image, for image in range 0..1 usually 0.01..0.06
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is width/height do not match provided
+pub fn non_local_means_plane_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    patch_radius: u32,
+    search_radius: u32,
+    strength: f32,
+    threading_policy: ThreadingPolicy,
+) {
+    non_local_means_dispatch::<f32, 1>(
+        src,
+        width,
+        dst,
+        width,
+        width,
+        height,
+        patch_radius,
+        search_radius,
+        strength,
+        threading_policy,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn brute_force(
+        src: &[f32],
+        width: usize,
+        height: usize,
+        channels: usize,
+        patch_radius: i64,
+        search_radius: i64,
+        strength: f32,
+    ) -> Vec<f32> {
+        let at = |x: i64, y: i64, c: usize| -> f32 {
+            let x = x.clamp(0, width as i64 - 1) as usize;
+            let y = y.clamp(0, height as i64 - 1) as usize;
+            src[(y * width + x) * channels + c]
+        };
+        let patch_side = (2 * patch_radius + 1) as f32;
+        let normalization = 1f32 / (patch_side * patch_side * channels as f32);
+        let mut dst = vec![0f32; src.len()];
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                let mut numerator = vec![0f32; channels];
+                let mut denominator = 0f32;
+                for sy in -search_radius..=search_radius {
+                    for sx in -search_radius..=search_radius {
+                        let mut distance = 0f32;
+                        for py in -patch_radius..=patch_radius {
+                            for px in -patch_radius..=patch_radius {
+                                for c in 0..channels {
+                                    let d = at(x + px, y + py, c) - at(x + px + sx, y + py + sy, c);
+                                    distance += d * d;
+                                }
+                            }
+                        }
+                        let weight = (-distance * normalization / (strength * strength)).exp();
+                        denominator += weight;
+                        for (c, n) in numerator.iter_mut().enumerate() {
+                            *n += weight * at(x + sx, y + sy, c);
+                        }
+                    }
+                }
+                for (c, n) in numerator.iter().enumerate() {
+                    dst[(y as usize * width + x as usize) * channels + c] = n / denominator;
+                }
+            }
+        }
+        dst
+    }
+
+    fn noisy_image(width: usize, height: usize, channels: usize) -> Vec<u8> {
+        (0..width * height * channels)
+            .map(|i| {
+                let x = (i / channels) % width;
+                let base = if x < width / 2 { 70 } else { 180 };
+                (base + (i * 7919 + 5) % 17) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn u8_matches_brute_force() {
+        let (width, height) = (19usize, 14usize);
+        for (channels, cn) in [
+            (None, 1usize),
+            (Some(FastBlurChannels::Channels3), 3),
+            (Some(FastBlurChannels::Channels4), 4),
+        ] {
+            let src = noisy_image(width, height, cn);
+            let stride = (width * cn) as u32;
+            for (patch_radius, search_radius, strength) in [(1u32, 3u32, 10f32), (2, 5, 6f32)] {
+                for threading_policy in [ThreadingPolicy::Single, ThreadingPolicy::Fixed(3)] {
+                    let mut dst = vec![0u8; src.len()];
+                    match channels {
+                        None => non_local_means_plane(
+                            &src,
+                            stride,
+                            &mut dst,
+                            stride,
+                            width as u32,
+                            height as u32,
+                            patch_radius,
+                            search_radius,
+                            strength,
+                            threading_policy,
+                        ),
+                        Some(channels) => non_local_means(
+                            &src,
+                            stride,
+                            &mut dst,
+                            stride,
+                            width as u32,
+                            height as u32,
+                            patch_radius,
+                            search_radius,
+                            strength,
+                            channels,
+                            threading_policy,
+                        ),
+                    }
+                    let reference = brute_force(
+                        &src.iter().map(|&v| v as f32).collect::<Vec<_>>(),
+                        width,
+                        height,
+                        cn,
+                        patch_radius as i64,
+                        search_radius as i64,
+                        strength,
+                    );
+                    for (i, (&d, &r)) in dst.iter().zip(reference.iter()).enumerate() {
+                        assert!(
+                            (d as f32 - r).abs() <= 1f32,
+                            "channels {} at {}: {} vs {}",
+                            cn,
+                            i,
+                            d,
+                            r
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn f32_reduces_noise_and_keeps_edge() {
+        let (width, height) = (24usize, 16usize);
+        let src = noisy_image(width, height, 1)
+            .iter()
+            .map(|&v| v as f32 / 255f32)
+            .collect::<Vec<_>>();
+        let mut dst = vec![0f32; src.len()];
+        non_local_means_plane_f32(
+            &src,
+            &mut dst,
+            width as u32,
+            height as u32,
+            1,
+            4,
+            0.05f32,
+            ThreadingPolicy::Adaptive,
+        );
+        let reference = brute_force(&src, width, height, 1, 1, 4, 0.05f32);
+        for (&d, &r) in dst.iter().zip(reference.iter()) {
+            assert!((d - r).abs() < 1e-4, "{} vs {}", d, r);
+        }
+        let variance = |image: &[f32], left: bool| -> f32 {
+            let values = image
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| (i % width < width / 2) == left)
+                .map(|(_, &v)| v)
+                .collect::<Vec<_>>();
+            let mean = values.iter().sum::<f32>() / values.len() as f32;
+            values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / values.len() as f32
+        };
+        for left in [true, false] {
+            assert!(variance(&dst, left) < variance(&src, left) * 0.5f32);
+        }
+        for (i, &v) in dst.iter().enumerate() {
+            if i % width < width / 2 {
+                assert!(v < 0.45f32);
+            } else {
+                assert!(v > 0.6f32);
+            }
+        }
+    }
+}
@@ -0,0 +1,529 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::{fast_gaussian_f32, gaussian_blur_f32, EdgeMode, ThreadingPolicy};
+use alloc::vec;
+use alloc::vec::Vec;
+
+const WEIGHT_EPSILON: f32 = 1e-5;
+
+/// Blurs values premultiplied by weights and the weights with the same filter and divides them in place.
+///
+/// Each pixel has `CHANNELS_CONFIGURATION` value lanes followed by `WEIGHTS` weight lanes,
+/// one weight shared by all channels or one weight for each channel,
+/// `weight` receives pixel index, channel and the value of the channel.
+/// Lanes are packed into 3 or 4 channel images supported by the blur,
+/// `blur` blurs packed lanes into the second buffer of the same size, both are reused for every pack.
+/// Values without any weight in the neighborhood become `empty`, weights below `WEIGHT_EPSILON`
+/// of the largest weight are treated as no weight.
+pub(crate) fn normalized_convolution_impl<
+    const CHANNELS_CONFIGURATION: usize,
+    const WEIGHTS: usize,
+>(
+    image: &mut [f32],
+    width: u32,
+    height: u32,
+    weight: impl Fn(usize, usize, f32) -> f32,
+    empty: f32,
+    mut blur: impl FnMut(&[f32], &mut [f32], FastBlurChannels),
+) {
+    let pixels = width as usize * height as usize;
+    assert!(
+        image.len() >= pixels * CHANNELS_CONFIGURATION,
+        "Image buffer is smaller than width * height * channels"
+    );
+    let lanes = CHANNELS_CONFIGURATION + WEIGHTS;
+    let mut groups = Vec::new();
+    let mut start = 0usize;
+    while start < lanes {
+        let remaining = lanes - start;
+        let channels = if remaining & 3 == 0 {
+            FastBlurChannels::Channels4
+        } else {
+            FastBlurChannels::Channels3
+        };
+        let end = lanes.min(start + channels.get_channels());
+        groups.push((start, end, channels));
+        start = end;
+    }
+    let mut accumulated = vec![0f32; pixels * lanes];
+    let mut packed = vec![0f32; pixels * 4];
+    let mut blurred = vec![0f32; pixels * 4];
+    let mut max_weight = 0f32;
+    for &(start, end, channels) in groups.iter() {
+        let group_channels = channels.get_channels();
+        let packed = &mut packed[..pixels * group_channels];
+        let blurred = &mut blurred[..pixels * group_channels];
+        for (pixel, (dst, src)) in packed
+            .chunks_exact_mut(group_channels)
+            .zip(image.chunks_exact(CHANNELS_CONFIGURATION))
+            .take(pixels)
+            .enumerate()
+        {
+            for (dst, lane) in dst.iter_mut().zip(start..end) {
+                *dst = if lane >= CHANNELS_CONFIGURATION {
+                    let channel = lane - CHANNELS_CONFIGURATION;
+                    let weight = weight(pixel, channel, src[channel]);
+                    max_weight = max_weight.max(weight);
+                    weight
+                } else {
+                    let weight = weight(pixel, lane, src[lane]);
+                    if weight != 0f32 {
+                        src[lane] * weight
+                    } else {
+                        0f32
+                    }
+                };
+            }
+        }
+        blur(packed, blurred, channels);
+        for (dst, src) in accumulated
+            .chunks_exact_mut(lanes)
+            .zip(blurred.chunks_exact(group_channels))
+        {
+            dst[start..end].copy_from_slice(&src[..end - start]);
+        }
+    }
+    // Running sums blurs leave residuals in f32 where the true weight is zero
+    let min_weight = max_weight * WEIGHT_EPSILON;
+    for (dst, src) in image
+        .chunks_exact_mut(CHANNELS_CONFIGURATION)
+        .zip(accumulated.chunks_exact(lanes))
+    {
+        let (values, weights) = src.split_at(CHANNELS_CONFIGURATION);
+        for (c, (dst, &v)) in dst.iter_mut().zip(values.iter()).enumerate() {
+            let weight = weights[if WEIGHTS == 1 { 0 } else { c }];
+            *dst = if weight > min_weight {
+                v / weight
+            } else {
+                empty
+            };
+        }
+    }
+}
+
+fn normalized_gaussian_blur_dispatch<const CHANNELS_CONFIGURATION: usize>(
+    src: &[f32],
+    weights: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) {
+    let image_size = width as usize * height as usize * CHANNELS_CONFIGURATION;
+    dst[..image_size].copy_from_slice(&src[..image_size]);
+    normalized_convolution_impl::<CHANNELS_CONFIGURATION, 1>(
+        dst,
+        width,
+        height,
+        |pixel, _, _| weights[pixel],
+        0f32,
+        |packed, blurred, channels| {
+            gaussian_blur_f32(
+                packed,
+                blurred,
+                width,
+                height,
+                kernel_size,
+                sigma,
+                channels,
+                edge_mode,
+                threading_policy,
+            );
+        },
+    );
+}
+
+fn normalized_fast_gaussian_dispatch<const CHANNELS_CONFIGURATION: usize>(
+    src: &[f32],
+    weights: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    let image_size = width as usize * height as usize * CHANNELS_CONFIGURATION;
+    dst[..image_size].copy_from_slice(&src[..image_size]);
+    normalized_convolution_impl::<CHANNELS_CONFIGURATION, 1>(
+        dst,
+        width,
+        height,
+        |pixel, _, _| weights[pixel],
+        0f32,
+        |packed, blurred, channels| {
+            blurred.copy_from_slice(packed);
+            fast_gaussian_f32(
+                blurred,
+                width,
+                height,
+                radius,
+                channels,
+                threading_policy,
+                edge_mode,
+            );
+        },
+    );
+}
+
+/// Performs normalized gaussian blur on the image with per pixel weights.
+///
+/// Values multiplied by weights and the weights are blurred separately and divided,
+/// so pixels with zero weight are filled from valid neighbors instead of pulling zeros in.
+/// Useful for sparse depth maps and filling small holes. Pixels without any weight in the kernel become zero.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `weights` - Confidence of each pixel, one per pixel, 0 marks invalid pixel
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
+/// * `channels` - Count of channels in the image
+/// * `edge_mode` - Rule to handle edge mode
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is width/height/channel configuration or weights length do not match provided
+pub fn normalized_gaussian_blur_f32(
+    src: &[f32],
+    weights: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) {
+    let dispatcher = match channels {
+        FastBlurChannels::Channels3 => normalized_gaussian_blur_dispatch::<3>,
+        FastBlurChannels::Channels4 => normalized_gaussian_blur_dispatch::<4>,
+    };
+    dispatcher(
+        src,
+        weights,
+        dst,
+        width,
+        height,
+        kernel_size,
+        sigma,
+        edge_mode,
+        threading_policy,
+    );
+}
+
+/// Performs normalized gaussian blur on the single plane image with per pixel weights, depth maps etc.
+///
+/// Values multiplied by weights and the weights are blurred separately and divided,
+/// so pixels with zero weight are filled from valid neighbors instead of pulling zeros in.
+/// Pixels without any weight in the kernel become zero.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `weights` - Confidence of each pixel, 0 marks invalid pixel
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `kernel_size` - Length of gaussian kernel. Panic if kernel size is not odd, even kernels with unbalanced center is not accepted.
+/// * `sigma` - Sigma for a gaussian kernel, corresponds to kernel flattening level. Default - kernel_size / 6
+/// * `edge_mode` - Rule to handle edge mode
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+///
+/// # Panics
+/// Panic is width/height or weights length do not match provided
+pub fn normalized_gaussian_blur_plane_f32(
+    src: &[f32],
+    weights: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    kernel_size: u32,
+    sigma: f32,
+    edge_mode: EdgeMode,
+    threading_policy: ThreadingPolicy,
+) {
+    normalized_gaussian_blur_dispatch::<1>(
+        src,
+        weights,
+        dst,
+        width,
+        height,
+        kernel_size,
+        sigma,
+        edge_mode,
+        threading_policy,
+    );
+}
+
+/// Performs normalized gaussian approximation on the image with per pixel weights.
+///
+/// Values multiplied by weights and the weights are blurred separately with *fast_gaussian_f32* and divided,
+/// so pixels with zero weight are filled from valid neighbors instead of pulling zeros in.
+/// Pixels without any weight in the kernel become zero.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `weights` - Confidence of each pixel, one per pixel, 0 marks invalid pixel
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - almost any radius is supported
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+///
+/// # Panics
+/// Panic is width/height/channel configuration or weights length do not match provided
+pub fn normalized_fast_gaussian_f32(
+    src: &[f32],
+    weights: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    let dispatcher = match channels {
+        FastBlurChannels::Channels3 => normalized_fast_gaussian_dispatch::<3>,
+        FastBlurChannels::Channels4 => normalized_fast_gaussian_dispatch::<4>,
+    };
+    dispatcher(
+        src,
+        weights,
+        dst,
+        width,
+        height,
+        radius,
+        threading_policy,
+        edge_mode,
+    );
+}
+
+/// Performs normalized gaussian approximation on the single plane image with per pixel weights, depth maps etc.
+///
+/// Values multiplied by weights and the weights are blurred separately with *fast_gaussian_f32* and divided,
+/// so pixels with zero weight are filled from valid neighbors instead of pulling zeros in.
+/// Pixels without any weight in the kernel become zero.
+/// O(1) complexity.
+///
+/// # Arguments
+///
+/// * `weights` - Confidence of each pixel, 0 marks invalid pixel
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - almost any radius is supported
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Edge handling mode, *Kernel clip* is not supported!
+///
+/// # Panics
+/// Panic is width/height or weights length do not match provided
+pub fn normalized_fast_gaussian_plane_f32(
+    src: &[f32],
+    weights: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    normalized_fast_gaussian_dispatch::<1>(
+        src,
+        weights,
+        dst,
+        width,
+        height,
+        radius,
+        threading_policy,
+        edge_mode,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_image(width: usize, height: usize, channels: usize) -> Vec<f32> {
+        (0..width * height * channels)
+            .map(|i| ((i * 7919 + 11) % 97) as f32 / 97f32)
+            .collect()
+    }
+
+    #[test]
+    fn identity_blur_keeps_weighted_values() {
+        let src = test_image(5, 4, 3);
+        let mut dst = src.clone();
+        normalized_convolution_impl::<3, 3>(
+            &mut dst,
+            5,
+            4,
+            |pixel, channel, _| ((pixel + channel) % 3) as f32,
+            -1f32,
+            |packed, blurred, _| blurred.copy_from_slice(packed),
+        );
+        for (i, (&d, &s)) in dst.iter().zip(src.iter()).enumerate() {
+            if (i / 3 + i % 3) % 3 == 0 {
+                assert_eq!(d, -1f32);
+            } else {
+                assert!((d - s).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn unit_weights_match_gaussian_blur() {
+        let (width, height) = (21u32, 16u32);
+        let src = test_image(width as usize, height as usize, 4);
+        let weights = vec![1f32; (width * height) as usize];
+        for edge_mode in [EdgeMode::Clamp, EdgeMode::Reflect101] {
+            let mut expected = vec![0f32; src.len()];
+            gaussian_blur_f32(
+                &src,
+                &mut expected,
+                width,
+                height,
+                7,
+                1.5f32,
+                FastBlurChannels::Channels4,
+                edge_mode,
+                ThreadingPolicy::Single,
+            );
+            let mut dst = vec![0f32; src.len()];
+            normalized_gaussian_blur_f32(
+                &src,
+                &weights,
+                &mut dst,
+                width,
+                height,
+                7,
+                1.5f32,
+                FastBlurChannels::Channels4,
+                edge_mode,
+                ThreadingPolicy::Single,
+            );
+            for (&d, &e) in dst.iter().zip(expected.iter()) {
+                assert!((d - e).abs() < 1e-4, "{:?} {} vs {}", edge_mode, d, e);
+            }
+        }
+    }
+
+    #[test]
+    fn holes_are_filled_from_valid_neighbors() {
+        let (width, height) = (24u32, 18u32);
+        let pixels = (width * height) as usize;
+        let weights = (0..pixels)
+            .map(|i| {
+                if i % 5 == 2 {
+                    0f32
+                } else {
+                    0.5f32 + (i % 3) as f32
+                }
+            })
+            .collect::<Vec<_>>();
+        let src = weights
+            .iter()
+            .map(|&w| if w == 0f32 { 1000f32 } else { 0.25f32 })
+            .collect::<Vec<_>>();
+        let mut dst = vec![0f32; pixels];
+        normalized_gaussian_blur_plane_f32(
+            &src,
+            &weights,
+            &mut dst,
+            width,
+            height,
+            9,
+            2f32,
+            EdgeMode::Reflect,
+            ThreadingPolicy::Adaptive,
+        );
+        assert!(dst.iter().all(|&v| (v - 0.25f32).abs() < 1e-4));
+        let mut dst = vec![0f32; pixels];
+        normalized_fast_gaussian_plane_f32(
+            &src,
+            &weights,
+            &mut dst,
+            width,
+            height,
+            4,
+            ThreadingPolicy::Adaptive,
+            EdgeMode::Clamp,
+        );
+        assert!(dst.iter().all(|&v| (v - 0.25f32).abs() < 1e-3));
+    }
+
+    #[test]
+    fn scaled_weights_and_empty_image() {
+        let (width, height) = (13u32, 9u32);
+        let pixels = (width * height) as usize;
+        let src = test_image(width as usize, height as usize, 3);
+        let weights = (0..pixels).map(|i| (i % 4) as f32).collect::<Vec<_>>();
+        let scaled = weights.iter().map(|&w| w * 8f32).collect::<Vec<_>>();
+        let mut first = vec![0f32; src.len()];
+        let mut second = vec![0f32; src.len()];
+        for (weights, dst) in [(&weights, &mut first), (&scaled, &mut second)] {
+            normalized_fast_gaussian_f32(
+                &src,
+                weights,
+                dst,
+                width,
+                height,
+                3,
+                FastBlurChannels::Channels3,
+                ThreadingPolicy::Single,
+                EdgeMode::Clamp,
+            );
+        }
+        for (&a, &b) in first.iter().zip(second.iter()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+
+        let mut dst = vec![1f32; src.len()];
+        normalized_gaussian_blur_f32(
+            &src,
+            &vec![0f32; pixels],
+            &mut dst,
+            width,
+            height,
+            5,
+            1f32,
+            FastBlurChannels::Channels3,
+            EdgeMode::Clamp,
+            ThreadingPolicy::Single,
+        );
+        assert!(dst.iter().all(|&v| v == 0f32));
+    }
+}
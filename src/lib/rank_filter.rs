@@ -0,0 +1,877 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::edge_mode::border_position;
+use crate::median_blur::rank_filter_columns_impl;
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{RankEdgeMode, ThreadingPolicy};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Declares shape of the rank filter window
+pub enum RankWindow {
+    /// Square `(2 * radius + 1) x (2 * radius + 1)` window
+    #[default]
+    Square = 0,
+    /// Circular window, pixels with `dx * dx + dy * dy <= radius * radius`
+    Circle = 1,
+}
+
+pub(crate) trait RankValue: Copy + Send + Sync + 'static {
+    /// Count of fine bins covered by one coarse bin in bits
+    const COARSE_SHIFT: usize;
+    const FINE_BINS: usize;
+    fn bin(self) -> usize;
+    fn from_bin(bin: usize) -> Self;
+}
+
+impl RankValue for u8 {
+    const COARSE_SHIFT: usize = 4;
+    const FINE_BINS: usize = 256;
+
+    #[inline(always)]
+    fn bin(self) -> usize {
+        self as usize
+    }
+
+    #[inline(always)]
+    fn from_bin(bin: usize) -> Self {
+        bin as u8
+    }
+}
+
+impl RankValue for u16 {
+    const COARSE_SHIFT: usize = 8;
+    const FINE_BINS: usize = 65536;
+
+    #[inline(always)]
+    fn bin(self) -> usize {
+        self as usize
+    }
+
+    #[inline(always)]
+    fn from_bin(bin: usize) -> Self {
+        bin as u16
+    }
+}
+
+/// Rank of requested percentile in the kernel histogram holding `count` samples of `window`,
+/// `None` means that value is a zero from *Constant* border
+#[inline(always)]
+pub(crate) fn kernel_rank(
+    count: u32,
+    window: u32,
+    percentile: f64,
+    edge_mode: RankEdgeMode,
+) -> Option<u32> {
+    let total = if edge_mode == RankEdgeMode::Constant {
+        window
+    } else {
+        count
+    };
+    let rank = (total.saturating_sub(1) as f64 * percentile).round() as u32;
+    if edge_mode == RankEdgeMode::Constant {
+        let zeros = window - count;
+        if rank < zeros {
+            None
+        } else {
+            Some(rank - zeros)
+        }
+    } else {
+        Some(rank)
+    }
+}
+
+/// Finds coarse segment holding value with the given rank, returns segment and rank inside segment
+#[inline(always)]
+pub(crate) fn histogram_rank(coarse: &[u32], rank: u32) -> (usize, u32) {
+    let mut accumulated = 0u32;
+    for (segment, &count) in coarse.iter().enumerate() {
+        if accumulated + count > rank {
+            return (segment, rank - accumulated);
+        }
+        accumulated += count;
+    }
+    (coarse.len() - 1, 0)
+}
+
+#[inline(always)]
+pub(crate) fn segment_rank(fine: &[u32], rank: u32) -> usize {
+    let mut accumulated = 0u32;
+    for (bin, &count) in fine.iter().enumerate() {
+        accumulated += count;
+        if accumulated > rank {
+            return bin;
+        }
+    }
+    fine.len() - 1
+}
+
+/// Horizontal half extent of the window for each row `-radius..=radius`,
+/// for both windows it is also vertical half extent for each column
+pub(crate) fn rank_window_spans(radius: u32, window: RankWindow) -> Vec<i64> {
+    let radius = radius as i64;
+    match window {
+        RankWindow::Square => vec![radius; 2 * radius as usize + 1],
+        RankWindow::Circle => (-radius..=radius)
+            .map(|dy| {
+                let limit = radius * radius - dy * dy;
+                let mut span = (limit as f64).sqrt() as i64;
+                while span * span > limit {
+                    span -= 1;
+                }
+                while (span + 1) * (span + 1) <= limit {
+                    span += 1;
+                }
+                span
+            })
+            .collect(),
+    }
+}
+
+/// Histogram of the sliding window which accepts pixels entering and leaving the window
+pub(crate) trait WindowHistogram<T> {
+    fn add(&mut self, pixel: &[T]);
+    fn remove(&mut self, pixel: &[T]);
+}
+
+/// Two-level histogram of the kernel, coarse level is used to find segment and fine level is a full histogram
+pub(crate) struct RankHistogram<T: RankValue, const CHANNELS_CONFIGURATION: usize> {
+    coarse: Vec<u32>,
+    fine: Vec<u32>,
+    count: u32,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: RankValue, const CHANNELS_CONFIGURATION: usize> RankHistogram<T, CHANNELS_CONFIGURATION> {
+    const COARSE_BINS: usize = T::FINE_BINS >> T::COARSE_SHIFT;
+
+    pub(crate) fn new() -> Self {
+        RankHistogram {
+            coarse: vec![0u32; Self::COARSE_BINS * CHANNELS_CONFIGURATION],
+            fine: vec![0u32; T::FINE_BINS * CHANNELS_CONFIGURATION],
+            count: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn rank(
+        &self,
+        channel: usize,
+        window: u32,
+        percentile: f64,
+        edge_mode: RankEdgeMode,
+    ) -> T {
+        let Some(rank) = kernel_rank(self.count, window, percentile, edge_mode) else {
+            return T::from_bin(0);
+        };
+        let coarse = &self.coarse[channel * Self::COARSE_BINS..(channel + 1) * Self::COARSE_BINS];
+        let (segment, rank) = histogram_rank(coarse, rank);
+        let segment_size = 1 << T::COARSE_SHIFT;
+        let fine_start = channel * T::FINE_BINS + segment * segment_size;
+        let bin = segment_rank(&self.fine[fine_start..fine_start + segment_size], rank);
+        T::from_bin(segment * segment_size + bin)
+    }
+}
+
+impl<T: RankValue, const CHANNELS_CONFIGURATION: usize> WindowHistogram<T>
+    for RankHistogram<T, CHANNELS_CONFIGURATION>
+{
+    #[inline(always)]
+    fn add(&mut self, pixel: &[T]) {
+        for (c, &v) in pixel.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+            let v = v.bin();
+            unsafe {
+                *self
+                    .coarse
+                    .get_unchecked_mut(c * Self::COARSE_BINS + (v >> T::COARSE_SHIFT)) += 1;
+                *self.fine.get_unchecked_mut(c * T::FINE_BINS + v) += 1;
+            }
+        }
+        self.count += 1;
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, pixel: &[T]) {
+        for (c, &v) in pixel.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+            let v = v.bin();
+            unsafe {
+                *self
+                    .coarse
+                    .get_unchecked_mut(c * Self::COARSE_BINS + (v >> T::COARSE_SHIFT)) -= 1;
+                *self.fine.get_unchecked_mut(c * T::FINE_BINS + v) -= 1;
+            }
+        }
+        self.count -= 1;
+    }
+}
+
+/// Adds or removes pixel at `x`, `y` when it is inside the image after edge mapping
+#[inline(always)]
+pub(crate) fn window_histogram_pixel<
+    T: Copy,
+    H: WindowHistogram<T>,
+    const CHANNELS_CONFIGURATION: usize,
+    const ADD: bool,
+>(
+    src: &[T],
+    src_stride: u32,
+    histogram: &mut H,
+    x: i64,
+    y: i64,
+    width: u32,
+    height: u32,
+    edge_mode: RankEdgeMode,
+) {
+    let (Some(column), Some(row)) = (
+        border_position(x, width as usize, edge_mode.border()),
+        border_position(y, height as usize, edge_mode.border()),
+    ) else {
+        return;
+    };
+    let offset = row * src_stride as usize + column * CHANNELS_CONFIGURATION;
+    let pixel = &src[offset..offset + CHANNELS_CONFIGURATION];
+    if ADD {
+        histogram.add(pixel);
+    } else {
+        histogram.remove(pixel);
+    }
+}
+
+/// Moves window of any shape over rows `start_y..end_y` in a snake order,
+/// on each step only the pixels entering and leaving the window are exchanged, so it costs O(R) per pixel.
+/// `emit` is called with the histogram of the window centered at each `x`, `y`
+pub(crate) fn window_snake_traversal<
+    T: Copy,
+    H: WindowHistogram<T>,
+    const CHANNELS_CONFIGURATION: usize,
+>(
+    src: &[T],
+    src_stride: u32,
+    histogram: &mut H,
+    width: u32,
+    height: u32,
+    spans: &[i64],
+    edge_mode: RankEdgeMode,
+    start_y: u32,
+    end_y: u32,
+    mut emit: impl FnMut(&H, u32, u32),
+) {
+    if start_y >= end_y || width == 0 {
+        return;
+    }
+    let radius = (spans.len() / 2) as i64;
+    let mut x = 0i64;
+    for (dy, &span) in (-radius..=radius).zip(spans.iter()) {
+        for dx in -span..=span {
+            window_histogram_pixel::<T, H, CHANNELS_CONFIGURATION, true>(
+                src,
+                src_stride,
+                histogram,
+                dx,
+                start_y as i64 + dy,
+                width,
+                height,
+                edge_mode,
+            );
+        }
+    }
+    for y in start_y..end_y {
+        let y_wide = y as i64;
+        if y > start_y {
+            for (dx, &span) in (-radius..=radius).zip(spans.iter()) {
+                window_histogram_pixel::<T, H, CHANNELS_CONFIGURATION, false>(
+                    src,
+                    src_stride,
+                    histogram,
+                    x + dx,
+                    y_wide - 1 - span,
+                    width,
+                    height,
+                    edge_mode,
+                );
+                window_histogram_pixel::<T, H, CHANNELS_CONFIGURATION, true>(
+                    src,
+                    src_stride,
+                    histogram,
+                    x + dx,
+                    y_wide + span,
+                    width,
+                    height,
+                    edge_mode,
+                );
+            }
+        }
+        let forward = (y - start_y) & 1 == 0;
+        let direction = if forward { 1i64 } else { -1i64 };
+        for step in 0..width {
+            if step > 0 {
+                for (dy, &span) in (-radius..=radius).zip(spans.iter()) {
+                    window_histogram_pixel::<T, H, CHANNELS_CONFIGURATION, false>(
+                        src,
+                        src_stride,
+                        histogram,
+                        x - direction * span,
+                        y_wide + dy,
+                        width,
+                        height,
+                        edge_mode,
+                    );
+                    window_histogram_pixel::<T, H, CHANNELS_CONFIGURATION, true>(
+                        src,
+                        src_stride,
+                        histogram,
+                        x + direction * (span + 1),
+                        y_wide + dy,
+                        width,
+                        height,
+                        edge_mode,
+                    );
+                }
+                x += direction;
+            }
+            emit(histogram, x as u32, y);
+        }
+    }
+}
+
+/// Rank filter with two-level kernel histogram for any window shape, O(R) per pixel
+pub(crate) fn rank_filter_snake_impl<T: RankValue, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    spans: &[i64],
+    percentile: f64,
+    edge_mode: RankEdgeMode,
+    start_y: u32,
+    end_y: u32,
+) {
+    let window = spans.iter().map(|&span| 2 * span as u32 + 1).sum::<u32>();
+    let mut histogram = RankHistogram::<T, CHANNELS_CONFIGURATION>::new();
+    window_snake_traversal::<T, RankHistogram<T, CHANNELS_CONFIGURATION>, CHANNELS_CONFIGURATION>(
+        src,
+        src_stride,
+        &mut histogram,
+        width,
+        height,
+        spans,
+        edge_mode,
+        start_y,
+        end_y,
+        |histogram, x, y| {
+            let offset = y as usize * dst_stride as usize + x as usize * CHANNELS_CONFIGURATION;
+            for c in 0..CHANNELS_CONFIGURATION {
+                unsafe {
+                    unsafe_dst.write(offset + c, histogram.rank(c, window, percentile, edge_mode));
+                }
+            }
+        },
+    );
+}
+
+/// Cost of pixel relative to stack blur for sliding histogram of the window
+pub(crate) fn rank_filter_work(radius: u32) -> f32 {
+    (2 * radius + 1) as f32 / 2f32
+}
+
+/// Splits rows between threads and runs `segment` for each range of rows
+pub(crate) fn rank_filter_dispatch<F: Fn(u32, u32) + Send + Sync>(
+    width: u32,
+    height: u32,
+    threading_policy: ThreadingPolicy,
+    work_per_pixel: f32,
+    segment: F,
+) {
+    let thread_count =
+        threading_policy.get_threads_count_for_work(width, height, work_per_pixel) as u32;
+    if thread_count == 1 {
+        segment(0, height);
+        return;
+    }
+    let pool = threading_policy.create_pool(thread_count as usize);
+    rank_filter_segments(&pool, thread_count, height, segment);
+}
+
+/// Splits rows of the image into segments for each thread of the pool
+pub(crate) fn rank_filter_segments<F: Fn(u32, u32) + Send + Sync>(
+    pool: &BlurPool,
+    thread_count: u32,
+    height: u32,
+    segment: F,
+) {
+    let segment = &segment;
+    pool.scope(|scope| {
+        let segment_size = height / thread_count;
+        for i in 0..thread_count {
+            let start_y = i * segment_size;
+            let mut end_y = (i + 1) * segment_size;
+            if i == thread_count - 1 {
+                end_y = height;
+            }
+            scope.spawn(move |_| {
+                segment(start_y, end_y);
+            });
+        }
+    });
+}
+
+fn rank_filter_u8_segment<const CHANNELS_CONFIGURATION: usize>(
+    src: &[u8],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    spans: &[i64],
+    percentile: f64,
+    window: RankWindow,
+    edge_mode: RankEdgeMode,
+    start_y: u32,
+    end_y: u32,
+) {
+    match window {
+        RankWindow::Square => {
+            rank_filter_columns_impl::<CHANNELS_CONFIGURATION>(
+                src, src_stride, unsafe_dst, dst_stride, width, height, radius, percentile,
+                edge_mode, start_y, end_y,
+            );
+        }
+        RankWindow::Circle => {
+            rank_filter_snake_impl::<u8, CHANNELS_CONFIGURATION>(
+                src, src_stride, unsafe_dst, dst_stride, width, height, spans, percentile,
+                edge_mode, start_y, end_y,
+            );
+        }
+    }
+}
+
+/// Performs rank filter on the image.
+///
+/// Writes value of the requested percentile in the window, 0 is the minimum, 50 is the median and 100 is the maximum.
+/// Square window uses Perreault - Hébert column histograms with O(1) complexity,
+/// circular window uses two-level histogram with O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - Radius of kernel
+/// * `percentile` - Percentile in range 0..=100
+/// * `window` - Window shape, see *RankWindow*
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, see *RankEdgeMode*, *KernelClip* ignores them and *Constant* treats them as zero
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn rank_filter(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    percentile: f32,
+    window: RankWindow,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: RankEdgeMode,
+) {
+    rank_filter_u8_impl(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        percentile,
+        window,
+        channels,
+        edge_mode,
+        |segment| {
+            rank_filter_dispatch(
+                width,
+                height,
+                threading_policy,
+                rank_filter_work(radius) * type_work_per_pixel::<u8>(),
+                segment,
+            )
+        },
+    );
+}
+
+/// Performs *rank_filter* in the thread pool
+pub(crate) fn rank_filter_in_pool(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    percentile: f32,
+    window: RankWindow,
+    channels: FastBlurChannels,
+    edge_mode: RankEdgeMode,
+    pool: &BlurPool,
+    thread_count: u32,
+) {
+    rank_filter_u8_impl(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        percentile,
+        window,
+        channels,
+        edge_mode,
+        |segment| rank_filter_segments(pool, thread_count, height, segment),
+    );
+}
+
+fn rank_filter_u8_impl(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    percentile: f32,
+    window: RankWindow,
+    channels: FastBlurChannels,
+    edge_mode: RankEdgeMode,
+    dispatch: impl FnOnce(&(dyn Fn(u32, u32) + Sync)),
+) {
+    let unsafe_dst = UnsafeSlice::new(dst);
+    let percentile = percentile.clamp(0f32, 100f32) as f64 / 100f64;
+    let spans = rank_window_spans(radius, window);
+    let spans = &spans;
+    dispatch(&|start_y, end_y| match channels {
+        FastBlurChannels::Channels3 => {
+            rank_filter_u8_segment::<3>(
+                src,
+                src_stride,
+                &unsafe_dst,
+                dst_stride,
+                width,
+                height,
+                radius,
+                spans,
+                percentile,
+                window,
+                edge_mode,
+                start_y,
+                end_y,
+            );
+        }
+        FastBlurChannels::Channels4 => {
+            rank_filter_u8_segment::<4>(
+                src,
+                src_stride,
+                &unsafe_dst,
+                dst_stride,
+                width,
+                height,
+                radius,
+                spans,
+                percentile,
+                window,
+                edge_mode,
+                start_y,
+                end_y,
+            );
+        }
+    });
+}
+
+/// Performs rank filter on the u16 image.
+///
+/// Writes value of the requested percentile in the window, 0 is the minimum, 50 is the median and 100 is the maximum.
+/// Full 16 bit column histograms are too large, so two-level kernel histogram is used for any window shape.
+/// O(R) complexity, there is no constant time path for u16.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length in elements, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length in elements, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - Radius of kernel
+/// * `percentile` - Percentile in range 0..=100
+/// * `window` - Window shape, see *RankWindow*
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, see *RankEdgeMode*, *KernelClip* ignores them and *Constant* treats them as zero
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn rank_filter_u16(
+    src: &[u16],
+    src_stride: u32,
+    dst: &mut [u16],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    percentile: f32,
+    window: RankWindow,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: RankEdgeMode,
+) {
+    let unsafe_dst = UnsafeSlice::new(dst);
+    let percentile = percentile.clamp(0f32, 100f32) as f64 / 100f64;
+    let spans = rank_window_spans(radius, window);
+    let spans = &spans;
+    rank_filter_dispatch(
+        width,
+        height,
+        threading_policy,
+        rank_filter_work(radius) * type_work_per_pixel::<u16>(),
+        |start_y, end_y| match channels {
+            FastBlurChannels::Channels3 => {
+                rank_filter_snake_impl::<u16, 3>(
+                    src,
+                    src_stride,
+                    &unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    spans,
+                    percentile,
+                    edge_mode,
+                    start_y,
+                    end_y,
+                );
+            }
+            FastBlurChannels::Channels4 => {
+                rank_filter_snake_impl::<u16, 4>(
+                    src,
+                    src_stride,
+                    &unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    spans,
+                    percentile,
+                    edge_mode,
+                    start_y,
+                    end_y,
+                );
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three channel rank filter evaluated by sorting every window
+    fn brute_force_rank(
+        src: &[u16],
+        width: usize,
+        height: usize,
+        radius: u32,
+        percentile: f32,
+        window: RankWindow,
+        edge_mode: RankEdgeMode,
+    ) -> Vec<u16> {
+        let spans = rank_window_spans(radius, window);
+        let percentile = percentile as f64 / 100f64;
+        let mut dst = vec![0u16; src.len()];
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                for c in 0..3 {
+                    let mut values = Vec::new();
+                    for (dy, &span) in (-(radius as i64)..).zip(spans.iter()) {
+                        for dx in -span..=span {
+                            match (
+                                border_position(y + dy, height, edge_mode.border()),
+                                border_position(x + dx, width, edge_mode.border()),
+                            ) {
+                                (Some(row), Some(column)) => {
+                                    values.push(src[(row * width + column) * 3 + c])
+                                }
+                                _ if edge_mode == RankEdgeMode::Constant => values.push(0),
+                                _ => {}
+                            }
+                        }
+                    }
+                    values.sort_unstable();
+                    let rank = ((values.len() - 1) as f64 * percentile).round() as usize;
+                    dst[(y as usize * width + x as usize) * 3 + c] = values[rank];
+                }
+            }
+        }
+        dst
+    }
+
+    #[test]
+    fn rank_filter_matches_brute_force() {
+        let width = 19usize;
+        let height = 14usize;
+        let src: Vec<u8> = (0..width * height * 3)
+            .map(|i| ((i * 7919 + i / 5) % 256) as u8)
+            .collect();
+        let src16: Vec<u16> = src.iter().map(|&v| v as u16 * 257).collect();
+        let stride = width as u32 * 3;
+        for edge_mode in [
+            RankEdgeMode::Clamp,
+            RankEdgeMode::KernelClip,
+            RankEdgeMode::Wrap,
+            RankEdgeMode::Reflect,
+            RankEdgeMode::Reflect101,
+            RankEdgeMode::Constant,
+        ] {
+            for window in [RankWindow::Square, RankWindow::Circle] {
+                for (radius, percentile) in [(1u32, 50f32), (2, 0f32), (3, 10f32), (4, 100f32)] {
+                    let reference = brute_force_rank(
+                        &src16, width, height, radius, percentile, window, edge_mode,
+                    );
+                    let mut dst16 = vec![0u16; src16.len()];
+                    rank_filter_u16(
+                        &src16,
+                        stride,
+                        &mut dst16,
+                        stride,
+                        width as u32,
+                        height as u32,
+                        radius,
+                        percentile,
+                        window,
+                        FastBlurChannels::Channels3,
+                        ThreadingPolicy::Single,
+                        edge_mode,
+                    );
+                    assert_eq!(
+                        dst16, reference,
+                        "u16 {:?} {:?} {} {}",
+                        edge_mode, window, radius, percentile
+                    );
+                    let mut dst = vec![0u8; src.len()];
+                    rank_filter(
+                        &src,
+                        stride,
+                        &mut dst,
+                        stride,
+                        width as u32,
+                        height as u32,
+                        radius,
+                        percentile,
+                        window,
+                        FastBlurChannels::Channels3,
+                        ThreadingPolicy::Single,
+                        edge_mode,
+                    );
+                    let reference: Vec<u8> = reference.iter().map(|&v| (v / 257) as u8).collect();
+                    assert_eq!(
+                        dst, reference,
+                        "u8 {:?} {:?} {} {}",
+                        edge_mode, window, radius, percentile
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn u16_rank_matches_brute_force_on_full_range() {
+        let width = 29usize;
+        let height = 17usize;
+        let src16: Vec<u16> = (0..width * height * 3)
+            .map(|i| {
+                let x = (i / 3) % width;
+                let y = i / 3 / width;
+                if (x / 5 + y / 3) & 1 == 0 {
+                    (x * 1500 + y * 700 + (i * 7919) % 1031) as u16
+                } else {
+                    ((i * 104729) % 65536) as u16
+                }
+            })
+            .collect();
+        let stride = width as u32 * 3;
+        for edge_mode in [
+            RankEdgeMode::Clamp,
+            RankEdgeMode::KernelClip,
+            RankEdgeMode::Wrap,
+            RankEdgeMode::Reflect101,
+            RankEdgeMode::Constant,
+        ] {
+            for window in [RankWindow::Square, RankWindow::Circle] {
+                for (radius, percentile, threading_policy) in [
+                    (0u32, 50f32, ThreadingPolicy::Single),
+                    (2, 25f32, ThreadingPolicy::Fixed(3)),
+                    (5, 50f32, ThreadingPolicy::Single),
+                    (7, 90f32, ThreadingPolicy::Fixed(2)),
+                ] {
+                    let reference = brute_force_rank(
+                        &src16, width, height, radius, percentile, window, edge_mode,
+                    );
+                    let mut dst16 = vec![0u16; src16.len()];
+                    rank_filter_u16(
+                        &src16,
+                        stride,
+                        &mut dst16,
+                        stride,
+                        width as u32,
+                        height as u32,
+                        radius,
+                        percentile,
+                        window,
+                        FastBlurChannels::Channels3,
+                        threading_policy,
+                        edge_mode,
+                    );
+                    assert_eq!(
+                        dst16, reference,
+                        "{:?} {:?} {} {}",
+                        edge_mode, window, radius, percentile
+                    );
+                }
+            }
+        }
+    }
+}
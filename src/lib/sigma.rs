@@ -0,0 +1,263 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+// Relation between gaussian sigma and radius of each approximation.
+//
+// Every approximation is a convolution of simple kernels, so radius is derived
+// from variance of the resulting kernel in one dimension:
+//
+// * box blur averages `2 * radius` samples, variance `((2r)^2 - 1) / 12`
+// * stack blur is a triangle `1..r+1..1`, variance `r * (r + 2) / 6`
+// * fast gaussian is a triangle `1..r..1`, variance `(r^2 - 1) / 6`
+// * fast gaussian next is three boxes of `r` samples, variance `(r^2 - 1) / 4`
+
+/// Computes radius for *fast_gaussian* that produces blur with the given gaussian sigma
+pub fn fast_gaussian_radius_for_sigma(sigma: f32) -> u32 {
+    let sigma = sigma.max(0f32) as f64;
+    let radius = (6f64 * sigma * sigma + 1f64).sqrt().round();
+    core::cmp::max(radius as u32, 1)
+}
+
+/// Computes radius for *fast_gaussian_next* that produces blur with the given gaussian sigma
+pub fn fast_gaussian_next_radius_for_sigma(sigma: f32) -> u32 {
+    let sigma = sigma.max(0f32) as f64;
+    let radius = (4f64 * sigma * sigma + 1f64).sqrt().round();
+    core::cmp::max(radius as u32, 1)
+}
+
+/// Computes radius for *stack_blur* that produces blur with the given gaussian sigma
+pub fn stack_blur_radius_for_sigma(sigma: f32) -> u32 {
+    let sigma = sigma.max(0f32) as f64;
+    let radius = ((6f64 * sigma * sigma + 1f64).sqrt() - 1f64).round();
+    core::cmp::max(radius as u32, 2)
+}
+
+/// Computes radius for *tent_blur* that produces blur with the given gaussian sigma
+pub fn tent_blur_radius_for_sigma(sigma: f32) -> u32 {
+    let sigma = sigma.max(0f32) as f64;
+    let radius = ((6f64 * sigma * sigma + 1f64) / 4f64).sqrt().round();
+    core::cmp::max(radius as u32, 1)
+}
+
+/// Computes radii of three box passes for *gaussian_box_blur* that produce blur with the given gaussian sigma.
+///
+/// Uses "extended box" derivation from P. Kovesi, Fast Almost-Gaussian Filtering, 2010:
+/// box widths are split between two neighbouring sizes so that total variance matches sigma.
+pub fn gaussian_box_blur_radii_for_sigma(sigma: f32) -> [u32; 3] {
+    const PASSES: f64 = 3f64;
+    let sigma = sigma.max(0f32) as f64;
+    let variance = 12f64 * sigma * sigma;
+    // Box width is always even here since box blur averages `2 * radius` samples
+    let ideal_width = (variance / PASSES + 1f64).sqrt();
+    let mut lower_width = ideal_width.floor() as i64;
+    if lower_width % 2 != 0 {
+        lower_width -= 1;
+    }
+    let lower_width = core::cmp::max(lower_width, 2);
+    let lw = lower_width as f64;
+    let lower_count = ((variance - PASSES * lw * lw - 4f64 * PASSES * lw - 3f64 * PASSES)
+        / (-4f64 * lw - 4f64))
+        .round()
+        .clamp(0f64, PASSES) as usize;
+    let lower_radius = (lower_width / 2) as u32;
+    let mut radii = [lower_radius + 1; 3];
+    for radius in radii.iter_mut().take(lower_count) {
+        *radius = lower_radius;
+    }
+    radii
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fast_gaussian_f32, fast_gaussian_next_f32, gaussian_box_blur_sigma_f32, stack_blur_f32,
+        tent_blur_f32, EdgeMode, FastBlurChannels, ThreadingPolicy,
+    };
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    const SIZE: u32 = 257;
+
+    /// Blurs unit impulse in the center and returns standard deviation of the response along x and y
+    fn impulse_sigma(blur: impl Fn(&mut [f32])) -> (f64, f64) {
+        let size = SIZE as usize;
+        let mut image = vec![0f32; size * size * 3];
+        image[(size / 2 * size + size / 2) * 3] = 1f32;
+        blur(&mut image);
+        let mut columns = vec![0f64; size];
+        let mut rows = vec![0f64; size];
+        for y in 0..size {
+            for x in 0..size {
+                let v = image[(y * size + x) * 3] as f64;
+                columns[x] += v;
+                rows[y] += v;
+            }
+        }
+        let std_dev = |marginal: &[f64]| {
+            let total: f64 = marginal.iter().sum();
+            let mean = marginal
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| i as f64 * v)
+                .sum::<f64>()
+                / total;
+            let variance = marginal
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| (i as f64 - mean) * (i as f64 - mean) * v)
+                .sum::<f64>()
+                / total;
+            variance.sqrt()
+        };
+        (std_dev(&columns), std_dev(&rows))
+    }
+
+    /// Radius is integer, so sigma of the blur may differ from requested within a step of radius
+    fn assert_sigma(name: &str, blur: impl Fn(&mut [f32], f32)) {
+        for sigma in [3.5f32, 6., 10., 16.] {
+            let (sigma_x, sigma_y) = impulse_sigma(|image| blur(image, sigma));
+            let tolerance = sigma as f64 * 0.1;
+            assert!(
+                (sigma_x - sigma as f64).abs() <= tolerance
+                    && (sigma_y - sigma as f64).abs() <= tolerance,
+                "{} with sigma {} has std dev {} along x and {} along y",
+                name,
+                sigma,
+                sigma_x,
+                sigma_y
+            );
+        }
+    }
+
+    fn copy_blur(image: &mut [f32], blur: impl Fn(&[f32], &mut [f32])) {
+        let mut dst: Vec<f32> = vec![0f32; image.len()];
+        blur(image, &mut dst);
+        image.copy_from_slice(&dst);
+    }
+
+    #[test]
+    fn fast_gaussian_radius_matches_sigma() {
+        assert_sigma("fast_gaussian", |image, sigma| {
+            fast_gaussian_f32(
+                image,
+                SIZE,
+                SIZE,
+                fast_gaussian_radius_for_sigma(sigma),
+                FastBlurChannels::Channels3,
+                ThreadingPolicy::Single,
+                EdgeMode::Clamp,
+            )
+        });
+    }
+
+    #[test]
+    fn fast_gaussian_next_radius_matches_sigma() {
+        assert_sigma("fast_gaussian_next", |image, sigma| {
+            fast_gaussian_next_f32(
+                image,
+                SIZE,
+                SIZE,
+                fast_gaussian_next_radius_for_sigma(sigma),
+                FastBlurChannels::Channels3,
+                ThreadingPolicy::Single,
+                EdgeMode::Clamp,
+            )
+        });
+    }
+
+    #[test]
+    fn stack_blur_radius_matches_sigma() {
+        assert_sigma("stack", |image, sigma| {
+            stack_blur_f32(
+                image,
+                SIZE,
+                SIZE,
+                stack_blur_radius_for_sigma(sigma),
+                FastBlurChannels::Channels3,
+                ThreadingPolicy::Single,
+            )
+        });
+    }
+
+    #[test]
+    fn tent_blur_radius_matches_sigma() {
+        assert_sigma("tent", |image, sigma| {
+            copy_blur(image, |src, dst| {
+                tent_blur_f32(
+                    src,
+                    dst,
+                    SIZE,
+                    SIZE,
+                    tent_blur_radius_for_sigma(sigma),
+                    FastBlurChannels::Channels3,
+                    ThreadingPolicy::Single,
+                )
+            })
+        });
+    }
+
+    #[test]
+    fn gaussian_box_blur_radii_match_sigma() {
+        assert_sigma("gaussian_box", |image, sigma| {
+            copy_blur(image, |src, dst| {
+                gaussian_box_blur_sigma_f32(
+                    src,
+                    dst,
+                    SIZE,
+                    SIZE,
+                    sigma,
+                    FastBlurChannels::Channels3,
+                    ThreadingPolicy::Single,
+                )
+            })
+        });
+    }
+
+    #[test]
+    fn gaussian_box_blur_radii_known_values() {
+        let expected = [
+            (0.5f32, [1u32, 1, 1]),
+            (1.2, [1, 1, 2]),
+            (2., [2, 2, 2]),
+            (2.7, [2, 3, 3]),
+            (3.2, [3, 3, 4]),
+            (7., [7, 7, 7]),
+        ];
+        for (sigma, radii) in expected {
+            assert_eq!(
+                gaussian_box_blur_radii_for_sigma(sigma),
+                radii,
+                "Radii for sigma {}",
+                sigma
+            );
+        }
+    }
+}
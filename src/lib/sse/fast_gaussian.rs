@@ -26,15 +26,14 @@
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::mul_table::{MUL_TABLE_DOUBLE, SHR_TABLE_DOUBLE};
-use crate::reflect_index;
 use crate::sse::utils::load_u8_s32_fast;
 use crate::sse::{_mm_mul_epi64, _mm_packus_epi64};
 use crate::unsafe_slice::UnsafeSlice;
 use crate::{clamp_edge, EdgeMode};
 #[cfg(target_arch = "x86")]
-use std::arch::x86::*;
+use core::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::*;
+use core::arch::x86_64::*;
 
 pub fn fast_gaussian_horizontal_pass_sse_u8<
     T,
@@ -42,6 +41,7 @@ pub fn fast_gaussian_horizontal_pass_sse_u8<
     const EDGE_MODE: usize,
 >(
     undefined_slice: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -50,7 +50,8 @@ pub fn fast_gaussian_horizontal_pass_sse_u8<
     end: u32,
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    let bytes: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_slice) };
+    let bytes: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_slice) };
+    let src: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_src) };
     let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
     let initial_sum = ((radius * radius) >> 1) as i32;
 
@@ -60,7 +61,7 @@ pub fn fast_gaussian_horizontal_pass_sse_u8<
     let shr_value = SHR_TABLE_DOUBLE[radius as usize];
     let v_mul_value = unsafe { _mm_set1_epi64x(mul_value as i64) };
     let v_shr_value = unsafe { _mm_setr_epi32(shr_value, 0, 0, 0) };
-    for y in start..std::cmp::min(height, end) {
+    for y in start..core::cmp::min(height, end) {
         let mut diffs = unsafe { _mm_set1_epi32(0) };
         let mut summs = unsafe { _mm_set1_epi32(initial_sum) };
 
@@ -69,7 +70,7 @@ pub fn fast_gaussian_horizontal_pass_sse_u8<
         let start_x = 0 - 2 * radius_64;
         for x in start_x..(width as i64) {
             if x >= 0 {
-                let current_px = ((std::cmp::max(x, 0) as u32) * CHANNELS_COUNT as u32) as usize;
+                let current_px = ((core::cmp::max(x, 0) as u32) * CHANNELS_COUNT as u32) as usize;
 
                 let hi_a = unsafe { _mm_unpackhi_epi32(summs, _mm_setzero_si128()) };
                 let lo_b = unsafe { _mm_unpacklo_epi32(summs, _mm_setzero_si128()) };
@@ -123,7 +124,7 @@ pub fn fast_gaussian_horizontal_pass_sse_u8<
             let next_row_x = clamp_edge!(edge_mode, x + radius_64, 0, width_wide - 1);
             let next_row_px = next_row_x * CHANNELS_COUNT;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_px) as *mut u8 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_px) as *mut u8 };
             let pixel_color = unsafe { load_u8_s32_fast::<CHANNELS_COUNT>(s_ptr) };
 
             let arr_index = ((x + radius_64) & 1023) as usize;
@@ -144,6 +145,7 @@ pub(crate) fn fast_gaussian_vertical_pass_sse_u8<
     const EDGE_MODE: usize,
 >(
     undefined_slice: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -152,7 +154,8 @@ pub(crate) fn fast_gaussian_vertical_pass_sse_u8<
     end: u32,
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    let bytes: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_slice) };
+    let bytes: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_slice) };
+    let src: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_src) };
     let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
     let initial_sum = ((radius * radius) >> 1) as i32;
 
@@ -163,7 +166,7 @@ pub(crate) fn fast_gaussian_vertical_pass_sse_u8<
     let shr_value = SHR_TABLE_DOUBLE[radius as usize];
     let v_mul_value = unsafe { _mm_set1_epi64x(mul_value as i64) };
     let v_shr_value = unsafe { _mm_setr_epi32(shr_value, 0, 0, 0) };
-    for x in start..std::cmp::min(width, end) {
+    for x in start..core::cmp::min(width, end) {
         let mut diffs = unsafe { _mm_set1_epi32(0) };
         let mut summs = unsafe { _mm_set1_epi32(initial_sum) };
 
@@ -172,7 +175,7 @@ pub(crate) fn fast_gaussian_vertical_pass_sse_u8<
             let current_y = (y * (stride as i64)) as usize;
 
             if y >= 0 {
-                let current_px = ((std::cmp::max(x, 0)) * CHANNELS_COUNT as u32) as usize;
+                let current_px = ((core::cmp::max(x, 0)) * CHANNELS_COUNT as u32) as usize;
 
                 let hi_a = unsafe { _mm_unpackhi_epi32(summs, _mm_setzero_si128()) };
                 let lo_b = unsafe { _mm_unpacklo_epi32(summs, _mm_setzero_si128()) };
@@ -226,7 +229,7 @@ pub(crate) fn fast_gaussian_vertical_pass_sse_u8<
                 clamp_edge!(edge_mode, y + radius_64, 0, height_wide - 1) * (stride as usize);
             let next_row_x = (x * CHANNELS_COUNT as u32) as usize;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_x) as *mut u8 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_x) as *mut u8 };
             let pixel_color = unsafe { load_u8_s32_fast::<CHANNELS_COUNT>(s_ptr) };
 
             let arr_index = ((y + radius_64) & 1023) as usize;
@@ -25,14 +25,13 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::reflect_index;
 use crate::sse::utils::load_u8_s32_fast;
 use crate::unsafe_slice::UnsafeSlice;
 use crate::{clamp_edge, EdgeMode};
 #[cfg(target_arch = "x86")]
-use std::arch::x86::*;
+use core::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::*;
+use core::arch::x86_64::*;
 
 pub fn fast_gaussian_next_vertical_pass_sse_u8<
     T,
@@ -40,6 +39,7 @@ pub fn fast_gaussian_next_vertical_pass_sse_u8<
     const EDGE_MODE: usize,
 >(
     undefined_slice: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -48,7 +48,8 @@ pub fn fast_gaussian_next_vertical_pass_sse_u8<
     end: u32,
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    let bytes: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_slice) };
+    let bytes: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_slice) };
+    let src: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_src) };
     let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
 
     let height_wide = height as i64;
@@ -58,7 +59,7 @@ pub fn fast_gaussian_next_vertical_pass_sse_u8<
     let radius_64 = radius as i64;
     let weight = 1.0f32 / ((radius as f32) * (radius as f32) * (radius as f32));
     let f_weight = unsafe { _mm_set1_ps(weight) };
-    for x in start..std::cmp::min(width, end) {
+    for x in start..core::cmp::min(width, end) {
         let mut diffs = unsafe { _mm_set1_epi32(0) };
         let mut ders = unsafe { _mm_set1_epi32(0) };
         let mut summs = unsafe { _mm_set1_epi32(0) };
@@ -68,7 +69,7 @@ pub fn fast_gaussian_next_vertical_pass_sse_u8<
             let current_y = (y * (stride as i64)) as usize;
 
             if y >= 0 {
-                let current_px = ((std::cmp::max(x, 0)) * CHANNELS_COUNT as u32) as usize;
+                let current_px = ((core::cmp::max(x, 0)) * CHANNELS_COUNT as u32) as usize;
                 const ROUNDING_FLAGS: i32 = _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC;
                 let prepared_px_s32 = unsafe {
                     _mm_cvtps_epi32(_mm_round_ps::<ROUNDING_FLAGS>(_mm_mul_ps(
@@ -142,7 +143,7 @@ pub fn fast_gaussian_next_vertical_pass_sse_u8<
                 * (stride as usize);
             let next_row_x = (x * CHANNELS_COUNT as u32) as usize;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_x) as *mut u8 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_x) as *mut u8 };
 
             let pixel_color = unsafe { load_u8_s32_fast::<CHANNELS_COUNT>(s_ptr) };
 
@@ -165,6 +166,7 @@ pub(crate) fn fast_gaussian_next_horizontal_pass_sse_u8<
     const EDGE_MODE: usize,
 >(
     undefined_slice: &UnsafeSlice<T>,
+    undefined_src: &UnsafeSlice<T>,
     stride: u32,
     width: u32,
     height: u32,
@@ -173,7 +175,8 @@ pub(crate) fn fast_gaussian_next_horizontal_pass_sse_u8<
     end: u32,
 ) {
     let edge_mode: EdgeMode = EDGE_MODE.into();
-    let bytes: &UnsafeSlice<'_, u8> = unsafe { std::mem::transmute(undefined_slice) };
+    let bytes: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_slice) };
+    let src: &UnsafeSlice<'_, u8> = unsafe { core::mem::transmute(undefined_src) };
     let mut buffer: [[i32; 4]; 1024] = [[0; 4]; 1024];
 
     let width_wide = width as i64;
@@ -183,7 +186,7 @@ pub(crate) fn fast_gaussian_next_horizontal_pass_sse_u8<
     let radius_64 = radius as i64;
     let weight = 1.0f32 / ((radius as f32) * (radius as f32) * (radius as f32));
     let f_weight = unsafe { _mm_set1_ps(weight) };
-    for y in start..std::cmp::min(height, end) {
+    for y in start..core::cmp::min(height, end) {
         let mut diffs = unsafe { _mm_set1_epi32(0) };
         let mut ders = unsafe { _mm_set1_epi32(0) };
         let mut summs = unsafe { _mm_set1_epi32(0) };
@@ -267,7 +270,7 @@ pub(crate) fn fast_gaussian_next_horizontal_pass_sse_u8<
             let next_row_x = clamp_edge!(edge_mode, x + 3 * radius_64 / 2, 0, width_wide - 1);
             let next_row_px = next_row_x * CHANNELS_COUNT;
 
-            let s_ptr = unsafe { bytes.slice.as_ptr().add(next_row_y + next_row_px) as *mut u8 };
+            let s_ptr = unsafe { src.slice.as_ptr().add(next_row_y + next_row_px) as *mut u8 };
 
             let pixel_color = unsafe { load_u8_s32_fast::<CHANNELS_COUNT>(s_ptr) };
 
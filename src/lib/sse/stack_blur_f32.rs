@@ -29,9 +29,9 @@ use crate::sse::{load_f32, store_f32};
 use crate::stack_blur::StackBlurPass;
 use crate::unsafe_slice::UnsafeSlice;
 #[cfg(target_arch = "x86")]
-use std::arch::x86::*;
+use core::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::*;
+use core::arch::x86_64::*;
 
 pub fn stack_blur_pass_sse_f<const COMPONENTS: usize>(
     pixels: &UnsafeSlice<f32>,
@@ -42,6 +42,7 @@ pub fn stack_blur_pass_sse_f<const COMPONENTS: usize>(
     pass: StackBlurPass,
     thread: usize,
     total_threads: usize,
+    stacks: &mut [f32],
 ) {
     unsafe {
         let div = ((radius * 2) + 1) as usize;
@@ -49,7 +50,7 @@ pub fn stack_blur_pass_sse_f<const COMPONENTS: usize>(
         let (mut xp, mut yp);
         let mut sp;
         let mut stack_start;
-        let mut stacks = vec![0f32; 4 * div * total_threads];
+        let stacks = &mut stacks[..4 * div];
 
         let mut sums: __m128;
         let mut sum_in: __m128;
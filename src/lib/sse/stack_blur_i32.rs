@@ -25,14 +25,15 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::blur_scratch::aligned_scratch;
 use crate::mul_table::{MUL_TABLE_STACK_BLUR, SHR_TABLE_STACK_BLUR};
 use crate::sse::utils::{_mm_mul_epi64, _mm_packus_epi64, load_u8_s32_fast, store_u8_s32};
 use crate::stack_blur::StackBlurPass;
 use crate::unsafe_slice::UnsafeSlice;
 #[cfg(target_arch = "x86")]
-use std::arch::x86::*;
+use core::arch::x86::*;
 #[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::*;
+use core::arch::x86_64::*;
 
 pub fn stack_blur_pass_sse<const COMPONENTS: usize>(
     pixels: &UnsafeSlice<u8>,
@@ -43,13 +44,12 @@ pub fn stack_blur_pass_sse<const COMPONENTS: usize>(
     pass: StackBlurPass,
     thread: usize,
     total_threads: usize,
+    stacks: &mut [u8],
 ) {
     unsafe {
-        let div = ((radius * 2) + 1) as usize;
         let (mut xp, mut yp);
         let mut sp;
         let mut stack_start;
-        let mut stacks = vec![0i32; 4 * div * total_threads];
 
         let mut sums: __m128i;
         let mut sum_in: __m128i;
@@ -58,6 +58,8 @@ pub fn stack_blur_pass_sse<const COMPONENTS: usize>(
         let wm = width - 1;
         let hm = height - 1;
         let div = (radius * 2) + 1;
+        // Stacks are placed into the scratch at the first position aligned for the accumulator
+        let stacks: &mut [i32] = aligned_scratch(stacks, 4 * div as usize);
         let mul_sum = _mm_set1_epi64x(MUL_TABLE_STACK_BLUR[radius as usize] as i64);
         let shr_sum = _mm_setr_epi32(SHR_TABLE_STACK_BLUR[radius as usize], 0i32, 0i32, 0i32);
 
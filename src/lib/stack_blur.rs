@@ -25,22 +25,32 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::blur_scratch::aligned_scratch;
 use crate::mul_table::{MUL_TABLE_STACK_BLUR, SHR_TABLE_STACK_BLUR};
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::neon::{stack_blur_pass_neon_i32, stack_blur_pass_neon_i64};
+use crate::sigma::stack_blur_radius_for_sigma;
 #[cfg(all(
     any(target_arch = "x86_64", target_arch = "x86"),
     target_feature = "sse4.1"
 ))]
 use crate::sse::{stack_blur_pass_sse, stack_blur_pass_sse_i64};
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
 use crate::unsafe_slice::UnsafeSlice;
+use crate::BlurAlgorithm;
 use crate::{FastBlurChannels, ThreadingPolicy};
+use alloc::vec;
+use core::mem::{align_of, size_of};
+use core::ops::AddAssign;
 use num_traits::{AsPrimitive, FromPrimitive};
-use std::ops::AddAssign;
 
 const BASE_RADIUS_I64_CUTOFF: u32 = 150;
 
-#[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq)]
+type StackBlurWorkerPass =
+    fn(&UnsafeSlice<u8>, u32, u32, u32, u32, StackBlurPass, usize, usize, &mut [u8]);
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub(crate) struct BlurStack<J: Copy + FromPrimitive> {
     pub r: J,
     pub g: J,
@@ -48,20 +58,6 @@ pub(crate) struct BlurStack<J: Copy + FromPrimitive> {
     pub a: J,
 }
 
-impl<J> BlurStack<J>
-where
-    J: Copy + FromPrimitive,
-{
-    pub fn new() -> BlurStack<J> {
-        BlurStack {
-            r: J::from_i32(0i32).unwrap(),
-            g: J::from_i32(0i32).unwrap(),
-            b: J::from_i32(0i32).unwrap(),
-            a: J::from_i32(0i32).unwrap(),
-        }
-    }
-}
-
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 pub(crate) enum StackBlurPass {
     HORIZONTAL,
@@ -83,22 +79,23 @@ fn stack_blur_pass<T, J, I, const COMPONENTS: usize>(
     pass: StackBlurPass,
     thread: usize,
     total_threads: usize,
+    stacks: &mut [u8],
 ) where
     J: Copy
         + 'static
         + FromPrimitive
         + AddAssign<J>
-        + std::ops::Mul<Output = J>
-        + std::ops::Shr<Output = J>
-        + std::ops::SubAssign
+        + core::ops::Mul<Output = J>
+        + core::ops::Shr<Output = J>
+        + core::ops::SubAssign
         + AsPrimitive<T>
         + AsPrimitive<I>,
     T: Copy + AsPrimitive<J> + FromPrimitive,
     I: Copy
         + AsPrimitive<T>
         + FromPrimitive
-        + std::ops::Mul<Output = I>
-        + std::ops::Shr<Output = I>,
+        + core::ops::Mul<Output = I>
+        + core::ops::Shr<Output = I>,
     i32: AsPrimitive<J>,
     u32: AsPrimitive<J>,
 {
@@ -106,10 +103,8 @@ fn stack_blur_pass<T, J, I, const COMPONENTS: usize>(
     let (mut xp, mut yp);
     let mut sp;
     let mut stack_start;
-    let mut stacks = vec![];
-    for _ in 0..div {
-        stacks.push(Box::new(BlurStack::new()));
-    }
+    // Stacks are placed into the scratch at the first position aligned for the accumulator
+    let stacks: &mut [BlurStack<J>] = unsafe { aligned_scratch(stacks, div) };
 
     let mut sum_r: J;
     let mut sum_g: J;
@@ -524,19 +519,11 @@ fn stack_blur_worker_horizontal(
     channels: FastBlurChannels,
     thread: usize,
     thread_count: usize,
+    stacks: &mut [u8],
 ) {
     match channels {
         FastBlurChannels::Channels3 => {
-            let mut _dispatcher: fn(
-                &UnsafeSlice<u8>,
-                u32,
-                u32,
-                u32,
-                u32,
-                StackBlurPass,
-                usize,
-                usize,
-            ) = if radius < BASE_RADIUS_I64_CUTOFF {
+            let mut _dispatcher: StackBlurWorkerPass = if radius < BASE_RADIUS_I64_CUTOFF {
                 stack_blur_pass::<u8, i64, i64, 3>
             } else {
                 stack_blur_pass::<u8, i32, i64, 3>
@@ -575,19 +562,11 @@ fn stack_blur_worker_horizontal(
                 StackBlurPass::HORIZONTAL,
                 thread,
                 thread_count,
+                stacks,
             );
         }
         FastBlurChannels::Channels4 => {
-            let mut _dispatcher: fn(
-                &UnsafeSlice<u8>,
-                u32,
-                u32,
-                u32,
-                u32,
-                StackBlurPass,
-                usize,
-                usize,
-            ) = if radius < BASE_RADIUS_I64_CUTOFF {
+            let mut _dispatcher: StackBlurWorkerPass = if radius < BASE_RADIUS_I64_CUTOFF {
                 stack_blur_pass::<u8, i64, i64, 4>
             } else {
                 stack_blur_pass::<u8, i32, i64, 4>
@@ -626,6 +605,7 @@ fn stack_blur_worker_horizontal(
                 StackBlurPass::HORIZONTAL,
                 thread,
                 thread_count,
+                stacks,
             );
         }
     }
@@ -640,19 +620,11 @@ fn stack_blur_worker_vertical(
     channels: FastBlurChannels,
     thread: usize,
     thread_count: usize,
+    stacks: &mut [u8],
 ) {
     match channels {
         FastBlurChannels::Channels3 => {
-            let mut _dispatcher: fn(
-                &UnsafeSlice<u8>,
-                u32,
-                u32,
-                u32,
-                u32,
-                StackBlurPass,
-                usize,
-                usize,
-            ) = if radius < BASE_RADIUS_I64_CUTOFF {
+            let mut _dispatcher: StackBlurWorkerPass = if radius < BASE_RADIUS_I64_CUTOFF {
                 stack_blur_pass::<u8, i64, i64, 3>
             } else {
                 stack_blur_pass::<u8, i32, i64, 3>
@@ -691,19 +663,11 @@ fn stack_blur_worker_vertical(
                 StackBlurPass::VERTICAL,
                 thread,
                 thread_count,
+                stacks,
             );
         }
         FastBlurChannels::Channels4 => {
-            let mut _dispatcher: fn(
-                &UnsafeSlice<u8>,
-                u32,
-                u32,
-                u32,
-                u32,
-                StackBlurPass,
-                usize,
-                usize,
-            ) = if radius < BASE_RADIUS_I64_CUTOFF {
+            let mut _dispatcher: StackBlurWorkerPass = if radius < BASE_RADIUS_I64_CUTOFF {
                 stack_blur_pass::<u8, i64, i64, 4>
             } else {
                 stack_blur_pass::<u8, i32, i64, 4>
@@ -742,6 +706,7 @@ fn stack_blur_worker_vertical(
                 StackBlurPass::VERTICAL,
                 thread,
                 thread_count,
+                stacks,
             );
         }
     }
@@ -773,21 +738,159 @@ pub fn stack_blur(
     channels: FastBlurChannels,
     threading_policy: ThreadingPolicy,
 ) {
-    let radius = std::cmp::max(std::cmp::min(254, radius), 2);
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
+    let radius = core::cmp::max(core::cmp::min(254, radius), 2);
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Stack.work_per_pixel(radius) * type_work_per_pixel::<u8>(),
+    ) as u32;
+    let mut stacks = vec![0u8; thread_count as usize * stack_blur_stacks_size(radius)];
     if thread_count == 1 {
         let slice = UnsafeSlice::new(in_place);
-        stack_blur_worker_horizontal(&slice, stride, width, height, radius, channels, 0, 1);
-        stack_blur_worker_vertical(&slice, stride, width, height, radius, channels, 0, 1);
+        stack_blur_worker_horizontal(
+            &slice,
+            stride,
+            width,
+            height,
+            radius,
+            channels,
+            0,
+            1,
+            &mut stacks,
+        );
+        stack_blur_worker_vertical(
+            &slice,
+            stride,
+            width,
+            height,
+            radius,
+            channels,
+            0,
+            1,
+            &mut stacks,
+        );
         return;
     }
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+    let pool = threading_policy.create_pool(thread_count as usize);
+    stack_blur_in_pool(
+        in_place,
+        stride,
+        width,
+        height,
+        radius,
+        channels,
+        &mut stacks,
+        &pool,
+        thread_count,
+    );
+}
+
+/// Returns count of bytes in the stacks of one thread for the radius
+pub(crate) fn stack_blur_stacks_size(radius: u32) -> usize {
+    let div = 2 * radius.clamp(2, 254) as usize + 1;
+    // i64 accumulators and bytes to reach their alignment
+    4 * div * size_of::<i64>() + align_of::<i64>() - 1
+}
+
+/// Returns count of bytes in the scratch required by *stack_blur_with_scratch*
+pub fn stack_blur_scratch_size(
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+) -> usize {
+    let radius = radius.clamp(2, 254);
+    threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Stack.work_per_pixel(radius) * type_work_per_pixel::<u8>(),
+    ) * stack_blur_stacks_size(radius)
+}
+
+/// Fastest available blur option with stacks taken from `scratch`
+///
+/// Same as *stack_blur* but nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single*. Other policies still allocate to schedule the threads,
+/// threads count is limited by the count of stacks fitting into the scratch.
+///
+/// # Arguments
+/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
+/// * `stride` - Bytes per lane, default is width * channels_count if not aligned
+/// * `width` - image width
+/// * `height` - image height
+/// * `radius` - radius is limited into 2..254
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+/// * `scratch` - Buffer of at least *stack_blur_scratch_size* bytes, its content is overwritten
+///
+/// # Panics
+/// Panic if scratch does not fit stacks of one thread
+///
+/// # Complexity
+/// O(1) complexity.
+#[allow(clippy::too_many_arguments)]
+pub fn stack_blur_with_scratch(
+    in_place: &mut [u8],
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [u8],
+) {
+    let radius = radius.clamp(2, 254);
+    let stacks_size = stack_blur_stacks_size(radius);
+    if scratch.len() < stacks_size {
+        panic!(
+            "Scratch has {} bytes but {} is required",
+            scratch.len(),
+            stacks_size
+        );
+    }
+    let thread_count = threading_policy
+        .get_threads_count_for_work(
+            width,
+            height,
+            BlurAlgorithm::Stack.work_per_pixel(radius) * type_work_per_pixel::<u8>(),
+        )
+        .min(scratch.len() / stacks_size) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    stack_blur_in_pool(
+        in_place,
+        stride,
+        width,
+        height,
+        radius,
+        channels,
+        scratch,
+        &pool,
+        thread_count,
+    );
+}
+
+/// Performs stack blur passes in the thread pool, radius must be already limited into 2..254,
+/// stacks must have *stack_blur_stacks_size* bytes for each thread
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn stack_blur_in_pool(
+    in_place: &mut [u8],
+    stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    stacks: &mut [u8],
+    pool: &BlurPool,
+    thread_count: u32,
+) {
+    let stacks_size = stack_blur_stacks_size(radius);
     pool.scope(|scope| {
         let slice = UnsafeSlice::new(in_place);
-        for i in 0..thread_count {
+        for (i, stacks) in stacks
+            .chunks_exact_mut(stacks_size)
+            .take(thread_count as usize)
+            .enumerate()
+        {
             scope.spawn(move |_| {
                 stack_blur_worker_horizontal(
                     &slice,
@@ -796,15 +899,20 @@ pub fn stack_blur(
                     height,
                     radius,
                     channels,
-                    i as usize,
+                    i,
                     thread_count as usize,
+                    stacks,
                 );
             });
         }
     });
     pool.scope(|scope| {
         let slice = UnsafeSlice::new(in_place);
-        for i in 0..thread_count {
+        for (i, stacks) in stacks
+            .chunks_exact_mut(stacks_size)
+            .take(thread_count as usize)
+            .enumerate()
+        {
             scope.spawn(move |_| {
                 stack_blur_worker_vertical(
                     &slice,
@@ -813,10 +921,48 @@ pub fn stack_blur(
                     height,
                     radius,
                     channels,
-                    i as usize,
+                    i,
                     thread_count as usize,
+                    stacks,
                 );
             });
         }
     })
 }
+
+/// Fastest available blur option with radius derived from gaussian sigma
+///
+/// Same as *stack_blur* however blur strength is set in terms of gaussian sigma,
+/// so switching between approximations keeps the same visual strength.
+/// Radius derived from sigma is limited into 2..254
+///
+/// # Arguments
+/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
+/// * `stride` - Bytes per lane, default is width * channels_count if not aligned
+/// * `width` - image width
+/// * `height` - image height
+/// * `sigma` - gaussian sigma, radius is computed with *stack_blur_radius_for_sigma*
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_sigma(
+    in_place: &mut [u8],
+    stride: u32,
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    stack_blur(
+        in_place,
+        stride,
+        width,
+        height,
+        stack_blur_radius_for_sigma(sigma),
+        channels,
+        threading_policy,
+    );
+}
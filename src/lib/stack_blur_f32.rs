@@ -27,18 +27,27 @@
 
 const BASE_RADIUS_F64_CUTOFF: u32 = 327;
 
+use crate::blur_scratch::aligned_scratch;
 #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
 use crate::neon::stack_blur_pass_neon_f32;
+use crate::sigma::stack_blur_radius_for_sigma;
 #[cfg(all(
     any(target_arch = "x86_64", target_arch = "x86"),
     target_feature = "sse4.1"
 ))]
 use crate::sse::stack_blur_pass_sse_f;
 use crate::stack_blur::{BlurStack, StackBlurPass};
+use crate::threading_policy::{type_work_per_pixel, BlurPool};
 use crate::unsafe_slice::UnsafeSlice;
+use crate::BlurAlgorithm;
 use crate::{FastBlurChannels, ThreadingPolicy};
+use alloc::vec;
+use core::ops::AddAssign;
 use num_traits::{AsPrimitive, FromPrimitive};
-use std::ops::AddAssign;
+
+/// Pass of stack blur over the rows or columns of the thread with the stacks of the thread
+type StackBlurF32Pass =
+    fn(&UnsafeSlice<f32>, u32, u32, u32, u32, StackBlurPass, usize, usize, &mut [f32]);
 
 fn stack_blur_pass_f<T, J, const COMPONENTS: usize>(
     pixels: &UnsafeSlice<T>,
@@ -49,15 +58,16 @@ fn stack_blur_pass_f<T, J, const COMPONENTS: usize>(
     pass: StackBlurPass,
     thread: usize,
     total_threads: usize,
+    stacks: &mut [f32],
 ) where
     T: Copy + Into<J> + FromPrimitive + AsPrimitive<J>,
     J: Copy
         + FromPrimitive
         + AddAssign<J>
-        + std::ops::Mul<Output = J>
-        + std::ops::Div<Output = J>
+        + core::ops::Mul<Output = J>
+        + core::ops::Div<Output = J>
         + AsPrimitive<T>
-        + std::ops::SubAssign,
+        + core::ops::SubAssign,
     f32: AsPrimitive<J>,
     i32: AsPrimitive<J>,
     u32: AsPrimitive<J>,
@@ -67,10 +77,8 @@ fn stack_blur_pass_f<T, J, const COMPONENTS: usize>(
     let (mut xp, mut yp);
     let mut sp;
     let mut stack_start;
-    let mut stacks = vec![];
-    for _ in 0..div {
-        stacks.push(Box::new(BlurStack::new()));
-    }
+    // Stacks are placed into the scratch at the first position aligned for the accumulator
+    let stacks: &mut [BlurStack<J>] = unsafe { aligned_scratch(stacks, div) };
 
     let mut sum_r: J;
     let mut sum_g: J;
@@ -474,19 +482,11 @@ fn stack_blur_worker_horizontal(
     channels: FastBlurChannels,
     thread: usize,
     thread_count: usize,
+    stacks: &mut [f32],
 ) {
     match channels {
         FastBlurChannels::Channels3 => {
-            let mut _dispatcher: fn(
-                &UnsafeSlice<f32>,
-                u32,
-                u32,
-                u32,
-                u32,
-                StackBlurPass,
-                usize,
-                usize,
-            ) = if radius < BASE_RADIUS_F64_CUTOFF {
+            let mut _dispatcher: StackBlurF32Pass = if radius < BASE_RADIUS_F64_CUTOFF {
                 stack_blur_pass_f::<f32, f32, 3>
             } else {
                 stack_blur_pass_f::<f32, f64, 3>
@@ -515,19 +515,11 @@ fn stack_blur_worker_horizontal(
                 StackBlurPass::HORIZONTAL,
                 thread,
                 thread_count,
+                stacks,
             );
         }
         FastBlurChannels::Channels4 => {
-            let mut _dispatcher: fn(
-                &UnsafeSlice<f32>,
-                u32,
-                u32,
-                u32,
-                u32,
-                StackBlurPass,
-                usize,
-                usize,
-            ) = if radius < BASE_RADIUS_F64_CUTOFF {
+            let mut _dispatcher: StackBlurF32Pass = if radius < BASE_RADIUS_F64_CUTOFF {
                 stack_blur_pass_f::<f32, f32, 4>
             } else {
                 stack_blur_pass_f::<f32, f64, 4>
@@ -556,6 +548,7 @@ fn stack_blur_worker_horizontal(
                 StackBlurPass::HORIZONTAL,
                 thread,
                 thread_count,
+                stacks,
             );
         }
     }
@@ -570,19 +563,11 @@ fn stack_blur_worker_vertical(
     channels: FastBlurChannels,
     thread: usize,
     thread_count: usize,
+    stacks: &mut [f32],
 ) {
     match channels {
         FastBlurChannels::Channels3 => {
-            let mut _dispatcher: fn(
-                &UnsafeSlice<f32>,
-                u32,
-                u32,
-                u32,
-                u32,
-                StackBlurPass,
-                usize,
-                usize,
-            ) = if radius < BASE_RADIUS_F64_CUTOFF {
+            let mut _dispatcher: StackBlurF32Pass = if radius < BASE_RADIUS_F64_CUTOFF {
                 stack_blur_pass_f::<f32, f32, 3>
             } else {
                 stack_blur_pass_f::<f32, f64, 3>
@@ -611,19 +596,11 @@ fn stack_blur_worker_vertical(
                 StackBlurPass::VERTICAL,
                 thread,
                 thread_count,
+                stacks,
             );
         }
         FastBlurChannels::Channels4 => {
-            let mut _dispatcher: fn(
-                &UnsafeSlice<f32>,
-                u32,
-                u32,
-                u32,
-                u32,
-                StackBlurPass,
-                usize,
-                usize,
-            ) = if radius < BASE_RADIUS_F64_CUTOFF {
+            let mut _dispatcher: StackBlurF32Pass = if radius < BASE_RADIUS_F64_CUTOFF {
                 stack_blur_pass_f::<f32, f32, 4>
             } else {
                 stack_blur_pass_f::<f32, f64, 4>
@@ -652,6 +629,7 @@ fn stack_blur_worker_vertical(
                 StackBlurPass::VERTICAL,
                 thread,
                 thread_count,
+                stacks,
             );
         }
     }
@@ -681,22 +659,129 @@ pub fn stack_blur_f32(
     channels: FastBlurChannels,
     threading_policy: ThreadingPolicy,
 ) {
-    let stride = width * channels.get_channels() as u32;
-    let radius = std::cmp::max(radius, 2);
-    let thread_count = threading_policy.get_threads_count(width, height) as u32;
-    if thread_count == 1 {
-        let slice = UnsafeSlice::new(in_place);
-        stack_blur_worker_horizontal(&slice, stride, width, height, radius, channels, 0, 1);
-        stack_blur_worker_vertical(&slice, stride, width, height, radius, channels, 0, 1);
-        return;
+    let thread_count = threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Stack.work_per_pixel(radius) * type_work_per_pixel::<f32>(),
+    ) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    let mut stacks = vec![0f32; thread_count as usize * stack_blur_f32_stacks_size(radius)];
+    stack_blur_f32_in_pool(
+        in_place,
+        width,
+        height,
+        radius,
+        channels,
+        &mut stacks,
+        &pool,
+        thread_count,
+    );
+}
+
+/// Returns count of f32 in the stacks of one thread for the radius
+pub(crate) fn stack_blur_f32_stacks_size(radius: u32) -> usize {
+    let div = 2 * core::cmp::max(radius, 2) as usize + 1;
+    if radius < BASE_RADIUS_F64_CUTOFF {
+        4 * div
+    } else {
+        // f64 accumulator and one f32 to reach its alignment
+        8 * div + 1
     }
-    let pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(thread_count as usize)
-        .build()
-        .unwrap();
+}
+
+/// Returns count of f32 in the scratch required by *stack_blur_f32_with_scratch*
+pub fn stack_blur_f32_scratch_size(
+    width: u32,
+    height: u32,
+    radius: u32,
+    threading_policy: ThreadingPolicy,
+) -> usize {
+    threading_policy.get_threads_count_for_work(
+        width,
+        height,
+        BlurAlgorithm::Stack.work_per_pixel(radius) * type_work_per_pixel::<f32>(),
+    ) * stack_blur_f32_stacks_size(radius)
+}
+
+/// Fastest available blur option in f32 with stacks taken from `scratch`
+///
+/// Same as *stack_blur_f32* but nothing is allocated when the work runs on the calling thread,
+/// as with *ThreadingPolicy::Single*. Other policies still allocate to schedule the threads,
+/// threads count is limited by the count of stacks fitting into the scratch.
+///
+/// # Arguments
+/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
+/// * `width` - image width
+/// * `height` - image height
+/// * `radius` - radius almost is not limited for f32 implementation
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+/// * `scratch` - Buffer of at least *stack_blur_f32_scratch_size* f32, its content is overwritten
+///
+/// # Panics
+/// Panic if scratch does not fit stacks of one thread
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_f32_with_scratch(
+    in_place: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    scratch: &mut [f32],
+) {
+    let stacks_size = stack_blur_f32_stacks_size(radius);
+    if scratch.len() < stacks_size {
+        panic!(
+            "Scratch has {} f32 but {} is required",
+            scratch.len(),
+            stacks_size
+        );
+    }
+    let thread_count = threading_policy
+        .get_threads_count_for_work(
+            width,
+            height,
+            BlurAlgorithm::Stack.work_per_pixel(radius) * type_work_per_pixel::<f32>(),
+        )
+        .min(scratch.len() / stacks_size) as u32;
+    let pool = threading_policy.create_pool(thread_count as usize);
+    stack_blur_f32_in_pool(
+        in_place,
+        width,
+        height,
+        radius,
+        channels,
+        scratch,
+        &pool,
+        thread_count,
+    );
+}
+
+/// Performs *stack_blur_f32* in the thread pool,
+/// stacks must have *stack_blur_f32_stacks_size* f32 for each thread
+pub(crate) fn stack_blur_f32_in_pool(
+    in_place: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    stacks: &mut [f32],
+    pool: &BlurPool,
+    thread_count: u32,
+) {
+    let stride = width * channels.get_channels() as u32;
+    let stacks_size = stack_blur_f32_stacks_size(radius);
+    let radius = core::cmp::max(radius, 2);
     pool.scope(|scope| {
         let slice = UnsafeSlice::new(in_place);
-        for i in 0..thread_count {
+        for (i, stacks) in stacks
+            .chunks_exact_mut(stacks_size)
+            .take(thread_count as usize)
+            .enumerate()
+        {
             scope.spawn(move |_| {
                 stack_blur_worker_horizontal(
                     &slice,
@@ -705,15 +790,20 @@ pub fn stack_blur_f32(
                     height,
                     radius,
                     channels,
-                    i as usize,
+                    i,
                     thread_count as usize,
+                    stacks,
                 );
             });
         }
     });
     pool.scope(|scope| {
         let slice = UnsafeSlice::new(in_place);
-        for i in 0..thread_count {
+        for (i, stacks) in stacks
+            .chunks_exact_mut(stacks_size)
+            .take(thread_count as usize)
+            .enumerate()
+        {
             scope.spawn(move |_| {
                 stack_blur_worker_vertical(
                     &slice,
@@ -722,10 +812,44 @@ pub fn stack_blur_f32(
                     height,
                     radius,
                     channels,
-                    i as usize,
+                    i,
                     thread_count as usize,
+                    stacks,
                 );
             });
         }
     })
 }
+
+/// Fastest available blur option in f32 with radius derived from gaussian sigma
+///
+/// Same as *stack_blur_f32* however blur strength is set in terms of gaussian sigma,
+/// so switching between approximations keeps the same visual strength.
+///
+/// # Arguments
+/// * `in_place` - mutable buffer contains image data that will be used as a source and destination
+/// * `width` - image width
+/// * `height` - image height
+/// * `sigma` - gaussian sigma, radius is computed with *stack_blur_radius_for_sigma*
+/// * `channels` - Count of channels of the image, only 3 and 4 is supported, alpha position, and channels order does not matter
+/// * `threading_policy` - Threads usage policy
+///
+/// # Complexity
+/// O(1) complexity.
+pub fn stack_blur_sigma_f32(
+    in_place: &mut [f32],
+    width: u32,
+    height: u32,
+    sigma: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+) {
+    stack_blur_f32(
+        in_place,
+        width,
+        height,
+        stack_blur_radius_for_sigma(sigma),
+        channels,
+        threading_policy,
+    );
+}
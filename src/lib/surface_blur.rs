@@ -0,0 +1,588 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::rank_filter::{
+    rank_filter_dispatch, rank_window_spans, window_snake_traversal, RankWindow, WindowHistogram,
+};
+use crate::threading_policy::type_work_per_pixel;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{EdgeMode, ThreadingPolicy};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+pub(crate) trait SurfaceValue: Copy + Send + Sync + 'static {
+    /// Count of histogram bins
+    const BINS: usize;
+    /// Value of the last histogram bin
+    const RANGE: f32;
+    /// Each bin holds exactly one value, so sums are not needed
+    const EXACT: bool;
+    fn bin(self) -> usize;
+    fn to_f32(self) -> f32;
+    fn from_f64(value: f64) -> Self;
+}
+
+impl SurfaceValue for u8 {
+    const BINS: usize = 256;
+    const RANGE: f32 = 255f32;
+    const EXACT: bool = true;
+
+    #[inline(always)]
+    fn bin(self) -> usize {
+        self as usize
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline(always)]
+    fn from_f64(value: f64) -> Self {
+        value.round().clamp(0f64, 255f64) as u8
+    }
+}
+
+impl SurfaceValue for f32 {
+    const BINS: usize = 1024;
+    const RANGE: f32 = 1f32;
+    const EXACT: bool = false;
+
+    #[inline(always)]
+    fn bin(self) -> usize {
+        surface_bin::<f32>(self)
+    }
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline(always)]
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+#[inline(always)]
+fn surface_bin<T: SurfaceValue>(value: f32) -> usize {
+    let scale = (T::BINS - 1) as f32 / T::RANGE;
+    (value * scale).round().clamp(0f32, (T::BINS - 1) as f32) as usize
+}
+
+/// Histogram of the window holding count and sum of the values for each bin,
+/// mean of the bin is exact for u8 and close to the values for f32
+struct SurfaceHistogram<T: SurfaceValue, const CHANNELS_CONFIGURATION: usize> {
+    count: Vec<u32>,
+    sum: Vec<f64>,
+    /// Weights by distance in bins for exact histograms
+    weights: Vec<f32>,
+    /// Count of bins with positive weight on each side of the center
+    reach_bins: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: SurfaceValue, const CHANNELS_CONFIGURATION: usize>
+    SurfaceHistogram<T, CHANNELS_CONFIGURATION>
+{
+    fn new(threshold: f32) -> Self {
+        let reach = 2.5f32 * threshold;
+        let bin_scale = T::RANGE / (T::BINS - 1) as f32;
+        let weights = if T::EXACT {
+            (0..T::BINS)
+                .map(|distance| (1f32 - distance as f32 * bin_scale / reach).max(0f32))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let reach_bins = weights
+            .iter()
+            .position(|&weight| weight == 0f32)
+            .unwrap_or(weights.len());
+        SurfaceHistogram {
+            count: vec![0u32; T::BINS * CHANNELS_CONFIGURATION],
+            sum: vec![0f64; T::BINS * CHANNELS_CONFIGURATION],
+            weights,
+            reach_bins,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Weighted mean of the values with weights `1 - |value - center| / (2.5 * threshold)`
+    fn filter(&self, channel: usize, center: T, threshold: f32) -> f64 {
+        let reach = 2.5f32 * threshold;
+        let offset = channel * T::BINS;
+        let center_value = center.to_f32();
+        let mut numerator = 0f64;
+        let mut denominator = 0f64;
+        if T::EXACT {
+            let center_bin = center.bin();
+            let start = center_bin.saturating_sub(self.reach_bins);
+            let end = core::cmp::min(center_bin + self.reach_bins, T::BINS);
+            let counts = &self.count[offset + start..offset + end];
+            let bin_scale = T::RANGE / (T::BINS - 1) as f32;
+            let mut numerator_bins = 0f32;
+            let mut denominator_bins = 0f32;
+            for (bin, &count) in (start..end).zip(counts.iter()) {
+                if count == 0 {
+                    continue;
+                }
+                let weighted = self.weights[bin.abs_diff(center_bin)] * count as f32;
+                numerator_bins += weighted * bin as f32;
+                denominator_bins += weighted;
+            }
+            numerator = numerator_bins as f64 * bin_scale as f64;
+            denominator = denominator_bins as f64;
+        } else {
+            let start = surface_bin::<T>(center_value - reach);
+            let end = surface_bin::<T>(center_value + reach);
+            let counts = &self.count[offset + start..=offset + end];
+            let sums = &self.sum[offset + start..=offset + end];
+            let center = center_value as f64;
+            let reach = reach as f64;
+            for (&count, &sum) in counts.iter().zip(sums.iter()) {
+                if count == 0 {
+                    continue;
+                }
+                let mean = sum / count as f64;
+                let weight = 1f64 - (mean - center).abs() / reach;
+                if weight > 0f64 {
+                    numerator += weight * sum;
+                    denominator += weight * count as f64;
+                }
+            }
+        }
+        if denominator == 0f64 {
+            center_value as f64
+        } else {
+            numerator / denominator
+        }
+    }
+}
+
+impl<T: SurfaceValue, const CHANNELS_CONFIGURATION: usize> WindowHistogram<T>
+    for SurfaceHistogram<T, CHANNELS_CONFIGURATION>
+{
+    #[inline(always)]
+    fn add(&mut self, pixel: &[T]) {
+        for (c, &v) in pixel.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+            let value = v.to_f32();
+            let bin = c * T::BINS + v.bin();
+            unsafe {
+                *self.count.get_unchecked_mut(bin) += 1;
+                if !T::EXACT {
+                    *self.sum.get_unchecked_mut(bin) += value as f64;
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn remove(&mut self, pixel: &[T]) {
+        for (c, &v) in pixel.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+            let value = v.to_f32();
+            let bin = c * T::BINS + v.bin();
+            unsafe {
+                *self.count.get_unchecked_mut(bin) -= 1;
+                if !T::EXACT {
+                    *self.sum.get_unchecked_mut(bin) -= value as f64;
+                }
+            }
+        }
+    }
+}
+
+/// Surface blur, averages only neighbors which are close to the center by intensity.
+///
+/// Window histogram is moved in a snake order, so window update costs O(R)
+/// and evaluation costs O(threshold) per pixel
+fn surface_blur_impl<T: SurfaceValue, const CHANNELS_CONFIGURATION: usize>(
+    src: &[T],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<T>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    spans: &[i64],
+    threshold: f32,
+    edge_mode: EdgeMode,
+    start_y: u32,
+    end_y: u32,
+) {
+    let mut histogram = SurfaceHistogram::<T, CHANNELS_CONFIGURATION>::new(threshold);
+    window_snake_traversal::<T, SurfaceHistogram<T, CHANNELS_CONFIGURATION>, CHANNELS_CONFIGURATION>(
+        src,
+        src_stride,
+        &mut histogram,
+        width,
+        height,
+        spans,
+        edge_mode.into(),
+        start_y,
+        end_y,
+        |histogram, x, y| {
+            let src_offset = y as usize * src_stride as usize + x as usize * CHANNELS_CONFIGURATION;
+            let dst_offset = y as usize * dst_stride as usize + x as usize * CHANNELS_CONFIGURATION;
+            for c in 0..CHANNELS_CONFIGURATION {
+                let center = src[src_offset + c];
+                let value = histogram.filter(c, center, threshold);
+                unsafe {
+                    unsafe_dst.write(dst_offset + c, T::from_f64(value));
+                }
+            }
+        },
+    );
+}
+
+fn surface_blur_dispatch<T: SurfaceValue>(
+    src: &[T],
+    src_stride: u32,
+    dst: &mut [T],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    threshold: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    if threshold <= 0f32 {
+        let row_length = width as usize * channels.get_channels();
+        for y in 0..height as usize {
+            let src_offset = y * src_stride as usize;
+            let dst_offset = y * dst_stride as usize;
+            dst[dst_offset..dst_offset + row_length]
+                .copy_from_slice(&src[src_offset..src_offset + row_length]);
+        }
+        return;
+    }
+    let unsafe_dst = UnsafeSlice::new(dst);
+    let spans = rank_window_spans(radius, RankWindow::Square);
+    let spans = &spans;
+    let window_side = (2 * radius + 1) as f32;
+    rank_filter_dispatch(
+        width,
+        height,
+        threading_policy,
+        window_side * window_side / 4f32 * type_work_per_pixel::<T>(),
+        |start_y, end_y| match channels {
+            FastBlurChannels::Channels3 => {
+                surface_blur_impl::<T, 3>(
+                    src,
+                    src_stride,
+                    &unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    spans,
+                    threshold,
+                    edge_mode,
+                    start_y,
+                    end_y,
+                );
+            }
+            FastBlurChannels::Channels4 => {
+                surface_blur_impl::<T, 4>(
+                    src,
+                    src_stride,
+                    &unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    spans,
+                    threshold,
+                    edge_mode,
+                    start_y,
+                    end_y,
+                );
+            }
+        },
+    );
+}
+
+/// Performs surface blur on the image.
+///
+/// Selective blur which averages only neighbors with intensity difference from the center
+/// less than `2.5 * threshold`, each neighbor is weighted by `1 - |difference| / (2.5 * threshold)`.
+/// Preserves edges and smooths flat areas, skin etc.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - Radius of kernel
+/// * `threshold` - Threshold in levels 0..255
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, *KernelClip* ignores them
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+pub fn surface_blur(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    threshold: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    surface_blur_dispatch(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        radius,
+        threshold,
+        channels,
+        threading_policy,
+        edge_mode,
+    );
+}
+
+/// Performs surface blur on the f32 image.
+///
+/// Selective blur which averages only neighbors with intensity difference from the center
+/// less than `2.5 * threshold`, each neighbor is weighted by `1 - |difference| / (2.5 * threshold)`.
+/// Preserves edges and smooths flat areas, skin etc.
+/// Image is expected to be in range 0..1, values are grouped into 1024 bins to find neighbors.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - Radius of kernel
+/// * `threshold` - Threshold in range 0..1
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, *KernelClip* ignores them
+///
+/// # Panics
+/// Panic is width/height/channel configuration do not match provided
+pub fn surface_blur_f32(
+    src: &[f32],
+    dst: &mut [f32],
+    width: u32,
+    height: u32,
+    radius: u32,
+    threshold: f32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: EdgeMode,
+) {
+    let stride = width * channels.get_channels() as u32;
+    surface_blur_dispatch(
+        src,
+        stride,
+        dst,
+        stride,
+        width,
+        height,
+        radius,
+        threshold,
+        channels,
+        threading_policy,
+        edge_mode,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edge_mode::border_position;
+
+    fn brute_force(
+        src: &[f32],
+        width: usize,
+        height: usize,
+        channels: usize,
+        radius: i64,
+        threshold: f32,
+        edge_mode: EdgeMode,
+    ) -> Vec<f32> {
+        let reach = 2.5f32 * threshold;
+        let mut dst = vec![0f32; src.len()];
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                for c in 0..channels {
+                    let center = src[(y as usize * width + x as usize) * channels + c];
+                    let mut numerator = 0f32;
+                    let mut denominator = 0f32;
+                    for dy in -radius..=radius {
+                        for dx in -radius..=radius {
+                            let (Some(py), Some(px)) = (
+                                border_position(y + dy, height, edge_mode),
+                                border_position(x + dx, width, edge_mode),
+                            ) else {
+                                continue;
+                            };
+                            let value = src[(py * width + px) * channels + c];
+                            let weight = (1f32 - (value - center).abs() / reach).max(0f32);
+                            numerator += weight * value;
+                            denominator += weight;
+                        }
+                    }
+                    dst[(y as usize * width + x as usize) * channels + c] = numerator / denominator;
+                }
+            }
+        }
+        dst
+    }
+
+    fn test_image(width: usize, height: usize, channels: usize) -> Vec<u8> {
+        (0..width * height * channels)
+            .map(|i| {
+                let x = (i / channels) % width;
+                let base = if x < width / 2 { 40 } else { 200 };
+                (base + (i * 7919 + 13) % 31) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn u8_matches_brute_force() {
+        let (width, height) = (23usize, 17usize);
+        for channels in [FastBlurChannels::Channels3, FastBlurChannels::Channels4] {
+            let cn = channels.get_channels();
+            let src = test_image(width, height, cn);
+            let src_f32 = src.iter().map(|&v| v as f32).collect::<Vec<_>>();
+            for edge_mode in [
+                EdgeMode::Clamp,
+                EdgeMode::KernelClip,
+                EdgeMode::Wrap,
+                EdgeMode::Reflect,
+                EdgeMode::Reflect101,
+            ] {
+                for (radius, threshold) in [(1u32, 10f32), (3, 25f32), (5, 4f32)] {
+                    let mut dst = vec![0u8; src.len()];
+                    surface_blur(
+                        &src,
+                        (width * cn) as u32,
+                        &mut dst,
+                        (width * cn) as u32,
+                        width as u32,
+                        height as u32,
+                        radius,
+                        threshold,
+                        channels,
+                        ThreadingPolicy::Adaptive,
+                        edge_mode,
+                    );
+                    let reference = brute_force(
+                        &src_f32,
+                        width,
+                        height,
+                        cn,
+                        radius as i64,
+                        threshold,
+                        edge_mode,
+                    );
+                    for (i, (&d, &r)) in dst.iter().zip(reference.iter()).enumerate() {
+                        assert!(
+                            (d as f32 - r).abs() <= 1f32,
+                            "{:?} r {} t {} at {}: {} vs {}",
+                            edge_mode,
+                            radius,
+                            threshold,
+                            i,
+                            d,
+                            r
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn f32_matches_brute_force_and_keeps_edge() {
+        let (width, height) = (20usize, 12usize);
+        let cn = 3;
+        let src = test_image(width, height, cn)
+            .iter()
+            .map(|&v| v as f32 / 255f32)
+            .collect::<Vec<_>>();
+        let mut dst = vec![0f32; src.len()];
+        surface_blur_f32(
+            &src,
+            &mut dst,
+            width as u32,
+            height as u32,
+            4,
+            0.05f32,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+            EdgeMode::Reflect101,
+        );
+        let reference = brute_force(&src, width, height, cn, 4, 0.05f32, EdgeMode::Reflect101);
+        for (i, (&d, &r)) in dst.iter().zip(reference.iter()).enumerate() {
+            assert!((d - r).abs() < 2e-3, "at {}: {} vs {}", i, d, r);
+            // Halves differ by far more than the threshold and never mix
+            let x = (i / cn) % width;
+            if x < width / 2 {
+                assert!(d < 0.3f32);
+            } else {
+                assert!(d > 0.7f32);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_threshold_copies_source() {
+        let src = test_image(9, 7, 4);
+        let mut dst = vec![0u8; src.len()];
+        surface_blur(
+            &src,
+            36,
+            &mut dst,
+            36,
+            9,
+            7,
+            3,
+            0f32,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Single,
+            EdgeMode::Clamp,
+        );
+        assert_eq!(src, dst);
+    }
+}
@@ -25,28 +25,353 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[cfg(feature = "std")]
+use crate::channels_configuration::FastBlurChannels;
+#[cfg(feature = "std")]
+use crate::stack_blur::stack_blur;
+#[cfg(not(feature = "rayon"))]
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// Work in pixels of light blur for each thread in adaptive mode, updated by *ThreadingPolicy::calibrate*
+static ADAPTIVE_WORK_PER_THREAD: AtomicUsize = AtomicUsize::new(256 * 256);
+/// Cached `available_parallelism`, zero until first requested
+#[cfg(feature = "std")]
+static AVAILABLE_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "std")]
+fn available_threads() -> usize {
+    let cached = AVAILABLE_THREADS.load(Ordering::Relaxed);
+    if cached != 0 {
+        return cached;
+    }
+    let threads = std::thread::available_parallelism()
+        .map(|x| x.get())
+        .unwrap_or(1);
+    AVAILABLE_THREADS.store(threads, Ordering::Relaxed);
+    threads
+}
+
+#[cfg(not(feature = "std"))]
+fn available_threads() -> usize {
+    1
+}
+
+/// Cost of the pixel of the storage type relative to u8 for *ThreadingPolicy::get_threads_count_for_work*,
+/// wider types move more memory and fill fewer SIMD lanes, f16 is also converted to f32 and back
+pub(crate) fn type_work_per_pixel<T>() -> f32 {
+    match core::any::type_name::<T>() {
+        "u8" => 1f32,
+        "f32" => 1.5f32,
+        "f16" | "half::f16" => 3f32,
+        _ => 2f32,
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
 /// Declares thread policy usage
 pub enum ThreadingPolicy {
     /// Will use only one thread, current is preferred
     Single,
-    /// Computes adaptive thread count between 1...12 for given image bounds
+    /// Computes adaptive thread count for given image bounds and algorithm cost,
+    /// up to available parallelism of the machine.
+    /// Without `rayon` feature runs on the calling thread
     Adaptive,
-    /// Spawn provided threads count
+    /// Spawn provided threads count, without `rayon` feature these are scoped std threads
     Fixed(usize),
+    /// Runs on the rayon pool of the caller without spawning threads,
+    /// the global pool or the one entered with `ThreadPool::install`.
+    /// To blur on own pool make the call inside `pool.install(|| ...)`,
+    /// calls made from tasks already running on the pool use it as well.
+    /// Without `rayon` feature runs on the calling thread
+    CurrentPool,
 }
 
 impl ThreadingPolicy {
+    /// Computes thread count for the image bounds of light algorithm as stack blur on u8 image
     pub fn get_threads_count(&self, width: u32, height: u32) -> usize {
+        self.get_threads_count_for_work(width, height, 1f32)
+    }
+
+    /// Computes thread count for the image bounds where each pixel costs `work_per_pixel`
+    /// relative to stack blur, so heavy algorithms get more threads on the same image
+    pub fn get_threads_count_for_work(
+        &self,
+        width: u32,
+        height: u32,
+        work_per_pixel: f32,
+    ) -> usize {
         match self {
             ThreadingPolicy::Single => 1,
             ThreadingPolicy::Adaptive => {
-                let thread_count =
-                    std::cmp::max(std::cmp::min(width * height / (256 * 256), 12), 1);
-                thread_count as usize
+                if cfg!(not(feature = "rayon")) {
+                    return 1;
+                }
+                let work = width as f64 * height as f64 * work_per_pixel.max(1f32) as f64;
+                let work_per_thread = ADAPTIVE_WORK_PER_THREAD.load(Ordering::Relaxed) as f64;
+                ((work / work_per_thread) as usize).clamp(1, available_threads())
             }
             ThreadingPolicy::Fixed(fixed) => *fixed,
+            #[cfg(feature = "rayon")]
+            ThreadingPolicy::CurrentPool => rayon::current_num_threads(),
+            #[cfg(not(feature = "rayon"))]
+            ThreadingPolicy::CurrentPool => 1,
         }
     }
+
+    /// Tunes *Adaptive* policy for this machine by a short self-benchmark,
+    /// thread is given enough work to be worth several times of its startup.
+    /// Intended to be called once at startup, returns work in pixels given to each thread
+    #[cfg(feature = "std")]
+    pub fn calibrate() -> usize {
+        const SIDE: u32 = 256;
+        const REPEATS: usize = 3;
+        let threads = core::cmp::min(available_threads(), 4);
+        let mut image: Vec<u8> = (0..SIDE as usize * SIDE as usize * 4)
+            .map(|i| (i * 37 % 251) as u8)
+            .collect();
+        let mut pixel_time = f64::MAX;
+        let mut startup_time = f64::MAX;
+        for _ in 0..REPEATS {
+            let start = Instant::now();
+            stack_blur(
+                &mut image,
+                SIDE * 4,
+                SIDE,
+                SIDE,
+                8,
+                FastBlurChannels::Channels4,
+                ThreadingPolicy::Single,
+            );
+            pixel_time = pixel_time.min(start.elapsed().as_secs_f64() / (SIDE * SIDE) as f64);
+
+            let start = Instant::now();
+            ThreadingPolicy::Fixed(threads)
+                .create_pool(threads)
+                .scope(|scope| {
+                    for _ in 0..threads {
+                        scope.spawn(|_| {});
+                    }
+                });
+            startup_time = startup_time.min(start.elapsed().as_secs_f64() / threads as f64);
+        }
+        let work_per_thread = (8f64 * startup_time / pixel_time.max(f64::MIN_POSITIVE)) as usize;
+        let work_per_thread = work_per_thread.clamp(64 * 64, 1024 * 1024);
+        ADAPTIVE_WORK_PER_THREAD.store(work_per_thread, Ordering::Relaxed);
+        work_per_thread
+    }
+
+    /// Returns pool for the work, single thread work is run inline without a pool,
+    /// falls back to the current rayon pool when threads cannot be spawned
+    #[cfg(feature = "rayon")]
+    pub(crate) fn create_pool(&self, thread_count: usize) -> BlurPool {
+        match self {
+            ThreadingPolicy::CurrentPool => BlurPool::Current,
+            _ if thread_count <= 1 => BlurPool::Inline,
+            _ => match rayon::ThreadPoolBuilder::new()
+                .num_threads(thread_count)
+                .build()
+            {
+                Ok(pool) => BlurPool::Owned(pool),
+                Err(_) => BlurPool::Current,
+            },
+        }
+    }
+
+    /// Returns scoped threads for *Fixed* policy, everything else runs on the calling thread
+    #[cfg(not(feature = "rayon"))]
+    pub(crate) fn create_pool(&self, thread_count: usize) -> BlurPool {
+        match self {
+            ThreadingPolicy::Fixed(_) if thread_count > 1 => BlurPool::Threads(thread_count),
+            _ => BlurPool::Inline,
+        }
+    }
+}
+
+/// Pool the blur work is scheduled on
+#[cfg(feature = "rayon")]
+pub(crate) enum BlurPool {
+    /// Pool spawned for the call
+    Owned(rayon::ThreadPool),
+    /// Pool of the caller
+    Current,
+    /// Calling thread, jobs are run as they are spawned and nothing is allocated
+    Inline,
+}
+
+/// Scope of *BlurPool::scope*, spawns on the rayon scope or runs the job right away
+#[cfg(feature = "rayon")]
+pub(crate) enum BlurScope<'a, 'scope> {
+    Rayon(&'a rayon::Scope<'scope>),
+    Inline,
+}
+
+#[cfg(feature = "rayon")]
+impl<'scope> BlurScope<'_, 'scope> {
+    pub(crate) fn spawn<BODY>(&self, body: BODY)
+    where
+        BODY: FnOnce(&()) + Send + 'scope,
+    {
+        match self {
+            BlurScope::Rayon(scope) => scope.spawn(move |_| body(&())),
+            BlurScope::Inline => body(&()),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl BlurPool {
+    pub(crate) fn scope<'scope, OP, R>(&self, op: OP) -> R
+    where
+        OP: for<'a> FnOnce(&BlurScope<'a, 'scope>) -> R + Send,
+        R: Send,
+    {
+        match self {
+            BlurPool::Owned(pool) => pool.scope(|scope| op(&BlurScope::Rayon(scope))),
+            BlurPool::Current => rayon::scope(|scope| op(&BlurScope::Rayon(scope))),
+            BlurPool::Inline => op(&BlurScope::Inline),
+        }
+    }
+}
+
+/// Threads the blur work is scheduled on
+#[cfg(not(feature = "rayon"))]
+pub(crate) enum BlurPool {
+    /// Up to count of scoped threads including the calling one
+    Threads(usize),
+    /// Calling thread, jobs are run as they are spawned and nothing is allocated
+    Inline,
+}
+
+#[cfg(not(feature = "rayon"))]
+type BlurJob<'scope> = Box<dyn FnOnce() + Send + 'scope>;
+
+/// Scope of *BlurPool::scope*, collects jobs to run when scope body returns or runs the job right away
+#[cfg(not(feature = "rayon"))]
+pub(crate) enum BlurScope<'scope> {
+    Deferred(core::cell::RefCell<Vec<BlurJob<'scope>>>),
+    Inline,
+}
+
+#[cfg(not(feature = "rayon"))]
+impl<'scope> BlurScope<'scope> {
+    pub(crate) fn spawn<BODY>(&self, body: BODY)
+    where
+        BODY: FnOnce(&()) + Send + 'scope,
+    {
+        match self {
+            BlurScope::Deferred(jobs) => jobs.borrow_mut().push(Box::new(move || body(&()))),
+            BlurScope::Inline => body(&()),
+        }
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+impl BlurPool {
+    pub(crate) fn scope<'scope, OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce(&BlurScope<'scope>) -> R,
+    {
+        match self {
+            BlurPool::Threads(thread_count) => {
+                let scope = BlurScope::Deferred(core::cell::RefCell::new(Vec::new()));
+                let result = op(&scope);
+                if let BlurScope::Deferred(jobs) = scope {
+                    let jobs = jobs.into_inner();
+                    if jobs.len() > 1 {
+                        run_threads(jobs, *thread_count);
+                    } else {
+                        jobs.into_iter().for_each(|job| job());
+                    }
+                }
+                result
+            }
+            BlurPool::Inline => op(&BlurScope::Inline),
+        }
+    }
+}
+/// Runs jobs on scoped threads, when thread cannot be spawned remaining jobs are done by the calling thread
+#[cfg(all(feature = "std", not(feature = "rayon")))]
+fn run_threads(jobs: Vec<BlurJob>, thread_count: usize) {
+    let thread_count = core::cmp::min(thread_count, jobs.len());
+    let queue = std::sync::Mutex::new(jobs);
+    let worker = || loop {
+        let job = queue.lock().unwrap().pop();
+        match job {
+            Some(job) => job(),
+            None => break,
+        }
+    };
+    std::thread::scope(|s| {
+        for _ in 1..thread_count {
+            if std::thread::Builder::new().spawn_scoped(s, worker).is_err() {
+                break;
+            }
+        }
+        worker();
+    });
+}
+
+/// Without std there are no threads, jobs are run on the calling thread
+#[cfg(not(feature = "std"))]
+fn run_threads(jobs: Vec<BlurJob>, _: usize) {
+    jobs.into_iter().for_each(|job| job());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adaptive_threads_are_capped_by_available_parallelism() {
+        let cap = if cfg!(feature = "rayon") {
+            available_threads()
+        } else {
+            1
+        };
+        for work_per_pixel in [1f32, 16f32, 1024f32] {
+            let threads =
+                ThreadingPolicy::Adaptive.get_threads_count_for_work(65536, 65536, work_per_pixel);
+            assert_eq!(threads, cap, "Work per pixel {}", work_per_pixel);
+        }
+        assert_eq!(
+            ThreadingPolicy::Adaptive.get_threads_count_for_work(1, 1, 1024f32),
+            1
+        );
+        assert_eq!(
+            ThreadingPolicy::Single.get_threads_count_for_work(65536, 65536, 1024f32),
+            1
+        );
+        assert_eq!(
+            ThreadingPolicy::Fixed(3).get_threads_count_for_work(1, 1, 1f32),
+            3
+        );
+    }
+
+    #[test]
+    fn type_work_per_pixel_is_relative_to_u8() {
+        assert_eq!(type_work_per_pixel::<u8>(), 1f32);
+        assert!(type_work_per_pixel::<f32>() > 1f32);
+        assert!(type_work_per_pixel::<u16>() > 1f32);
+        assert!(type_work_per_pixel::<half::f16>() > type_work_per_pixel::<f32>());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn calibrate_stores_work_within_range() {
+        let work_per_thread = ThreadingPolicy::calibrate();
+        assert!(
+            (64 * 64..=1024 * 1024).contains(&work_per_thread),
+            "Calibrated work per thread {}",
+            work_per_thread
+        );
+        assert_eq!(
+            ADAPTIVE_WORK_PER_THREAD.load(Ordering::Relaxed),
+            work_per_thread
+        );
+    }
 }
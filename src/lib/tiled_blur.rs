@@ -0,0 +1,316 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::threading_policy::type_work_per_pixel;
+use crate::{BlurFilter, BlurParams, EdgeMode, ThreadingPolicy};
+use alloc::vec;
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default)]
+/// Rectangle of the image in pixels passed to the callbacks of *blur_tiled*
+pub struct TileRegion {
+    /// Left column of the region
+    pub x: u32,
+    /// Top row of the region
+    pub y: u32,
+    /// Width of the region
+    pub width: u32,
+    /// Height of the region
+    pub height: u32,
+}
+
+/// Returns start and end of the tile with the halo along the axis of `length` pixels.
+///
+/// Tile touching the image edge reaches at least `2 * halo + 2` pixels from the edge,
+/// reflecting edge modes read pixels already blurred in place near the edge, those depend on the pixels one halo further
+fn tile_span(start: u32, size: u32, halo: u32, length: u32) -> (u32, u32) {
+    let edge_reach = 2 * halo + 2;
+    let mut from = start.saturating_sub(halo);
+    let mut to = (start + size).saturating_add(halo).min(length);
+    if from == 0 {
+        to = to.max(edge_reach.min(length));
+    }
+    if to == length {
+        from = from.min(length.saturating_sub(edge_reach));
+    }
+    (from, to)
+}
+
+/// Performs blur on the image tile by tile, image is never required to be fully in memory.
+///
+/// Each tile is extended by *BlurFilter::halo* pixels on each side clamped to the image,
+/// so the result is the same as of blurring the whole image at once.
+/// Tiles are distributed between threads, each tile is blurred by one thread.
+///
+/// # Arguments
+///
+/// * `filter` - Blur applied to the tiles, *BlurAlgorithm* or own *BlurFilter*
+/// * `params` - Parameters of the whole image, strides are ignored, see [BlurParams] for more info
+/// * `tile_size` - Width and height of the tile without the halo
+/// * `read` - Fills the buffer with the source pixels of the region, rows are `stride` elements long
+/// * `write` - Receives blurred pixels of the region, rows are `stride` elements long
+///
+/// # Panics
+/// Panic if tile size is zero or edge mode is *EdgeMode::Wrap*, tiles cannot wrap around the image,
+/// or edge mode is not supported by the algorithm
+pub fn blur_tiled<F, R, W>(filter: &F, params: &BlurParams, tile_size: u32, read: R, write: W)
+where
+    F: BlurFilter + Sync + ?Sized,
+    R: Fn(TileRegion, &mut [u8], u32) + Sync,
+    W: Fn(TileRegion, &[u8], u32) + Sync,
+{
+    if tile_size == 0 {
+        panic!("Tile size must be positive");
+    }
+    if params.edge_mode == EdgeMode::Wrap {
+        panic!("Wrap edge mode is not supported in tiled processing");
+    }
+    let channels = params.channels.get_channels();
+    let halo = filter.halo(params);
+    let tiles_x = params.width.div_ceil(tile_size) as usize;
+    let tiles_y = params.height.div_ceil(tile_size) as usize;
+    let tiles_count = tiles_x * tiles_y;
+    if tiles_count == 0 {
+        return;
+    }
+
+    let max_side = tile_size as usize + 2 * halo as usize + 2;
+    let buffer_size = core::cmp::min(max_side, params.width as usize)
+        * core::cmp::min(max_side, params.height as usize)
+        * channels;
+
+    let thread_count = core::cmp::min(
+        params.threading_policy.get_threads_count_for_work(
+            params.width,
+            params.height,
+            params.algorithm.work_per_pixel(params.radius) * type_work_per_pixel::<u8>(),
+        ),
+        tiles_count,
+    )
+    .max(1);
+    let pool = params.threading_policy.create_pool(thread_count);
+    let read = &read;
+    let write = &write;
+    pool.scope(|scope| {
+        for i in 0..thread_count {
+            scope.spawn(move |_| {
+                let mut src = vec![0u8; buffer_size];
+                let mut dst = vec![0u8; buffer_size];
+                for tile in (i..tiles_count).step_by(thread_count) {
+                    let x = (tile % tiles_x) as u32 * tile_size;
+                    let y = (tile / tiles_x) as u32 * tile_size;
+                    let core = TileRegion {
+                        x,
+                        y,
+                        width: core::cmp::min(tile_size, params.width - x),
+                        height: core::cmp::min(tile_size, params.height - y),
+                    };
+                    let (left, right) = tile_span(core.x, core.width, halo, params.width);
+                    let (top, bottom) = tile_span(core.y, core.height, halo, params.height);
+                    let region = TileRegion {
+                        x: left,
+                        y: top,
+                        width: right - left,
+                        height: bottom - top,
+                    };
+                    let stride = region.width * channels as u32;
+                    let length = stride as usize * region.height as usize;
+                    read(region, &mut src[..length], stride);
+
+                    let mut tile_params = *params;
+                    tile_params.width = region.width;
+                    tile_params.height = region.height;
+                    tile_params.src_stride = stride;
+                    tile_params.dst_stride = stride;
+                    tile_params.threading_policy = ThreadingPolicy::Single;
+                    filter.blur(&src[..length], &mut dst[..length], &tile_params);
+
+                    let offset = (core.y - top) as usize * stride as usize
+                        + (core.x - left) as usize * channels;
+                    write(core, &dst[offset..length], stride);
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blur, BlurAlgorithm, FastBlurChannels};
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+    fn test_image(len: usize) -> Vec<u8> {
+        (0..len).map(|i| ((i * 7919 + 17) % 251) as u8).collect()
+    }
+
+    /// Blurs the image with *blur_tiled* reading from `src` and collecting tiles into the returned image
+    fn blur_by_tiles<F: BlurFilter + Sync + ?Sized>(
+        filter: &F,
+        params: &BlurParams,
+        tile_size: u32,
+        src: &[u8],
+    ) -> Vec<u8> {
+        let row_length = params.width as usize * params.channels.get_channels();
+        let channels = params.channels.get_channels();
+        let dst: Vec<AtomicU8> = (0..src.len()).map(|_| AtomicU8::new(0)).collect();
+        blur_tiled(
+            filter,
+            params,
+            tile_size,
+            |region, buffer, stride| {
+                for (row, y) in buffer
+                    .chunks_exact_mut(stride as usize)
+                    .zip(region.y..region.y + region.height)
+                {
+                    let start = y as usize * row_length + region.x as usize * channels;
+                    row.copy_from_slice(&src[start..start + stride as usize]);
+                }
+            },
+            |region, buffer, stride| {
+                let length = region.width as usize * channels;
+                for (row, y) in buffer
+                    .chunks(stride as usize)
+                    .zip(region.y..region.y + region.height)
+                {
+                    let start = y as usize * row_length + region.x as usize * channels;
+                    for (dst, &value) in dst[start..start + length].iter().zip(row[..length].iter())
+                    {
+                        dst.store(value, Ordering::Relaxed);
+                    }
+                }
+            },
+        );
+        dst.into_iter().map(|x| x.into_inner()).collect()
+    }
+
+    #[test]
+    fn tiled_matches_whole_image() {
+        let (width, height) = (53u32, 41u32);
+        for algorithm in [
+            BlurAlgorithm::Stack,
+            BlurAlgorithm::FastGaussian,
+            BlurAlgorithm::FastGaussianNext,
+            BlurAlgorithm::FastGaussianSuperior,
+            BlurAlgorithm::Gaussian,
+            BlurAlgorithm::Box,
+            BlurAlgorithm::Tent,
+            BlurAlgorithm::GaussianBox,
+            BlurAlgorithm::Median,
+        ] {
+            for edge_mode in [EdgeMode::Clamp, EdgeMode::Reflect, EdgeMode::Reflect101] {
+                if !algorithm.supports_edge_mode(edge_mode) {
+                    continue;
+                }
+                for channels in [FastBlurChannels::Channels3, FastBlurChannels::Channels4] {
+                    let mut params = BlurParams::new(algorithm, width, height, 3, channels);
+                    params.edge_mode = edge_mode;
+                    params.threading_policy = ThreadingPolicy::Single;
+                    let src = test_image((width * height) as usize * channels.get_channels());
+                    let mut expected = vec![0u8; src.len()];
+                    blur(&src, &mut expected, &params);
+                    for (tile_size, threading_policy) in [
+                        (16, ThreadingPolicy::Single),
+                        (7, ThreadingPolicy::Fixed(3)),
+                        (64, ThreadingPolicy::Single),
+                    ] {
+                        params.threading_policy = threading_policy;
+                        let tiled = blur_by_tiles(&algorithm, &params, tile_size, &src);
+                        assert_eq!(
+                            tiled,
+                            expected,
+                            "{:?} {:?} {} channels tile {}",
+                            algorithm,
+                            edge_mode,
+                            channels.get_channels(),
+                            tile_size
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copies the source and counts pixels it was asked to write
+    struct CountingCopy {
+        pixels: AtomicUsize,
+    }
+
+    impl BlurFilter for CountingCopy {
+        fn blur(&self, src: &[u8], dst: &mut [u8], params: &BlurParams) {
+            dst.copy_from_slice(src);
+            self.pixels.fetch_add(
+                params.width as usize * params.height as usize,
+                Ordering::Relaxed,
+            );
+        }
+
+        fn halo(&self, _: &BlurParams) -> u32 {
+            0
+        }
+    }
+
+    #[test]
+    fn own_filter_tiles_cover_image_once() {
+        let (width, height) = (45u32, 30u32);
+        let params = BlurParams::new(
+            BlurAlgorithm::Box,
+            width,
+            height,
+            2,
+            FastBlurChannels::Channels3,
+        );
+        let filter = CountingCopy {
+            pixels: AtomicUsize::new(0),
+        };
+        let src = test_image((width * height * 3) as usize);
+        let tiled = blur_by_tiles(&filter, &params, 8, &src);
+        assert_eq!(tiled, src);
+        // Halo is zero, tiles touching the edges still reach two pixels in
+        assert!(filter.pixels.load(Ordering::Relaxed) >= (width * height) as usize);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrap_is_rejected() {
+        let mut params = BlurParams::new(
+            BlurAlgorithm::Gaussian,
+            16,
+            16,
+            2,
+            FastBlurChannels::Channels4,
+        );
+        params.edge_mode = EdgeMode::Wrap;
+        blur_tiled(
+            &BlurAlgorithm::Gaussian,
+            &params,
+            8,
+            |_, _, _| {},
+            |_, _, _| {},
+        );
+    }
+}
@@ -25,8 +25,8 @@
 // OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
 // OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::cell::UnsafeCell;
-use std::ops::Index;
+use core::cell::UnsafeCell;
+use core::ops::Index;
 
 #[derive(Copy, Clone)]
 pub struct UnsafeSlice<'a, T> {
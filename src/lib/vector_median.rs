@@ -0,0 +1,431 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::edge_mode::border_position;
+use crate::rank_filter::rank_filter_dispatch;
+use crate::threading_policy::type_work_per_pixel;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{RankEdgeMode, ThreadingPolicy};
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// Euclidean distance between the pixels
+#[inline(always)]
+fn pixel_distance<const CHANNELS_CONFIGURATION: usize>(a: &[f32; 4], b: &[f32; 4]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .take(CHANNELS_CONFIGURATION)
+        .map(|(&a, &b)| ((a - b) * (a - b)) as f64)
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Window of the vector median sliding along the row, columns are kept in the ring of slots
+/// and every pixel keeps sum of distances to all other pixels of the window,
+/// so moving the window computes distances only of the leaving and entering columns
+struct VectorMedianWindow<const CHANNELS_CONFIGURATION: usize> {
+    pixels: Vec<Vec<[f32; 4]>>,
+    sums: Vec<Vec<f64>>,
+}
+
+impl<const CHANNELS_CONFIGURATION: usize> VectorMedianWindow<CHANNELS_CONFIGURATION> {
+    fn new(side: usize) -> Self {
+        VectorMedianWindow {
+            pixels: (0..side).map(|_| Vec::with_capacity(side)).collect(),
+            sums: (0..side).map(|_| Vec::with_capacity(side)).collect(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.pixels.iter_mut().for_each(|column| column.clear());
+        self.sums.iter_mut().for_each(|column| column.clear());
+    }
+
+    fn add_column(&mut self, slot: usize, column: impl Iterator<Item = [f32; 4]>) {
+        let mut entering = core::mem::take(&mut self.pixels[slot]);
+        let mut entering_sums = core::mem::take(&mut self.sums[slot]);
+        entering.clear();
+        entering.extend(column);
+        entering_sums.clear();
+        entering_sums.resize(entering.len(), 0f64);
+        for (i, pixel) in entering.iter().enumerate() {
+            for (pixels, sums) in self.pixels.iter().zip(self.sums.iter_mut()) {
+                for (other, sum) in pixels.iter().zip(sums.iter_mut()) {
+                    let distance = pixel_distance::<CHANNELS_CONFIGURATION>(pixel, other);
+                    entering_sums[i] += distance;
+                    *sum += distance;
+                }
+            }
+            for (j, other) in entering.iter().enumerate().skip(i + 1) {
+                let distance = pixel_distance::<CHANNELS_CONFIGURATION>(pixel, other);
+                entering_sums[i] += distance;
+                entering_sums[j] += distance;
+            }
+        }
+        self.pixels[slot] = entering;
+        self.sums[slot] = entering_sums;
+    }
+
+    fn remove_column(&mut self, slot: usize) {
+        let leaving = core::mem::take(&mut self.pixels[slot]);
+        self.sums[slot].clear();
+        for (pixels, sums) in self.pixels.iter().zip(self.sums.iter_mut()) {
+            for (other, sum) in pixels.iter().zip(sums.iter_mut()) {
+                for pixel in leaving.iter() {
+                    *sum -= pixel_distance::<CHANNELS_CONFIGURATION>(pixel, other);
+                }
+            }
+        }
+        self.pixels[slot] = leaving;
+        self.pixels[slot].clear();
+    }
+
+    /// Pixel with the smallest sum of distances, slots are visited from `first_slot` in window order
+    fn median(&self, first_slot: usize) -> [f32; 4] {
+        let side = self.pixels.len();
+        let mut best = [0f32; 4];
+        let mut best_sum = f64::INFINITY;
+        for slot in (first_slot..side).chain(0..first_slot) {
+            for (pixel, &sum) in self.pixels[slot].iter().zip(self.sums[slot].iter()) {
+                if sum < best_sum {
+                    best_sum = sum;
+                    best = *pixel;
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Vector median filter
+///
+/// J. Astola, P. Haavisto, Y. Neuvo, Vector median filters, 1990.
+/// Output is the pixel from the window with the smallest sum of euclidean distances to all other pixels
+#[allow(clippy::too_many_arguments)]
+fn vector_median_impl<const CHANNELS_CONFIGURATION: usize>(
+    src: &[u8],
+    src_stride: u32,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    edge_mode: RankEdgeMode,
+    start_y: u32,
+    end_y: u32,
+) {
+    let radius = radius as i64;
+    let side = 2 * radius as usize + 1;
+    let mut window = VectorMedianWindow::<CHANNELS_CONFIGURATION>::new(side);
+    let mut rows: Vec<Option<usize>> = Vec::with_capacity(side);
+    let slot = |x: i64| x.rem_euclid(side as i64) as usize;
+    for y in start_y..end_y {
+        rows.clear();
+        rows.extend(
+            (-radius..=radius)
+                .map(|dy| border_position(y as i64 + dy, height as usize, edge_mode.border())),
+        );
+        let column_pixels = |x: i64| {
+            let column = border_position(x, width as usize, edge_mode.border());
+            rows.iter().filter_map(move |&row| match (row, column) {
+                (Some(row), Some(column)) => {
+                    let offset = row * src_stride as usize + column * CHANNELS_CONFIGURATION;
+                    let mut pixel = [0f32; 4];
+                    for (dst, &v) in pixel
+                        .iter_mut()
+                        .zip(src[offset..offset + CHANNELS_CONFIGURATION].iter())
+                    {
+                        *dst = v as f32;
+                    }
+                    Some(pixel)
+                }
+                _ => {
+                    if edge_mode == RankEdgeMode::Constant {
+                        Some([0f32; 4])
+                    } else {
+                        None
+                    }
+                }
+            })
+        };
+
+        window.clear();
+        for x in -radius..radius {
+            window.add_column(slot(x), column_pixels(x));
+        }
+
+        let y_dst_offset = y as usize * dst_stride as usize;
+        for x in 0..width as i64 {
+            if x > 0 {
+                window.remove_column(slot(x - radius - 1));
+            }
+            window.add_column(slot(x + radius), column_pixels(x + radius));
+
+            let median = window.median(slot(x - radius));
+            let px = x as usize * CHANNELS_CONFIGURATION;
+            for (c, &v) in median.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+                unsafe {
+                    unsafe_dst.write(y_dst_offset + px + c, v as u8);
+                }
+            }
+        }
+    }
+}
+
+/// Performs vector median filter on the image.
+///
+/// Instead of independent per channel medians picks an actual pixel from the window
+/// that has the smallest sum of euclidean distances to all other pixels in the window,
+/// so it removes impulse noise without producing false colors on the edges.
+/// O(R^3) complexity, intended for small radius.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - Radius of kernel
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, see *RankEdgeMode*, *KernelClip* ignores them and *Constant* treats them as zero
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+#[allow(clippy::too_many_arguments)]
+pub fn vector_median_blur(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: RankEdgeMode,
+) {
+    let unsafe_dst = UnsafeSlice::new(dst);
+    let side = (2 * radius + 1) as f32;
+    rank_filter_dispatch(
+        width,
+        height,
+        threading_policy,
+        side * side * side / 2f32 * type_work_per_pixel::<u8>(),
+        |start_y, end_y| match channels {
+            FastBlurChannels::Channels3 => {
+                vector_median_impl::<3>(
+                    src,
+                    src_stride,
+                    &unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius,
+                    edge_mode,
+                    start_y,
+                    end_y,
+                );
+            }
+            FastBlurChannels::Channels4 => {
+                vector_median_impl::<4>(
+                    src,
+                    src_stride,
+                    &unsafe_dst,
+                    dst_stride,
+                    width,
+                    height,
+                    radius,
+                    edge_mode,
+                    start_y,
+                    end_y,
+                );
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impulse_is_replaced_with_neighbor() {
+        let width = 12usize;
+        let height = 10usize;
+        let mut src = vec![0u8; width * height * 3];
+        for (i, pixel) in src.chunks_exact_mut(3).enumerate() {
+            pixel.copy_from_slice(&[40, 90 + (i % width) as u8, 200]);
+        }
+        src[(4 * width + 6) * 3..(4 * width + 7) * 3].copy_from_slice(&[255, 0, 0]);
+        let mut dst = vec![0u8; src.len()];
+        vector_median_blur(
+            &src,
+            width as u32 * 3,
+            &mut dst,
+            width as u32 * 3,
+            width as u32,
+            height as u32,
+            1,
+            FastBlurChannels::Channels3,
+            ThreadingPolicy::Single,
+            RankEdgeMode::Clamp,
+        );
+        for (y, row) in dst.chunks_exact(width * 3).enumerate() {
+            for (x, pixel) in row.chunks_exact(3).enumerate() {
+                assert_eq!(pixel[0], 40, "{} {}", x, y);
+                assert_eq!(pixel[2], 200, "{} {}", x, y);
+                assert!((pixel[1] as i32 - 90 - x as i32).abs() <= 1);
+            }
+        }
+    }
+
+    /// Sums of distances of every pixel in the window of (x, y) to all other pixels of the window
+    fn brute_force_window(
+        src: &[u8],
+        width: usize,
+        height: usize,
+        channels: usize,
+        radius: i64,
+        edge_mode: RankEdgeMode,
+        (x, y): (usize, usize),
+    ) -> Vec<(Vec<u8>, f64)> {
+        let mut window: Vec<Vec<u8>> = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let row = border_position(y as i64 + dy, height, edge_mode.border());
+                let column = border_position(x as i64 + dx, width, edge_mode.border());
+                match (row, column) {
+                    (Some(row), Some(column)) => {
+                        let offset = (row * width + column) * channels;
+                        window.push(src[offset..offset + channels].to_vec());
+                    }
+                    _ => {
+                        if edge_mode == RankEdgeMode::Constant {
+                            window.push(vec![0u8; channels]);
+                        }
+                    }
+                }
+            }
+        }
+        window
+            .iter()
+            .map(|pixel| {
+                let sum = window
+                    .iter()
+                    .map(|other| {
+                        pixel
+                            .iter()
+                            .zip(other.iter())
+                            .map(|(&a, &b)| (a as f64 - b as f64) * (a as f64 - b as f64))
+                            .sum::<f64>()
+                            .sqrt()
+                    })
+                    .sum::<f64>();
+                (pixel.clone(), sum)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn vector_median_matches_brute_force() {
+        let width = 13usize;
+        let height = 9usize;
+        for channels in [FastBlurChannels::Channels3, FastBlurChannels::Channels4] {
+            let cn = channels.get_channels();
+            // Few distinct values so windows often hold equal pixels and equal sums
+            let src: Vec<u8> = (0..width * height * cn)
+                .map(|i| ((i * 7 + i / 5 * 13) % 5 * 60) as u8)
+                .collect();
+            for edge_mode in [
+                RankEdgeMode::Clamp,
+                RankEdgeMode::KernelClip,
+                RankEdgeMode::Wrap,
+                RankEdgeMode::Reflect,
+                RankEdgeMode::Reflect101,
+                RankEdgeMode::Constant,
+            ] {
+                for (radius, threading_policy) in [
+                    (1u32, ThreadingPolicy::Single),
+                    (2, ThreadingPolicy::Fixed(3)),
+                    (3, ThreadingPolicy::Single),
+                ] {
+                    let mut dst = vec![0u8; src.len()];
+                    vector_median_blur(
+                        &src,
+                        (width * cn) as u32,
+                        &mut dst,
+                        (width * cn) as u32,
+                        width as u32,
+                        height as u32,
+                        radius,
+                        channels,
+                        threading_policy,
+                        edge_mode,
+                    );
+                    for y in 0..height {
+                        for x in 0..width {
+                            let window = brute_force_window(
+                                &src,
+                                width,
+                                height,
+                                cn,
+                                radius as i64,
+                                edge_mode,
+                                (x, y),
+                            );
+                            let min_sum = window
+                                .iter()
+                                .map(|(_, sum)| *sum)
+                                .fold(f64::INFINITY, f64::min);
+                            let offset = (y * width + x) * cn;
+                            let output = &dst[offset..offset + cn];
+                            let output_sum = window
+                                .iter()
+                                .filter(|(pixel, _)| pixel.as_slice() == output)
+                                .map(|(_, sum)| *sum)
+                                .fold(f64::INFINITY, f64::min);
+                            assert!(
+                                output_sum <= min_sum + 1e-6 * min_sum.max(1f64),
+                                "{:?} radius {} channels {} at {} {}: output sum {} min sum {}",
+                                edge_mode,
+                                radius,
+                                cn,
+                                x,
+                                y,
+                                output_sum,
+                                min_sum
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
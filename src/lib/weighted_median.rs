@@ -0,0 +1,396 @@
+// Copyright (c) Radzivon Bartoshyk. All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+// 1.  Redistributions of source code must retain the above copyright notice, this
+// list of conditions and the following disclaimer.
+//
+// 2.  Redistributions in binary form must reproduce the above copyright notice,
+// this list of conditions and the following disclaimer in the documentation
+// and/or other materials provided with the distribution.
+//
+// 3.  Neither the name of the copyright holder nor the names of its
+// contributors may be used to endorse or promote products derived from
+// this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+// FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+// DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+// CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+// OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+// OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::channels_configuration::FastBlurChannels;
+use crate::edge_mode::border_position;
+use crate::rank_filter::{histogram_rank, rank_filter_dispatch, segment_rank};
+use crate::threading_policy::type_work_per_pixel;
+use crate::unsafe_slice::UnsafeSlice;
+use crate::{RankEdgeMode, ThreadingPolicy};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// Weighted histogram of the kernel, coarse level has 16 bins and fine 256
+struct WeightedHistogram {
+    coarse: [[u32; 16]; 4],
+    fine: [[u32; 256]; 4],
+}
+
+impl WeightedHistogram {
+    /// Weight may be negative when pixel leaves the kernel or its weight decreases,
+    /// bins may wrap in between updates but they are never negative once the step is complete
+    #[inline(always)]
+    fn update<const CHANNELS_CONFIGURATION: usize>(&mut self, pixel: &[u8; 4], weight: i64) {
+        let weight = weight as u32;
+        for (c, &v) in pixel.iter().take(CHANNELS_CONFIGURATION).enumerate() {
+            let v = v as usize;
+            self.coarse[c][v >> 4] = self.coarse[c][v >> 4].wrapping_add(weight);
+            self.fine[c][v] = self.fine[c][v].wrapping_add(weight);
+        }
+    }
+}
+
+/// Weights of the kernel prepared for sliding along the row
+struct WeightedKernel {
+    /// Non zero weights as `(dy, dx, weight)`
+    initial: Vec<(i64, i64, i64)>,
+    /// Changes of the weights when kernel centered at `x` moves to `x + 1` as `(dy, dx, delta)`,
+    /// where `dx` is relative to `x`, pixels leaving and entering the kernel are included
+    steps: Vec<(i64, i64, i64)>,
+}
+
+impl WeightedKernel {
+    fn new(weights: &[u32], radius: i64) -> WeightedKernel {
+        let side = 2 * radius as usize + 1;
+        let weight = |dy: i64, dx: i64| -> i64 {
+            if dx < -radius || dx > radius {
+                return 0;
+            }
+            weights[(dy + radius) as usize * side + (dx + radius) as usize] as i64
+        };
+        let mut initial = Vec::new();
+        let mut steps = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if weight(dy, dx) != 0 {
+                    initial.push((dy, dx, weight(dy, dx)));
+                }
+            }
+            for dx in -radius..=radius + 1 {
+                let delta = weight(dy, dx - 1) - weight(dy, dx);
+                if delta != 0 {
+                    steps.push((dy, dx, delta));
+                }
+            }
+        }
+        WeightedKernel { initial, steps }
+    }
+}
+
+/// Source image of the weighted median with its edge handling
+struct WeightedMedianSource<'a, const CHANNELS_CONFIGURATION: usize> {
+    src: &'a [u8],
+    src_stride: u32,
+    width: u32,
+    height: u32,
+    edge_mode: RankEdgeMode,
+}
+
+impl<const CHANNELS_CONFIGURATION: usize> WeightedMedianSource<'_, CHANNELS_CONFIGURATION> {
+    /// Returns `None` if pixel is out of bounds and must be ignored
+    #[inline(always)]
+    fn pixel(&self, x: i64, y: i64) -> Option<[u8; 4]> {
+        let edge_mode = self.edge_mode.border();
+        match (
+            border_position(y, self.height as usize, edge_mode),
+            border_position(x, self.width as usize, edge_mode),
+        ) {
+            (Some(row), Some(column)) => {
+                let offset = row * self.src_stride as usize + column * CHANNELS_CONFIGURATION;
+                let mut pixel = [0u8; 4];
+                pixel[..CHANNELS_CONFIGURATION]
+                    .copy_from_slice(&self.src[offset..offset + CHANNELS_CONFIGURATION]);
+                Some(pixel)
+            }
+            _ if self.edge_mode == RankEdgeMode::Constant => Some([0u8; 4]),
+            _ => None,
+        }
+    }
+}
+
+/// Kernel histogram is built once per row and then slides to the right,
+/// each step updates only pixels whose weight changes
+fn weighted_median_impl<const CHANNELS_CONFIGURATION: usize>(
+    source: &WeightedMedianSource<CHANNELS_CONFIGURATION>,
+    unsafe_dst: &UnsafeSlice<u8>,
+    dst_stride: u32,
+    kernel: &WeightedKernel,
+    start_y: u32,
+    end_y: u32,
+) {
+    let mut histogram = WeightedHistogram {
+        coarse: [[0u32; 16]; 4],
+        fine: [[0u32; 256]; 4],
+    };
+    for y in start_y..end_y {
+        let y_src_offset = y as usize * source.src_stride as usize;
+        let y_dst_offset = y as usize * dst_stride as usize;
+        histogram.coarse = [[0u32; 16]; 4];
+        histogram.fine = [[0u32; 256]; 4];
+        let mut total = 0i64;
+        for &(dy, dx, weight) in kernel.initial.iter() {
+            if let Some(pixel) = source.pixel(dx, y as i64 + dy) {
+                histogram.update::<CHANNELS_CONFIGURATION>(&pixel, weight);
+                total += weight;
+            }
+        }
+        for x in 0..source.width {
+            let px = x as usize * CHANNELS_CONFIGURATION;
+            for c in 0..CHANNELS_CONFIGURATION {
+                let value = if total == 0 {
+                    source.src[y_src_offset + px + c]
+                } else {
+                    let (segment, rank) = histogram_rank(&histogram.coarse[c], (total / 2) as u32);
+                    let bin =
+                        segment_rank(&histogram.fine[c][segment * 16..segment * 16 + 16], rank);
+                    (segment * 16 + bin) as u8
+                };
+                unsafe {
+                    unsafe_dst.write(y_dst_offset + px + c, value);
+                }
+            }
+            if x + 1 == source.width {
+                break;
+            }
+            for &(dy, dx, delta) in kernel.steps.iter() {
+                if let Some(pixel) = source.pixel(x as i64 + dx, y as i64 + dy) {
+                    histogram.update::<CHANNELS_CONFIGURATION>(&pixel, delta);
+                    total += delta;
+                }
+            }
+        }
+    }
+}
+
+/// Performs weighted median blur on the image.
+///
+/// Each pixel in the window is counted as many times as its weight, so larger weights
+/// near the center keep more details than plain median does.
+/// Kernel histogram slides along the row and updates only pixels whose weight changes,
+/// so complexity is O(R) for weights constant along rows except a few positions and O(R^2) at most.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `weights` - Square kernel of weights `(2 * radius + 1) x (2 * radius + 1)` in row major order
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, see *RankEdgeMode*, *KernelClip* ignores them and *Constant* treats them as zero
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided or weights is not a square kernel with odd side
+#[allow(clippy::too_many_arguments)]
+pub fn weighted_median_blur(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    weights: &[u32],
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: RankEdgeMode,
+) {
+    let side = (weights.len() as f64).sqrt().round() as usize;
+    if side * side != weights.len() || side & 1 == 0 {
+        panic!("Weights must be a square kernel with odd side");
+    }
+    let kernel = WeightedKernel::new(weights, (side / 2) as i64);
+    let unsafe_dst = UnsafeSlice::new(dst);
+    rank_filter_dispatch(
+        width,
+        height,
+        threading_policy,
+        (kernel.steps.len() as f32 / 4f32).max(1f32) * type_work_per_pixel::<u8>(),
+        |start_y, end_y| match channels {
+            FastBlurChannels::Channels3 => {
+                let source = WeightedMedianSource::<3> {
+                    src,
+                    src_stride,
+                    width,
+                    height,
+                    edge_mode,
+                };
+                weighted_median_impl(&source, &unsafe_dst, dst_stride, &kernel, start_y, end_y);
+            }
+            FastBlurChannels::Channels4 => {
+                let source = WeightedMedianSource::<4> {
+                    src,
+                    src_stride,
+                    width,
+                    height,
+                    edge_mode,
+                };
+                weighted_median_impl(&source, &unsafe_dst, dst_stride, &kernel, start_y, end_y);
+            }
+        },
+    );
+}
+
+/// Performs center weighted median blur on the image.
+///
+/// Center pixel is counted `center_weight` times and any other pixel in the window once.
+/// O(R) complexity.
+///
+/// # Arguments
+///
+/// * `src_stride` - Lane length, default is width * channels_count if not aligned
+/// * `dst_stride` - Lane length, default is width * channels_count if not aligned
+/// * `width` - Width of the image
+/// * `height` - Height of the image
+/// * `radius` - Radius of kernel
+/// * `center_weight` - Weight of the center pixel, 1 is a plain median
+/// * `channels` - Count of channels in the image
+/// * `threading_policy` - Threading policy according to *ThreadingPolicy*
+/// * `edge_mode` - Rule to extend out of bounds pixels, see *RankEdgeMode*, *KernelClip* ignores them and *Constant* treats them as zero
+///
+/// # Panics
+/// Panic is stride/width/height/channel configuration do not match provided
+#[allow(clippy::too_many_arguments)]
+pub fn center_weighted_median_blur(
+    src: &[u8],
+    src_stride: u32,
+    dst: &mut [u8],
+    dst_stride: u32,
+    width: u32,
+    height: u32,
+    radius: u32,
+    center_weight: u32,
+    channels: FastBlurChannels,
+    threading_policy: ThreadingPolicy,
+    edge_mode: RankEdgeMode,
+) {
+    let side = 2 * radius as usize + 1;
+    let mut weights = vec![1u32; side * side];
+    weights[side * side / 2] = center_weight;
+    weighted_median_blur(
+        src,
+        src_stride,
+        dst,
+        dst_stride,
+        width,
+        height,
+        &weights,
+        channels,
+        threading_policy,
+        edge_mode,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{median_blur, RankWindow};
+
+    #[test]
+    fn weighted_median_matches_brute_force() {
+        let width = 17usize;
+        let height = 11usize;
+        let src: Vec<u8> = (0..width * height * 3)
+            .map(|i| ((i * 7919 + i / 7) % 256) as u8)
+            .collect();
+        let weights: Vec<u32> = (0..25u32).map(|i| (i * 7) % 4).collect();
+        for edge_mode in [
+            RankEdgeMode::Clamp,
+            RankEdgeMode::KernelClip,
+            RankEdgeMode::Wrap,
+            RankEdgeMode::Reflect,
+            RankEdgeMode::Reflect101,
+            RankEdgeMode::Constant,
+        ] {
+            let mut dst = vec![0u8; src.len()];
+            weighted_median_blur(
+                &src,
+                width as u32 * 3,
+                &mut dst,
+                width as u32 * 3,
+                width as u32,
+                height as u32,
+                &weights,
+                FastBlurChannels::Channels3,
+                ThreadingPolicy::Single,
+                edge_mode,
+            );
+            for y in 0..height as i64 {
+                for x in 0..width as i64 {
+                    for c in 0..3 {
+                        let mut values = Vec::new();
+                        for (i, &weight) in weights.iter().enumerate() {
+                            let row =
+                                border_position(y + i as i64 / 5 - 2, height, edge_mode.border());
+                            let column =
+                                border_position(x + i as i64 % 5 - 2, width, edge_mode.border());
+                            let value = match (row, column) {
+                                (Some(row), Some(column)) => src[(row * width + column) * 3 + c],
+                                _ if edge_mode == RankEdgeMode::Constant => 0,
+                                _ => continue,
+                            };
+                            values.resize(values.len() + weight as usize, value);
+                        }
+                        values.sort_unstable();
+                        let offset = (y as usize * width + x as usize) * 3 + c;
+                        assert_eq!(dst[offset], values[values.len() / 2], "{:?}", edge_mode);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unit_center_weight_is_plain_median() {
+        let width = 23usize;
+        let height = 9usize;
+        let src: Vec<u8> = (0..width * height * 4)
+            .map(|i| ((i * 104729) % 256) as u8)
+            .collect();
+        let mut weighted = vec![0u8; src.len()];
+        center_weighted_median_blur(
+            &src,
+            width as u32 * 4,
+            &mut weighted,
+            width as u32 * 4,
+            width as u32,
+            height as u32,
+            3,
+            1,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Single,
+            RankEdgeMode::Reflect101,
+        );
+        let mut median = vec![0u8; src.len()];
+        median_blur(
+            &src,
+            width as u32 * 4,
+            &mut median,
+            width as u32 * 4,
+            width as u32,
+            height as u32,
+            3,
+            RankWindow::Square,
+            FastBlurChannels::Channels4,
+            ThreadingPolicy::Single,
+            RankEdgeMode::Reflect101,
+        );
+        assert_eq!(weighted, median);
+    }
+}